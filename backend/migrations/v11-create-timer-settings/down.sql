@@ -0,0 +1,2 @@
+DROP TABLE timer_settings;
+ALTER TABLE timer DROP COLUMN `review`;
\ No newline at end of file
@@ -0,0 +1,8 @@
+-- Diesel orders migrations by comparing names as strings, the `v` prefix keeps new ones after 9-create-log
+CREATE TABLE timer_settings (
+  id INTEGER PRIMARY KEY,
+  `user_id` INTEGER NOT NULL UNIQUE,
+  `idle_limit` INTEGER NOT NULL,
+   FOREIGN KEY(`user_id`) REFERENCES users(id) ON DELETE CASCADE
+);
+ALTER TABLE timer ADD COLUMN `review` INTEGER NOT NULL DEFAULT 0;
@@ -0,0 +1,6 @@
+ALTER TABLE timer DROP COLUMN `kind`;
+ALTER TABLE timer DROP COLUMN `work`;
+ALTER TABLE timer DROP COLUMN `short_break`;
+ALTER TABLE timer DROP COLUMN `long_break`;
+ALTER TABLE timer DROP COLUMN `cycles`;
+ALTER TABLE timer DROP COLUMN `pomodoros`;
\ No newline at end of file
@@ -0,0 +1,6 @@
+ALTER TABLE timer ADD COLUMN `kind` VARCHAR(60) NOT NULL DEFAULT 'simple';
+ALTER TABLE timer ADD COLUMN `work` INTEGER NOT NULL DEFAULT 1500;
+ALTER TABLE timer ADD COLUMN `short_break` INTEGER NOT NULL DEFAULT 300;
+ALTER TABLE timer ADD COLUMN `long_break` INTEGER NOT NULL DEFAULT 900;
+ALTER TABLE timer ADD COLUMN `cycles` INTEGER NOT NULL DEFAULT 4;
+ALTER TABLE timer ADD COLUMN `pomodoros` INTEGER NOT NULL DEFAULT 0;
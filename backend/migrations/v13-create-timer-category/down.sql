@@ -0,0 +1,3 @@
+DROP TABLE timer_session;
+DROP TABLE timer_category;
+ALTER TABLE timer DROP COLUMN `category`;
\ No newline at end of file
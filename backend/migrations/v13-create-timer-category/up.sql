@@ -0,0 +1,20 @@
+CREATE TABLE timer_category (
+  id INTEGER PRIMARY KEY,
+  `name` VARCHAR(60) NOT NULL,
+  `color` VARCHAR(7) NOT NULL,
+  `user_id` INTEGER,
+  `team_id` INTEGER,
+   FOREIGN KEY(`user_id`) REFERENCES users(id) ON DELETE CASCADE,
+   FOREIGN KEY(`team_id`) REFERENCES team(id) ON DELETE CASCADE
+);
+CREATE TABLE timer_session (
+  id INTEGER PRIMARY KEY,
+  `timer_id` INTEGER NOT NULL,
+  `user_id` INTEGER NOT NULL,
+  `category` INTEGER,
+  `start` INTEGER NOT NULL,
+  `duration` INTEGER NOT NULL,
+   FOREIGN KEY(`timer_id`) REFERENCES timer(id) ON DELETE CASCADE,
+   FOREIGN KEY(`user_id`) REFERENCES users(id) ON DELETE CASCADE
+);
+ALTER TABLE timer ADD COLUMN `category` INTEGER;
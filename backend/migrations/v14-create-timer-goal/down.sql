@@ -0,0 +1 @@
+DROP TABLE timer_goal;
\ No newline at end of file
@@ -0,0 +1,8 @@
+CREATE TABLE timer_goal (
+  id INTEGER PRIMARY KEY,
+  `user_id` INTEGER NOT NULL,
+  `period` VARCHAR(10) NOT NULL,
+  `category` INTEGER,
+  `target` INTEGER NOT NULL,
+   FOREIGN KEY(`user_id`) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,6 @@
+DROP TABLE billing_rate;
+ALTER TABLE timer_session DROP COLUMN `billable`;
+ALTER TABLE timer DROP COLUMN `board_type`;
+ALTER TABLE timer DROP COLUMN `board_id`;
+ALTER TABLE timer DROP COLUMN `client`;
+ALTER TABLE timer DROP COLUMN `billable`;
@@ -0,0 +1,15 @@
+ALTER TABLE timer ADD COLUMN `billable` INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE timer ADD COLUMN `client` VARCHAR(60);
+ALTER TABLE timer ADD COLUMN `board_id` INTEGER;
+ALTER TABLE timer ADD COLUMN `board_type` VARCHAR(10);
+ALTER TABLE timer_session ADD COLUMN `billable` INTEGER NOT NULL DEFAULT 0;
+CREATE TABLE billing_rate (
+  id INTEGER PRIMARY KEY,
+  `scope` VARCHAR(20) NOT NULL,
+  `scope_id` INTEGER NOT NULL,
+  `rate` INTEGER NOT NULL,
+  `currency` VARCHAR(3) NOT NULL,
+  `rounding` INTEGER NOT NULL DEFAULT 0,
+  `rounding_mode` VARCHAR(10) NOT NULL DEFAULT 'up',
+  UNIQUE(`scope`, `scope_id`)
+);
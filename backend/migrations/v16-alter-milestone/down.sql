@@ -0,0 +1,4 @@
+ALTER TABLE milestone DROP COLUMN `status`;
+ALTER TABLE milestone DROP COLUMN `due_date`;
+ALTER TABLE milestone DROP COLUMN `start_date`;
+ALTER TABLE milestone DROP COLUMN `description`;
@@ -0,0 +1,4 @@
+ALTER TABLE milestone ADD COLUMN `description` TEXT;
+ALTER TABLE milestone ADD COLUMN `start_date` VARCHAR(10);
+ALTER TABLE milestone ADD COLUMN `due_date` VARCHAR(10);
+ALTER TABLE milestone ADD COLUMN `status` VARCHAR(10) NOT NULL DEFAULT 'open';
@@ -0,0 +1,2 @@
+ALTER TABLE log DROP COLUMN `milestone`;
+ALTER TABLE log DROP COLUMN `done`;
@@ -0,0 +1,2 @@
+ALTER TABLE log ADD COLUMN `done` INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE log ADD COLUMN `milestone` INTEGER;
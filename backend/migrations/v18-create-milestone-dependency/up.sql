@@ -0,0 +1,8 @@
+CREATE TABLE milestone_dependency (
+  id INTEGER PRIMARY KEY,
+  `milestone` INTEGER NOT NULL,
+  `predecessor` INTEGER NOT NULL,
+   UNIQUE(`milestone`, `predecessor`),
+   FOREIGN KEY(`milestone`) REFERENCES milestone(id) ON DELETE CASCADE,
+   FOREIGN KEY(`predecessor`) REFERENCES milestone(id) ON DELETE CASCADE
+);
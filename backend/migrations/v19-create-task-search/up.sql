@@ -0,0 +1,19 @@
+CREATE VIRTUAL TABLE task_search USING fts5(
+  `name`, `note`, `place`, `members`, `tags`, `subtasks`,
+  content='task', content_rowid='id'
+);
+INSERT INTO task_search(task_search) VALUES('rebuild');
+CREATE TRIGGER task_search_insert AFTER INSERT ON task BEGIN
+  INSERT INTO task_search(rowid, `name`, `note`, `place`, `members`, `tags`, `subtasks`)
+  VALUES (new.id, new.name, new.note, new.place, new.members, new.tags, new.subtasks);
+END;
+CREATE TRIGGER task_search_delete AFTER DELETE ON task BEGIN
+  INSERT INTO task_search(task_search, rowid, `name`, `note`, `place`, `members`, `tags`, `subtasks`)
+  VALUES ('delete', old.id, old.name, old.note, old.place, old.members, old.tags, old.subtasks);
+END;
+CREATE TRIGGER task_search_update AFTER UPDATE ON task BEGIN
+  INSERT INTO task_search(task_search, rowid, `name`, `note`, `place`, `members`, `tags`, `subtasks`)
+  VALUES ('delete', old.id, old.name, old.note, old.place, old.members, old.tags, old.subtasks);
+  INSERT INTO task_search(rowid, `name`, `note`, `place`, `members`, `tags`, `subtasks`)
+  VALUES (new.id, new.name, new.note, new.place, new.members, new.tags, new.subtasks);
+END;
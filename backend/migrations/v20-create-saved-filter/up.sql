@@ -0,0 +1,10 @@
+CREATE TABLE saved_filter (
+  id INTEGER PRIMARY KEY,
+  `name` VARCHAR(60) NOT NULL,
+  `user_id` INTEGER NOT NULL,
+  `board_type` VARCHAR(10) NOT NULL,
+  `board_id` INTEGER NOT NULL,
+  `shared` INTEGER NOT NULL DEFAULT 0,
+  `filter` TEXT NOT NULL,
+   FOREIGN KEY(`user_id`) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,3 @@
+ALTER TABLE task DROP COLUMN `version`;
+ALTER TABLE private_board DROP COLUMN `version`;
+ALTER TABLE team_board DROP COLUMN `version`;
@@ -0,0 +1,3 @@
+ALTER TABLE task ADD COLUMN `version` INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE private_board ADD COLUMN `version` INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE team_board ADD COLUMN `version` INTEGER NOT NULL DEFAULT 0;
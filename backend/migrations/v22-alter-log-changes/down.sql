@@ -0,0 +1 @@
+ALTER TABLE log DROP COLUMN `changes`;
@@ -0,0 +1 @@
+ALTER TABLE log ADD COLUMN `changes` TEXT;
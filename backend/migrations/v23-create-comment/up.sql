@@ -0,0 +1,14 @@
+CREATE TABLE comment (
+  id INTEGER PRIMARY KEY,
+  `task_id` INTEGER NOT NULL,
+  `parent` INTEGER,
+  `author` INTEGER NOT NULL,
+  `body` TEXT NOT NULL,
+  `mentions` TEXT NOT NULL DEFAULT '',
+  `created` VARCHAR(60) NOT NULL,
+  `edited` VARCHAR(60),
+  `deleted` INTEGER NOT NULL DEFAULT 0,
+   FOREIGN KEY(`task_id`) REFERENCES task(id) ON DELETE CASCADE,
+   FOREIGN KEY(`parent`) REFERENCES comment(id) ON DELETE CASCADE,
+   FOREIGN KEY(`author`) REFERENCES users(id) ON DELETE CASCADE
+);
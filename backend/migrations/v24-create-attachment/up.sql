@@ -0,0 +1,11 @@
+CREATE TABLE attachment (
+  id INTEGER PRIMARY KEY,
+  `task_id` INTEGER NOT NULL,
+  `name` VARCHAR(200) NOT NULL,
+  `content_type` VARCHAR(100) NOT NULL,
+  `size` INTEGER NOT NULL,
+  `uploader` INTEGER NOT NULL,
+  `created` VARCHAR(60) NOT NULL,
+   FOREIGN KEY(`task_id`) REFERENCES task(id) ON DELETE CASCADE,
+   FOREIGN KEY(`uploader`) REFERENCES users(id) ON DELETE CASCADE
+);
@@ -0,0 +1,8 @@
+CREATE TABLE task_dependency (
+  id INTEGER PRIMARY KEY,
+  `task` INTEGER NOT NULL,
+  `blocker` INTEGER NOT NULL,
+   UNIQUE(`task`, `blocker`),
+   FOREIGN KEY(`task`) REFERENCES task(id) ON DELETE CASCADE,
+   FOREIGN KEY(`blocker`) REFERENCES task(id) ON DELETE CASCADE
+);
@@ -0,0 +1,9 @@
+CREATE TABLE recurrence (
+  id INTEGER PRIMARY KEY,
+  `task` INTEGER NOT NULL UNIQUE,
+  `rule` VARCHAR(200) NOT NULL,
+  `list` INTEGER NOT NULL,
+  `count` INTEGER NOT NULL DEFAULT 1,
+   FOREIGN KEY(`task`) REFERENCES task(id) ON DELETE CASCADE,
+   FOREIGN KEY(`list`) REFERENCES list(id) ON DELETE CASCADE
+);
@@ -0,0 +1 @@
+ALTER TABLE timer DROP COLUMN `review_session`;
@@ -0,0 +1 @@
+ALTER TABLE timer ADD COLUMN `review_session` INTEGER DEFAULT NULL;
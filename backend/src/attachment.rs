@@ -0,0 +1,385 @@
+use crate::db::Connection;
+use crate::list::List;
+use crate::schema::{attachment, list, task};
+use crate::task::Task;
+use crate::utils::get_date;
+use diesel::prelude::*;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::fs::{NamedFile, TempFile};
+use rocket::http::{ContentType, Header, RawStr};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::{Build, Rocket};
+use std::collections::HashMap;
+use std::io;
+use std::path::PathBuf;
+
+/// `[default.attachments]` of Rocket.toml, the sizes are in bytes
+#[derive(Deserialize, Debug, Clone)]
+#[serde(default)]
+pub struct AttachmentConfig {
+    pub dir: PathBuf,
+    pub max_file_size: i64,
+    pub max_board_size: i64, // All the attachments on the tasks of a board
+}
+
+impl Default for AttachmentConfig {
+    fn default() -> Self {
+        AttachmentConfig {
+            dir: PathBuf::from("attachments"),
+            max_file_size: 10 << 20,
+            max_board_size: 200 << 20,
+        }
+    }
+}
+
+impl AttachmentConfig {
+    /// Files are stored under the id of their attachment
+    pub fn path(&self, id: i32) -> PathBuf {
+        self.dir.join(id.to_string())
+    }
+}
+
+/// Reads the configuration and creates the storage directory
+pub async fn storage(rocket: Rocket<Build>) -> Rocket<Build> {
+    let config = rocket
+        .figment()
+        .extract_inner::<AttachmentConfig>("attachments")
+        .unwrap_or_default();
+    rocket::tokio::fs::create_dir_all(&config.dir)
+        .await
+        .expect("attachment directory");
+    rocket.manage(config)
+}
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, Debug, Clone, PartialEq)]
+#[table_name = "attachment"]
+pub struct Attachment {
+    pub id: Option<i32>,
+    pub task_id: i32,
+    pub name: String,
+    pub content_type: String, // Sniffed from the content, not taken from the client
+    pub size: i64,
+    pub uploader: i32,
+    pub created: String,
+}
+
+/// Why an upload was refused
+#[derive(Debug, PartialEq)]
+pub enum UploadError {
+    TooLarge,
+    QuotaExceeded,
+    Io,
+}
+
+impl From<io::Error> for UploadError {
+    fn from(_: io::Error) -> Self {
+        UploadError::Io
+    }
+}
+
+impl From<diesel::result::Error> for UploadError {
+    fn from(_: diesel::result::Error) -> Self {
+        UploadError::Io
+    }
+}
+
+/// File of an attachment, images are shown by the browser and the rest is downloaded
+#[derive(Responder)]
+pub struct Download {
+    file: NamedFile,
+    content_type: ContentType,
+    disposition: Header<'static>,
+    nosniff: Header<'static>,
+}
+
+impl Download {
+    pub fn new(file: NamedFile, attachment: &Attachment) -> Download {
+        let content_type =
+            ContentType::parse_flexible(&attachment.content_type).unwrap_or(ContentType::Binary);
+        let name = RawStr::new(&attachment.name).percent_encode();
+        let kind = if attachment.is_image() {
+            "inline"
+        } else {
+            "attachment"
+        };
+        Download {
+            file,
+            content_type,
+            disposition: Header::new(
+                "Content-Disposition",
+                format!("{}; filename*=UTF-8''{}", kind, name),
+            ),
+            nosniff: Header::new("X-Content-Type-Options", "nosniff"),
+        }
+    }
+}
+
+/// Content type from the first bytes of the file
+pub fn sniff(head: &[u8]) -> ContentType {
+    const SIGNATURES: [(&[u8], &str, &str); 7] = [
+        (b"\x89PNG\r\n\x1a\n", "image", "png"),
+        (b"\xff\xd8\xff", "image", "jpeg"),
+        (b"GIF87a", "image", "gif"),
+        (b"GIF89a", "image", "gif"),
+        (b"%PDF-", "application", "pdf"),
+        (b"PK\x03\x04", "application", "zip"),
+        (b"\x1f\x8b", "application", "gzip"),
+    ];
+    if let Some((_, top, sub)) = SIGNATURES.iter().find(|s| head.starts_with(s.0)) {
+        return ContentType::new(*top, *sub);
+    }
+    if head.len() >= 12 && &head[..4] == b"RIFF" && &head[8..12] == b"WEBP" {
+        return ContentType::new("image", "webp");
+    }
+    // Text cut in the middle of a character is still text
+    let text = match std::str::from_utf8(head) {
+        Ok(_) => true,
+        Err(e) => e.error_len().is_none(),
+    };
+    if text && !head.contains(&0) {
+        ContentType::Plain
+    } else {
+        ContentType::Binary
+    }
+}
+
+/// Name to show for the uploaded file, without any path
+pub fn display_name(raw: &str) -> String {
+    let name = raw.rsplit(['/', '\\']).next().unwrap_or_default();
+    let name = name
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"')
+        .take(200)
+        .collect::<String>();
+    match name.trim() {
+        "" => "file".to_owned(),
+        name => name.to_owned(),
+    }
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+
+    /// Stores the file, refused when it's larger than the limit or the board's quota
+    pub async fn create(
+        task: &Task,
+        file: &mut TempFile<'_>,
+        uploader: i32,
+        config: &AttachmentConfig,
+        connection: &Connection,
+    ) -> Result<i32, UploadError> {
+        let size = file.len() as i64;
+        if size > config.max_file_size {
+            return Err(UploadError::TooLarge);
+        }
+        let list = List::get_by_id(task.list, connection).await?;
+        let content_type = match file.path() {
+            Some(path) => {
+                let mut head = vec![0; 512];
+                let mut opened = rocket::tokio::fs::File::open(path).await?;
+                let read = rocket::tokio::io::AsyncReadExt::read(&mut opened, &mut head).await?;
+                sniff(&head[..read])
+            }
+            None => ContentType::Binary,
+        };
+        let name = file
+            .raw_name()
+            .map(|name| display_name(name.dangerous_unsafe_unsanitized_raw().as_str()))
+            .unwrap_or_else(|| "file".to_owned());
+        let attachment = Attachment {
+            id: None,
+            task_id: task.id.unwrap_or_default(),
+            name,
+            content_type: content_type.to_string(),
+            size,
+            uploader,
+            created: get_date(),
+        };
+        let max_board_size = config.max_board_size;
+        // Immediate, so concurrent uploads can't both fit in what's left of the quota
+        let id = connection
+            .run(move |conn| {
+                conn.immediate_transaction(|| {
+                    let used = Self::board_size(list.board_type, list.board, conn)?;
+                    if used + size > max_board_size {
+                        return Err(UploadError::QuotaExceeded);
+                    }
+                    diesel::insert_into(attachment::table)
+                        .values(attachment)
+                        .execute(conn)?;
+                    Ok(diesel::select(crate::task::last_insert_rowid).get_result::<i32>(conn)?)
+                })
+            })
+            .await?;
+        if let Err(e) = file.move_copy_to(config.path(id)).await {
+            let _ = Self::delete_row(id, connection).await;
+            return Err(e.into());
+        }
+        Ok(id)
+    }
+
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<Attachment> {
+        connection
+            .run(move |conn| attachment::table.filter(attachment::id.eq(id)).first(conn))
+            .await
+    }
+
+    /// Bytes used by the attachments on the board's tasks
+    fn board_size(board_type: String, board_id: i32, conn: &SqliteConnection) -> QueryResult<i64> {
+        let lists = list::table
+            .filter(list::board_type.eq(board_type))
+            .filter(list::board.eq(board_id))
+            .select(list::id)
+            .load::<Option<i32>>(conn)?;
+        let tasks = task::table
+            .filter(task::list.eq_any(lists.into_iter().flatten()))
+            .select(task::id)
+            .load::<Option<i32>>(conn)?;
+        let sizes = attachment::table
+            .filter(attachment::task_id.eq_any(tasks.into_iter().flatten()))
+            .select(attachment::size)
+            .load::<i64>(conn)?;
+        Ok(sizes.into_iter().sum())
+    }
+
+    /// Attachments of each of the tasks, oldest first
+    pub async fn get_tasks(
+        ids: Vec<i32>,
+        connection: &Connection,
+    ) -> QueryResult<HashMap<i32, Vec<Attachment>>> {
+        let attachments = connection
+            .run(move |conn| {
+                attachment::table
+                    .filter(attachment::task_id.eq_any(ids))
+                    .order(attachment::id)
+                    .load::<Attachment>(conn)
+            })
+            .await?;
+        let mut result = HashMap::<i32, Vec<Attachment>>::new();
+        for attachment in attachments {
+            result
+                .entry(attachment.task_id)
+                .or_default()
+                .push(attachment);
+        }
+        Ok(result)
+    }
+
+    async fn delete_row(id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(attachment::table.filter(attachment::id.eq(id))).execute(conn)
+            })
+            .await
+    }
+
+    /// Removes the attachment with its file
+    pub async fn delete(
+        id: i32,
+        config: &AttachmentConfig,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let cnt = Self::delete_row(id, connection).await?;
+        if cnt > 0 {
+            let _ = rocket::tokio::fs::remove_file(config.path(id)).await;
+        }
+        Ok(cnt)
+    }
+
+    /// Removes the attachments of the tasks on the lists, before the lists are deleted
+    pub async fn delete_list_attachments(
+        lists: Vec<i32>,
+        config: &AttachmentConfig,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let ids = connection
+            .run(move |conn| {
+                let tasks = task::table
+                    .filter(task::list.eq_any(lists))
+                    .select(task::id)
+                    .load::<Option<i32>>(conn)?;
+                attachment::table
+                    .filter(attachment::task_id.eq_any(tasks.into_iter().flatten()))
+                    .select(attachment::id)
+                    .load::<Option<i32>>(conn)
+            })
+            .await?;
+        let mut cnt = 0;
+        for id in ids.into_iter().flatten() {
+            cnt += Self::delete(id, config, connection).await?;
+        }
+        Ok(cnt)
+    }
+
+    /// Removes the attachments of all the board's tasks, before the board is deleted
+    pub async fn delete_board_attachments(
+        board_type: String,
+        board_id: i32,
+        config: &AttachmentConfig,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let lists = connection
+            .run(move |conn| {
+                list::table
+                    .filter(list::board_type.eq(board_type))
+                    .filter(list::board.eq(board_id))
+                    .select(list::id)
+                    .load::<Option<i32>>(conn)
+            })
+            .await?;
+        let lists = lists.into_iter().flatten().collect();
+        Self::delete_list_attachments(lists, config, connection).await
+    }
+
+    pub async fn delete_task_attachments(
+        task_id: i32,
+        config: &AttachmentConfig,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let ids = connection
+            .run(move |conn| {
+                attachment::table
+                    .filter(attachment::task_id.eq(task_id))
+                    .select(attachment::id)
+                    .load::<Option<i32>>(conn)
+            })
+            .await?;
+        let mut cnt = 0;
+        for id in ids.into_iter().flatten() {
+            cnt += Self::delete(id, config, connection).await?;
+        }
+        Ok(cnt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        assert_eq!(sniff(b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR"), ContentType::PNG);
+        assert_eq!(sniff(b"\xff\xd8\xff\xe0\0\x10JFIF"), ContentType::JPEG);
+        assert_eq!(
+            sniff(b"RIFF\0\0\0\0WEBPVP8 "),
+            ContentType::new("image", "webp")
+        );
+        assert_eq!(sniff(b"%PDF-1.7"), ContentType::PDF);
+        assert_eq!(sniff("zażółć gęślą jaźń".as_bytes()), ContentType::Plain);
+        // Cut in the middle of `ż`
+        assert_eq!(sniff(&"zaż".as_bytes()[..3]), ContentType::Plain);
+        assert_eq!(sniff(b"\0\x01\x02"), ContentType::Binary);
+        assert_eq!(sniff(b""), ContentType::Plain);
+    }
+
+    #[test]
+    fn test_display_name() {
+        assert_eq!(display_name("C:\\Users\\ala\\spec v2.pdf"), "spec v2.pdf");
+        assert_eq!(display_name("../../etc/passwd"), "passwd");
+        assert_eq!(display_name("a\"b\n.png"), "ab.png");
+        assert_eq!(display_name("dir/"), "file");
+    }
+}
@@ -1,3 +1,4 @@
+use crate::utils::get_time;
 use rocket::outcome::Outcome;
 use rocket::request::{self, FromRequest, Request};
 
@@ -10,19 +11,53 @@ use self::jwt::{Header, Registered, Token};
 
 pub struct ApiKey(pub String);
 
-pub fn read_token(key: &str) -> Result<String, String> {
+/// Audience of the tickets which authorize urls, like the board's events and attachments.
+/// Urls end up in logs and the browser's history, so tickets expire and aren't tokens.
+const TICKET: &str = "ticket";
+
+/// Seconds a ticket is valid for, streams opened with it stay open
+const TICKET_LIFETIME: u64 = 15 * 60;
+
+fn read_claims(key: &str) -> Result<Registered, String> {
     let token =
         Token::<Header, Registered>::parse(key).map_err(|_| "Unable to parse key".to_string())?;
     if token.verify(b"secret_key", Sha256::new()) {
-        token
-            .claims
-            .sub
-            .ok_or_else(|| "Claims not valid".to_string())
+        Ok(token.claims)
     } else {
         Err("Token not valid".to_string())
     }
 }
 
+pub fn read_token(key: &str) -> Result<String, String> {
+    let claims = read_claims(key)?;
+    if claims.aud.as_deref() == Some(TICKET) {
+        return Err("Tickets only authorize urls".to_string());
+    }
+    claims.sub.ok_or_else(|| "Claims not valid".to_string())
+}
+
+pub fn create_ticket(username: String) -> Result<String, String> {
+    let claims = Registered {
+        sub: Some(username),
+        aud: Some(TICKET.to_owned()),
+        exp: Some(get_time() as u64 + TICKET_LIFETIME),
+        ..Default::default()
+    };
+    Token::new(Header::default(), claims)
+        .signed(b"secret_key", Sha256::new())
+        .map_err(|_| "Unable to sign the ticket".to_string())
+}
+
+pub fn read_ticket(ticket: &str) -> Result<String, String> {
+    let claims = read_claims(ticket)?;
+    match (claims.aud.as_deref(), claims.exp) {
+        (Some(TICKET), Some(exp)) if exp > get_time() as u64 => {
+            claims.sub.ok_or_else(|| "Claims not valid".to_string())
+        }
+        _ => Err("Ticket not valid".to_string()),
+    }
+}
+
 #[rocket::async_trait]
 impl<'a> FromRequest<'a> for ApiKey {
     type Error = ();
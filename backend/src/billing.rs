@@ -0,0 +1,504 @@
+use crate::db::Connection;
+use crate::schema::{
+    billing_rate, private_board, team_board, team_user, timer, timer_category, timer_session,
+};
+use crate::timer::{Timer, TimerSession};
+use chrono::{TimeZone, Utc};
+use diesel::prelude::*;
+use diesel::AsChangeset;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+const NO_CLIENT: &str = "No client";
+const PDF_LINES_PER_PAGE: usize = 50;
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug, Clone)]
+#[table_name = "billing_rate"]
+pub struct BillingRate {
+    pub id: Option<i32>,
+    pub scope: String, // "user", "team", "private_board" or "team_board"
+    pub scope_id: i32,
+    pub rate: i32, // Cents per hour
+    pub currency: String,
+    pub rounding: i32, // Every session is rounded to this many minutes, 0 bills exact time
+    pub rounding_mode: String, // "up", "down" or "nearest"
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillingLine {
+    pub session: i32,
+    pub start: i32,
+    pub timer: String,
+    pub client: String,
+    pub duration: i32,
+    pub billed: i32,
+    pub rate: i32,
+    pub currency: String,
+    pub amount: i64, // Cents
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientTotal {
+    pub client: String,
+    pub currency: String,
+    pub duration: i32,
+    pub billed: i32,
+    pub amount: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BillingSummary {
+    pub lines: Vec<BillingLine>,
+    pub clients: Vec<ClientTotal>,
+}
+
+pub fn round_duration(duration: i32, rounding: i32, mode: &str) -> i32 {
+    let (duration, step) = (duration as i64, rounding as i64 * 60);
+    if step <= 0 {
+        return duration as i32;
+    }
+    let rounded = match mode {
+        "down" => duration / step * step,
+        "nearest" => (duration + step / 2) / step * step,
+        _ => (duration + step - 1) / step * step,
+    };
+    rounded.min(i32::MAX as i64) as i32
+}
+
+/// Price of `seconds` at `rate` cents per hour, rounded to the nearest cent
+pub fn amount(seconds: i32, rate: i32) -> i64 {
+    (seconds as i64 * rate as i64 + 1800) / 3600
+}
+
+/// The most specific rate for the timer: its board, then its team, then its owner
+fn find_rate<'a>(
+    rates: &'a HashMap<(String, i32), BillingRate>,
+    timer: &Timer,
+    team: Option<i32>,
+) -> Option<&'a BillingRate> {
+    let board = match (timer.board_type.as_deref(), timer.board_id) {
+        (Some("private"), Some(id)) => rates.get(&("private_board".to_owned(), id)),
+        (Some("team"), Some(id)) => rates.get(&("team_board".to_owned(), id)),
+        _ => None,
+    };
+    board
+        .or_else(|| team.and_then(|team| rates.get(&("team".to_owned(), team))))
+        .or_else(|| rates.get(&("user".to_owned(), timer.user_id)))
+}
+
+fn totals(lines: &[BillingLine]) -> Vec<ClientTotal> {
+    let mut clients: Vec<ClientTotal> = Vec::new();
+    for line in lines {
+        let found = clients
+            .iter_mut()
+            .find(|c| c.client == line.client && c.currency == line.currency);
+        match found {
+            Some(total) => {
+                total.duration += line.duration;
+                total.billed += line.billed;
+                total.amount += line.amount;
+            }
+            None => clients.push(ClientTotal {
+                client: line.client.clone(),
+                currency: line.currency.clone(),
+                duration: line.duration,
+                billed: line.billed,
+                amount: line.amount,
+            }),
+        }
+    }
+    clients.sort_by(|a, b| a.client.cmp(&b.client));
+    clients
+}
+
+pub fn format_date(time: i32) -> String {
+    match Utc.timestamp_opt(time as i64, 0).single() {
+        Some(date) => date.format("%Y-%m-%d").to_string(),
+        None => String::new(),
+    }
+}
+
+pub fn format_amount(cents: i64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+fn format_hours(seconds: i32) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_owned()
+    }
+}
+
+pub fn to_csv(summary: &BillingSummary) -> String {
+    let mut csv = "date,timer,client,hours,billed_hours,rate,currency,amount\n".to_owned();
+    for line in summary.lines.iter() {
+        let fields = [
+            format_date(line.start),
+            csv_field(&line.timer),
+            csv_field(&line.client),
+            format_hours(line.duration),
+            format_hours(line.billed),
+            format_amount(line.rate as i64),
+            line.currency.clone(),
+            format_amount(line.amount),
+        ];
+        csv.push_str(&fields.join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+/// Text lines of the invoice, grouped by client
+pub fn invoice_lines(summary: &BillingSummary, title: &str) -> Vec<String> {
+    let mut lines = vec![title.to_owned(), String::new()];
+    for client in summary.clients.iter() {
+        lines.push(format!("{} ({})", client.client, client.currency));
+        for line in summary
+            .lines
+            .iter()
+            .filter(|l| l.client == client.client && l.currency == client.currency)
+        {
+            lines.push(format!(
+                "    {}  {}  {} h x {} = {}",
+                format_date(line.start),
+                line.timer,
+                format_hours(line.billed),
+                format_amount(line.rate as i64),
+                format_amount(line.amount)
+            ));
+        }
+        lines.push(format!(
+            "    Total: {} h, {} {}",
+            format_hours(client.billed),
+            format_amount(client.amount),
+            client.currency
+        ));
+        lines.push(String::new());
+    }
+    lines
+}
+
+fn pdf_escape(line: &str) -> String {
+    let mut escaped = String::new();
+    for c in line.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            ' '..='~' => escaped.push(c),
+            _ => escaped.push('?'), // The built-in font has no encoding for the rest
+        }
+    }
+    escaped
+}
+
+/// Minimal PDF with the lines printed in Helvetica on A4 pages
+pub fn to_pdf(lines: &[String]) -> Vec<u8> {
+    let mut objects = vec![
+        "<< /Type /Catalog /Pages 2 0 R >>".to_owned(),
+        String::new(), // Page tree, filled in once the pages are known
+        "<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica >>".to_owned(),
+    ];
+    let mut pages = Vec::new();
+    let mut chunks: Vec<&[String]> = lines.chunks(PDF_LINES_PER_PAGE).collect();
+    if chunks.is_empty() {
+        chunks.push(&[]);
+    }
+    for chunk in chunks {
+        let mut stream = "BT /F1 10 Tf 14 TL 50 800 Td\n".to_owned();
+        for line in chunk {
+            stream.push_str(&format!("({}) '\n", pdf_escape(line)));
+        }
+        stream.push_str("ET");
+        objects.push(format!(
+            "<< /Length {} >>\nstream\n{}\nendstream",
+            stream.len(),
+            stream
+        ));
+        objects.push(format!(
+            "<< /Type /Page /Parent 2 0 R /MediaBox [0 0 595 842] \
+             /Resources << /Font << /F1 3 0 R >> >> /Contents {} 0 R >>",
+            objects.len()
+        ));
+        pages.push(format!("{} 0 R", objects.len()));
+    }
+    objects[1] = format!(
+        "<< /Type /Pages /Kids [{}] /Count {} >>",
+        pages.join(" "),
+        pages.len()
+    );
+
+    let mut pdf = b"%PDF-1.4\n".to_vec();
+    let mut offsets = Vec::new();
+    for (i, object) in objects.iter().enumerate() {
+        offsets.push(pdf.len());
+        pdf.extend(format!("{} 0 obj\n{}\nendobj\n", i + 1, object).as_bytes());
+    }
+    let xref = pdf.len();
+    pdf.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).as_bytes());
+    for offset in offsets {
+        pdf.extend(format!("{:010} 00000 n \n", offset).as_bytes());
+    }
+    pdf.extend(
+        format!(
+            "trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{}\n%%EOF\n",
+            objects.len() + 1,
+            xref
+        )
+        .as_bytes(),
+    );
+    pdf
+}
+
+impl BillingRate {
+    /// Replaces the previous rate of the same scope
+    pub async fn set(rate: BillingRate, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(|conn| {
+                diesel::replace_into(billing_rate::table)
+                    .values(rate)
+                    .execute(conn)
+            })
+            .await
+    }
+
+    /// Rates of the user, of the user's teams and of the boards the user can access
+    pub async fn get(user_id: i32, connection: &Connection) -> QueryResult<Vec<BillingRate>> {
+        connection
+            .run(move |conn| {
+                let teams = team_user::table
+                    .filter(team_user::user.eq(user_id))
+                    .select(team_user::team)
+                    .load::<i32>(conn)?;
+                let private_boards = private_board::table
+                    .filter(private_board::owner.eq(user_id))
+                    .select(private_board::id)
+                    .load::<Option<i32>>(conn)?;
+                let team_boards = team_board::table
+                    .filter(team_board::owner.eq_any(teams.clone()))
+                    .select(team_board::id)
+                    .load::<Option<i32>>(conn)?;
+                let scoped = |scope: &str, ids: Vec<i32>| {
+                    billing_rate::scope
+                        .eq(scope.to_owned())
+                        .and(billing_rate::scope_id.eq_any(ids))
+                };
+                billing_rate::table
+                    .filter(
+                        scoped("user", vec![user_id])
+                            .or(scoped("team", teams))
+                            .or(scoped(
+                                "private_board",
+                                private_boards.into_iter().flatten().collect(),
+                            ))
+                            .or(scoped(
+                                "team_board",
+                                team_boards.into_iter().flatten().collect(),
+                            )),
+                    )
+                    .order((billing_rate::scope, billing_rate::scope_id))
+                    .load::<BillingRate>(conn)
+            })
+            .await
+    }
+
+    pub async fn is_visible(id: i32, user_id: i32, connection: &Connection) -> bool {
+        match Self::get(user_id, connection).await {
+            Ok(rates) => rates.into_iter().any(|rate| rate.id == Some(id)),
+            Err(_) => false,
+        }
+    }
+
+    pub async fn delete(id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(billing_rate::table.filter(billing_rate::id.eq(id))).execute(conn)
+            })
+            .await
+    }
+
+    /// Billable sessions of the user started between `from` and `to`, priced with the rate
+    /// that applies to their timer. Sessions without any rate are listed with a zero rate.
+    pub async fn summary(
+        user_id: i32,
+        from: Option<i32>,
+        to: Option<i32>,
+        client: Option<String>,
+        connection: &Connection,
+    ) -> QueryResult<BillingSummary> {
+        let mut lines = connection
+            .run(move |conn| {
+                let mut query = timer_session::table
+                    .filter(timer_session::user_id.eq(user_id))
+                    .filter(timer_session::billable.eq(1))
+                    .into_boxed();
+                if let Some(from) = from {
+                    query = query.filter(timer_session::start.ge(from));
+                }
+                if let Some(to) = to {
+                    query = query.filter(timer_session::start.lt(to));
+                }
+                let sessions = query
+                    .order(timer_session::start)
+                    .load::<TimerSession>(conn)?;
+                let timers = timer::table
+                    .filter(timer::user_id.eq(user_id))
+                    .load::<Timer>(conn)?
+                    .into_iter()
+                    .map(|timer| (timer.id.unwrap(), timer))
+                    .collect::<HashMap<_, _>>();
+                let rates = billing_rate::table
+                    .load::<BillingRate>(conn)?
+                    .into_iter()
+                    .map(|rate| ((rate.scope.clone(), rate.scope_id), rate))
+                    .collect::<HashMap<_, _>>();
+                let board_teams = team_board::table
+                    .select((team_board::id, team_board::owner))
+                    .load::<(Option<i32>, i32)>(conn)?
+                    .into_iter()
+                    .collect::<HashMap<_, _>>();
+                let category_teams = timer_category::table
+                    .select((timer_category::id, timer_category::team_id))
+                    .load::<(Option<i32>, Option<i32>)>(conn)?
+                    .into_iter()
+                    .collect::<HashMap<_, _>>();
+
+                let mut lines = Vec::new();
+                for session in sessions {
+                    let timer = match timers.get(&session.timer_id) {
+                        Some(timer) => timer,
+                        None => continue,
+                    };
+                    let name = timer.client.clone().unwrap_or_else(|| NO_CLIENT.to_owned());
+                    if client.is_some() && client.as_ref() != Some(&name) {
+                        continue;
+                    }
+                    let team = match timer.board_type.as_deref() {
+                        Some("team") => board_teams.get(&timer.board_id).copied(),
+                        _ => category_teams.get(&session.category).copied().flatten(),
+                    };
+                    let (rate, currency, billed) = match find_rate(&rates, timer, team) {
+                        Some(rate) => (
+                            rate.rate,
+                            rate.currency.clone(),
+                            round_duration(session.duration, rate.rounding, &rate.rounding_mode),
+                        ),
+                        None => (0, String::new(), session.duration),
+                    };
+                    lines.push(BillingLine {
+                        session: session.id.unwrap(),
+                        start: session.start,
+                        timer: timer.name.clone(),
+                        client: name,
+                        duration: session.duration,
+                        billed,
+                        rate,
+                        currency,
+                        amount: amount(billed, rate),
+                    });
+                }
+                Ok::<_, diesel::result::Error>(lines)
+            })
+            .await?;
+        lines.sort_by(|a, b| a.client.cmp(&b.client).then(a.start.cmp(&b.start)));
+        Ok(BillingSummary {
+            clients: totals(&lines),
+            lines,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(client: &str, billed: i32, rate: i32) -> BillingLine {
+        BillingLine {
+            session: 1,
+            start: 1654473600,
+            timer: "Ala, \"ma\" kota".to_string(),
+            client: client.to_string(),
+            duration: billed,
+            billed,
+            rate,
+            currency: "PLN".to_string(),
+            amount: amount(billed, rate),
+        }
+    }
+
+    #[test]
+    fn test_round_duration() {
+        assert_eq!(round_duration(61, 0, "up"), 61);
+        assert_eq!(round_duration(61, 15, "up"), 900);
+        assert_eq!(round_duration(900, 15, "up"), 900);
+        assert_eq!(round_duration(1000, 15, "down"), 900);
+        assert_eq!(round_duration(1349, 15, "nearest"), 900);
+        assert_eq!(round_duration(1350, 15, "nearest"), 1800);
+        assert_eq!(round_duration(i32::MAX, 1440, "up"), i32::MAX);
+        assert_eq!(round_duration(100, i32::MAX, "nearest"), 0);
+    }
+
+    #[test]
+    fn test_amount() {
+        assert_eq!(amount(3600, 10000), 10000);
+        assert_eq!(amount(900, 9999), 2500);
+        assert_eq!(amount(0, 10000), 0);
+    }
+
+    #[test]
+    fn test_totals() {
+        let lines = vec![
+            line("B", 3600, 100),
+            line("A", 1800, 100),
+            line("B", 1800, 100),
+        ];
+        let clients = totals(&lines);
+        assert_eq!(clients.len(), 2);
+        assert_eq!(clients[0].client, "A");
+        assert_eq!(clients[1].billed, 5400);
+        assert_eq!(clients[1].amount, 150);
+    }
+
+    #[test]
+    fn test_to_csv() {
+        let summary = BillingSummary {
+            lines: vec![line("ACME", 5400, 12050)],
+            clients: Vec::new(),
+        };
+        let csv = to_csv(&summary);
+        assert_eq!(
+            csv.lines().nth(1).unwrap(),
+            "2022-06-06,\"Ala, \"\"ma\"\" kota\",ACME,1.50,1.50,120.50,PLN,180.75"
+        );
+    }
+
+    #[test]
+    fn test_to_pdf() {
+        let lines = (0..120)
+            .map(|i| format!("Line ({})", i))
+            .collect::<Vec<_>>();
+        let pdf = String::from_utf8(to_pdf(&lines)).unwrap();
+        assert!(pdf.starts_with("%PDF-1.4"));
+        assert!(pdf.ends_with("%%EOF\n"));
+        assert!(pdf.contains("/Count 3"));
+        assert!(pdf.contains("(Line \\(119\\)) '"));
+        // Every xref entry points at the start of its object
+        let xref = pdf.find("xref\n").unwrap();
+        let entries = pdf[xref..]
+            .lines()
+            .skip(3)
+            .take_while(|l| l.ends_with(" n "));
+        for (i, entry) in entries.enumerate() {
+            let offset = entry[..10].parse::<usize>().unwrap();
+            assert!(pdf[offset..].starts_with(&format!("{} 0 obj", i + 1)));
+        }
+    }
+}
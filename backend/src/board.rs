@@ -2,6 +2,7 @@ use crate::db::Connection;
 use crate::list::List;
 use crate::schema::private_board;
 use crate::schema::team_board;
+use crate::team::Team;
 use crate::types::BoardUpdate;
 use diesel::prelude::*;
 use diesel::AsChangeset;
@@ -15,6 +16,8 @@ pub struct PrivateBoard {
     pub id: Option<i32>,
     pub name: String,
     pub owner: i32,
+    #[serde(default)]
+    pub version: i32, // Incremented by every update
 }
 
 #[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug)]
@@ -23,9 +26,46 @@ pub struct TeamBoard {
     pub id: Option<i32>,
     pub name: String,
     pub owner: i32,
+    #[serde(default)]
+    pub version: i32, // Incremented by every update
 }
 
+/// Private boards are accessible to their owner, team boards to members of the owning team
+pub async fn has_board_access(
+    board_type: &str,
+    board_id: i32,
+    user_id: i32,
+    connection: &Connection,
+) -> bool {
+    match board_type {
+        "private" => match PrivateBoard::get_by_id(board_id, connection).await {
+            Ok(board) => board.owner == user_id,
+            Err(_) => false,
+        },
+        "team" => match TeamBoard::get_by_id(board_id, connection).await {
+            Ok(board) => Team::has_access(board.owner, user_id, connection)
+                .await
+                .is_ok(),
+            Err(_) => false,
+        },
+        _ => false,
+    }
+}
+
+/// Sortable fields of the board listings
+pub const BOARD_SORT: [(&str, &str); 2] = [("name", "name"), ("created", "id")];
+
 impl PrivateBoard {
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<PrivateBoard> {
+        connection
+            .run(move |conn| {
+                private_board::table
+                    .filter(private_board::id.eq(id))
+                    .first(conn)
+            })
+            .await
+    }
+
     pub async fn create(board: PrivateBoard, connection: &Connection) -> QueryResult<usize> {
         connection
             .run(|conn| {
@@ -46,6 +86,7 @@ impl PrivateBoard {
         Ok(cnt)
     }
 
+    /// 0 rows when the board isn't at the version the update names
     pub async fn update(
         board: BoardUpdate,
         id: i32,
@@ -53,15 +94,38 @@ impl PrivateBoard {
     ) -> QueryResult<usize> {
         connection
             .run(move |conn| {
-                diesel::update(private_board::table.filter(private_board::id.eq(id)))
-                    .set(private_board::name.eq(board.name))
-                    .execute(conn)
+                diesel::update(
+                    private_board::table
+                        .filter(private_board::id.eq(id))
+                        .filter(private_board::version.eq(board.version)),
+                )
+                .set((
+                    private_board::name.eq(board.name),
+                    private_board::version.eq(board.version + 1),
+                ))
+                .execute(conn)
             })
             .await
     }
 }
 
 impl TeamBoard {
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<TeamBoard> {
+        connection
+            .run(move |conn| team_board::table.filter(team_board::id.eq(id)).first(conn))
+            .await
+    }
+
+    pub async fn get_by_team(team: i32, connection: &Connection) -> QueryResult<Vec<TeamBoard>> {
+        connection
+            .run(move |conn| {
+                team_board::table
+                    .filter(team_board::owner.eq(team))
+                    .load::<TeamBoard>(conn)
+            })
+            .await
+    }
+
     pub async fn create(board: TeamBoard, connection: &Connection) -> QueryResult<usize> {
         connection
             .run(|conn| {
@@ -82,6 +146,7 @@ impl TeamBoard {
         Ok(cnt)
     }
 
+    /// 0 rows when the board isn't at the version the update names
     pub async fn update(
         board: BoardUpdate,
         id: i32,
@@ -89,9 +154,16 @@ impl TeamBoard {
     ) -> QueryResult<usize> {
         connection
             .run(move |conn| {
-                diesel::update(team_board::table.filter(team_board::id.eq(id)))
-                    .set(team_board::name.eq(board.name))
-                    .execute(conn)
+                diesel::update(
+                    team_board::table
+                        .filter(team_board::id.eq(id))
+                        .filter(team_board::version.eq(board.version)),
+                )
+                .set((
+                    team_board::name.eq(board.name),
+                    team_board::version.eq(board.version + 1),
+                ))
+                .execute(conn)
             })
             .await
     }
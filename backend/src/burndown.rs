@@ -0,0 +1,268 @@
+use crate::db::Connection;
+use crate::log::Log;
+use crate::milestone::Milestone;
+use crate::schema::log;
+use chrono::{Duration, Local, NaiveDate};
+use diesel::prelude::*;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct BurndownDay {
+    pub date: String,
+    pub total_points: i32,
+    pub done_points: i32,
+    pub remaining_points: i32,
+    pub total_tasks: i32,
+    pub done_tasks: i32,
+    pub remaining_tasks: i32,
+}
+
+/// A task entering (tasks = 1) or leaving (tasks = -1) the milestone, or its points changing
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ScopeChange {
+    pub date: String,
+    pub task_id: i32,
+    pub name: String,
+    pub tasks: i32,
+    pub points: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct IdealPoint {
+    pub date: String,
+    pub points: f64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Burndown {
+    pub start_date: String,
+    pub due_date: Option<String>,
+    pub days: Vec<BurndownDay>,
+    pub scope_changes: Vec<ScopeChange>,
+    pub ideal: Vec<IdealPoint>, // Empty without a due date
+}
+
+#[derive(Clone, Copy, Default)]
+struct TaskState {
+    in_scope: bool,
+    done: bool,
+    points: i32,
+}
+
+/// Day of a log entry, timestamps are written by `get_date`
+fn log_date(log: &Log) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(log.timestamp.get(..10)?, "%Y-%m-%d").ok()
+}
+
+fn parse_date(date: &Option<String>) -> Option<NaiveDate> {
+    NaiveDate::parse_from_str(date.as_ref()?, "%Y-%m-%d").ok()
+}
+
+/// Replays the task logs (oldest first) and returns the state of the milestone at the end
+/// of every day from `start` to `end`. Changes made before `start` are not scope changes.
+pub fn replay(
+    logs: &[Log],
+    milestone: i32,
+    start: NaiveDate,
+    end: NaiveDate,
+) -> (Vec<BurndownDay>, Vec<ScopeChange>) {
+    let mut states: HashMap<i32, TaskState> = HashMap::new();
+    let mut days = Vec::new();
+    let mut changes = Vec::new();
+    let mut logs = logs
+        .iter()
+        .filter_map(|log| Some((log_date(log)?, log)))
+        .peekable();
+    let mut date = start;
+    while date <= end {
+        while let Some((_, log)) = logs.next_if(|(day, _)| *day <= date) {
+            let old = states.get(&log.task_id).copied().unwrap_or_default();
+            let new = TaskState {
+                in_scope: log.action != "deleted" && log.milestone == Some(milestone),
+                done: log.done == 1,
+                points: log.points.max(0), // -1 when the task has no points
+            };
+            let (tasks, points) = match (old.in_scope, new.in_scope) {
+                (false, true) => (1, new.points),
+                (true, false) => (-1, -old.points),
+                (true, true) => (0, new.points - old.points),
+                (false, false) => (0, 0),
+            };
+            if (tasks, points) != (0, 0) && log_date(log).unwrap() >= start {
+                changes.push(ScopeChange {
+                    date: date.to_string(),
+                    task_id: log.task_id,
+                    name: log.name.clone(),
+                    tasks,
+                    points,
+                });
+            }
+            states.insert(log.task_id, new);
+        }
+        let scope = states.values().filter(|state| state.in_scope);
+        let (total_tasks, total_points) = scope.clone().fold((0, 0), |(tasks, points), state| {
+            (tasks + 1, points + state.points)
+        });
+        let (done_tasks, done_points) = scope
+            .filter(|state| state.done)
+            .fold((0, 0), |(tasks, points), state| {
+                (tasks + 1, points + state.points)
+            });
+        days.push(BurndownDay {
+            date: date.to_string(),
+            total_points,
+            done_points,
+            remaining_points: total_points - done_points,
+            total_tasks,
+            done_tasks,
+            remaining_tasks: total_tasks - done_tasks,
+        });
+        date += Duration::days(1);
+    }
+    (days, changes)
+}
+
+/// Straight line from the points at the start down to zero at the due date
+pub fn ideal_line(start: NaiveDate, points: i32, due: Option<NaiveDate>) -> Vec<IdealPoint> {
+    match due {
+        Some(due) if due >= start => vec![
+            IdealPoint {
+                date: start.to_string(),
+                points: points as f64,
+            },
+            IdealPoint {
+                date: due.to_string(),
+                points: 0.0,
+            },
+        ],
+        _ => Vec::new(),
+    }
+}
+
+impl Burndown {
+    /// History of every task that has ever been in the milestone
+    async fn get_logs(milestone: i32, connection: &Connection) -> QueryResult<Vec<Log>> {
+        connection
+            .run(move |conn| {
+                let tasks = log::table
+                    .filter(log::milestone.eq(milestone))
+                    .select(log::task_id)
+                    .distinct()
+                    .load::<i32>(conn)?;
+                log::table
+                    .filter(log::task_id.eq_any(tasks))
+                    .order(log::id.asc())
+                    .load::<Log>(conn)
+            })
+            .await
+    }
+
+    pub async fn get(milestone: Milestone, connection: &Connection) -> QueryResult<Burndown> {
+        let id = milestone.id.unwrap();
+        let logs = Self::get_logs(id, connection).await?;
+        let today = Local::now().date_naive();
+        // Without a start date the chart begins when the first task joined the milestone
+        let start = parse_date(&milestone.start_date)
+            .or_else(|| {
+                logs.iter()
+                    .filter(|log| log.milestone == Some(id))
+                    .filter_map(log_date)
+                    .min()
+            })
+            .unwrap_or(today);
+        let due = parse_date(&milestone.due_date);
+        let (days, scope_changes) = replay(&logs, id, start, today);
+        let points = days.first().map(|day| day.total_points).unwrap_or(0);
+        Ok(Burndown {
+            start_date: start.to_string(),
+            due_date: milestone.due_date,
+            days,
+            scope_changes,
+            ideal: ideal_line(start, points, due),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn log(task_id: i32, date: &str, action: &str, done: i32, points: i32, m: Option<i32>) -> Log {
+        Log {
+            id: None,
+            name: format!("task{}", task_id),
+            list: 1,
+            note: None,
+            place: None,
+            members: None,
+            timestamp: format!("{} 12:00:00.000000000 +02:00", date),
+            action: action.to_owned(),
+            task_id,
+            deadline: String::new(),
+            subtasks: String::new(),
+            points,
+            tags: String::new(),
+            done,
+            milestone: m,
+            changes: None,
+        }
+    }
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_replay() {
+        let logs = vec![
+            log(1, "2022-06-05", "created", 0, 3, Some(1)),
+            log(2, "2022-06-05", "created", 0, 5, None),
+            log(2, "2022-06-06", "updated", 0, 5, Some(1)),
+            log(1, "2022-06-07", "updated", 1, 3, Some(1)),
+            log(4, "2022-06-07", "created", 0, -1, Some(1)),
+            log(2, "2022-06-08", "updated", 0, 8, Some(1)),
+            log(4, "2022-06-08", "updated", 0, 2, Some(1)),
+            log(3, "2022-06-08", "created", 0, 2, Some(2)),
+            log(2, "2022-06-09", "deleted", 0, 8, Some(1)),
+        ];
+        let (days, changes) = replay(&logs, 1, date("2022-06-06"), date("2022-06-09"));
+        let remaining = days
+            .iter()
+            .map(|day| (day.remaining_points, day.done_points, day.total_tasks))
+            .collect::<Vec<_>>();
+        assert_eq!(remaining, vec![(8, 0, 2), (5, 3, 3), (10, 3, 3), (2, 3, 2)]);
+        let changes = changes
+            .iter()
+            .map(|change| {
+                (
+                    change.date.as_str(),
+                    change.task_id,
+                    change.tasks,
+                    change.points,
+                )
+            })
+            .collect::<Vec<_>>();
+        // Task 1 joined before the start, it's not a scope change
+        assert_eq!(
+            changes,
+            vec![
+                ("2022-06-06", 2, 1, 5),
+                ("2022-06-07", 4, 1, 0),
+                ("2022-06-08", 2, 0, 3),
+                ("2022-06-08", 4, 0, 2),
+                ("2022-06-09", 2, -1, -8)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ideal_line() {
+        let line = ideal_line(date("2022-06-06"), 8, Some(date("2022-06-10")));
+        assert_eq!(line.len(), 2);
+        assert_eq!(line[1].date, "2022-06-10");
+        assert_eq!(line[1].points, 0.0);
+        assert!(ideal_line(date("2022-06-06"), 8, Some(date("2022-06-01"))).is_empty());
+        assert!(ideal_line(date("2022-06-06"), 8, None).is_empty());
+    }
+}
@@ -0,0 +1,113 @@
+use crate::db::Connection;
+use crate::schema::{team_user, timer, timer_category, timer_session};
+use crate::timer::TimerSession;
+use diesel::prelude::*;
+use diesel::AsChangeset;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::serde::{Deserialize, Serialize};
+use std::cmp::Reverse;
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug, Clone)]
+#[table_name = "timer_category"]
+pub struct TimerCategory {
+    pub id: Option<i32>,
+    pub name: String,
+    pub color: String,
+    pub user_id: Option<i32>, // Exactly one of user_id and team_id is set
+    pub team_id: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CategoryReport {
+    pub category: Option<i32>,
+    pub name: String,
+    pub color: String,
+    pub time: i32,
+}
+
+impl TimerCategory {
+    pub async fn create(category: TimerCategory, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(|conn| {
+                diesel::insert_into(timer_category::table)
+                    .values(category)
+                    .execute(conn)
+            })
+            .await
+    }
+
+    /// Categories of the user and of all teams the user belongs to
+    pub async fn get(user_id: i32, connection: &Connection) -> QueryResult<Vec<TimerCategory>> {
+        connection
+            .run(move |conn| {
+                let teams = team_user::table
+                    .filter(team_user::user.eq(user_id))
+                    .select(team_user::team)
+                    .load::<i32>(conn)?;
+                timer_category::table
+                    .filter(
+                        timer_category::user_id
+                            .eq(user_id)
+                            .or(timer_category::team_id.eq_any(teams)),
+                    )
+                    .order(timer_category::name)
+                    .load::<TimerCategory>(conn)
+            })
+            .await
+    }
+
+    pub async fn is_visible(id: i32, user_id: i32, connection: &Connection) -> bool {
+        match Self::get(user_id, connection).await {
+            Ok(categories) => categories.into_iter().any(|c| c.id == Some(id)),
+            Err(_) => false,
+        }
+    }
+
+    pub async fn delete(id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::update(timer::table.filter(timer::category.eq(id)))
+                    .set(timer::category.eq(None::<i32>))
+                    .execute(conn)?;
+                diesel::update(timer_session::table.filter(timer_session::category.eq(id)))
+                    .set(timer_session::category.eq(None::<i32>))
+                    .execute(conn)?;
+                diesel::delete(timer_category::table.filter(timer_category::id.eq(id)))
+                    .execute(conn)
+            })
+            .await
+    }
+
+    /// Time tracked by the user in sessions started between `from` and `to`, summed by category
+    pub async fn report(
+        user_id: i32,
+        from: Option<i32>,
+        to: Option<i32>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<CategoryReport>> {
+        let categories = Self::get(user_id, connection).await?;
+        let sessions = TimerSession::get_by_user(user_id, from, to, connection).await?;
+        let mut totals = HashMap::<Option<i32>, i32>::new();
+        for session in sessions {
+            *totals.entry(session.category).or_insert(0) += session.duration;
+        }
+        let mut report: Vec<CategoryReport> = totals
+            .into_iter()
+            .map(|(category, time)| {
+                let found = categories
+                    .iter()
+                    .find(|c| c.id.is_some() && c.id == category);
+                CategoryReport {
+                    category,
+                    name: found.map_or("Uncategorized".to_owned(), |c| c.name.clone()),
+                    color: found.map_or("#6c757d".to_owned(), |c| c.color.clone()),
+                    time,
+                }
+            })
+            .collect();
+        report.sort_by_key(|r| Reverse(r.time));
+        Ok(report)
+    }
+}
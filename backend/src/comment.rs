@@ -0,0 +1,357 @@
+use crate::db::Connection;
+use crate::page::Page;
+use crate::schema::{comment, list, task, users};
+use crate::types::CommentData;
+use crate::user::User;
+use crate::utils::get_date;
+use diesel::prelude::*;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, Debug, Clone)]
+#[table_name = "comment"]
+pub struct Comment {
+    pub id: Option<i32>,
+    pub task_id: i32,
+    pub parent: Option<i32>, // Comment this one replies to
+    pub author: i32,
+    pub body: String,
+    pub mentions: String, // Usernames of the mentioned users, separated by spaces
+    pub created: String,
+    pub edited: Option<String>,
+    pub deleted: i32, // Deleted comments with replies stay in the thread without their text
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommentResponse {
+    pub id: i32,
+    pub task_id: i32,
+    pub parent: Option<i32>,
+    pub author: i32,
+    pub author_name: String,
+    pub body: String,
+    pub mentions: Vec<String>,
+    pub created: String,
+    pub edited: Option<String>,
+    pub deleted: bool,
+    pub replies: Vec<CommentResponse>,
+}
+
+/// Comment in the board's activity, with the task it's on
+#[derive(Serialize, Deserialize, Debug)]
+pub struct CommentActivity {
+    pub task_name: String,
+    pub comment: CommentResponse,
+}
+
+/// Names after `@` in the text, an `@` inside a word like in an email isn't a mention
+pub fn parse_mentions(body: &str) -> Vec<String> {
+    let mut mentions = Vec::new();
+    let mut previous = ' ';
+    for (i, c) in body.char_indices() {
+        if c == '@' && !previous.is_alphanumeric() {
+            let name = body[i + 1..]
+                .split(|c: char| !(c.is_alphanumeric() || c == '_' || c == '-' || c == '.'))
+                .next()
+                .unwrap_or_default()
+                .trim_end_matches('.');
+            if !name.is_empty() && !mentions.iter().any(|m| m == name) {
+                mentions.push(name.to_owned());
+            }
+        }
+        previous = c;
+    }
+    mentions
+}
+
+/// Mentioned names that are users, the others are left as plain text
+async fn resolve_mentions(body: &str, connection: &Connection) -> String {
+    let mut users = Vec::new();
+    for name in parse_mentions(body) {
+        if User::get_username_id(name.clone(), connection)
+            .await
+            .is_some()
+        {
+            users.push(name);
+        }
+    }
+    users.join(" ")
+}
+
+/// Nests the replies under the comments they answer, deleted comments are dropped once
+/// nobody replies to them
+pub fn thread(comments: Vec<CommentResponse>) -> Vec<CommentResponse> {
+    let ids = comments.iter().map(|c| c.id).collect::<Vec<_>>();
+    let mut children: HashMap<Option<i32>, Vec<CommentResponse>> = HashMap::new();
+    for comment in comments {
+        // Replies to missing comments are shown at the top
+        let parent = comment.parent.filter(|parent| ids.contains(parent));
+        children.entry(parent).or_default().push(comment);
+    }
+    fn build(
+        parent: Option<i32>,
+        children: &mut HashMap<Option<i32>, Vec<CommentResponse>>,
+    ) -> Vec<CommentResponse> {
+        let mut comments = children.remove(&parent).unwrap_or_default();
+        for comment in comments.iter_mut() {
+            comment.replies = build(Some(comment.id), children);
+        }
+        comments.retain(|comment| !comment.deleted || !comment.replies.is_empty());
+        comments
+    }
+    build(None, &mut children)
+}
+
+impl Comment {
+    pub const SORT: [(&'static str, &'static str); 1] = [("created", "comment.id")];
+
+    pub async fn create(
+        task_id: i32,
+        data: CommentData,
+        author: i32,
+        connection: &Connection,
+    ) -> QueryResult<i32> {
+        let comment = Comment {
+            id: None,
+            task_id,
+            parent: data.parent,
+            author,
+            mentions: resolve_mentions(&data.body, connection).await,
+            body: data.body,
+            created: get_date(),
+            edited: None,
+            deleted: 0,
+        };
+        connection
+            .run(|conn| {
+                diesel::insert_into(comment::table)
+                    .values(comment)
+                    .execute(conn)?;
+                diesel::select(crate::task::last_insert_rowid).get_result::<i32>(conn)
+            })
+            .await
+    }
+
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<Comment> {
+        connection
+            .run(move |conn| comment::table.filter(comment::id.eq(id)).first(conn))
+            .await
+    }
+
+    /// Comments of the task, threaded
+    pub async fn get(task_id: i32, connection: &Connection) -> QueryResult<Vec<CommentResponse>> {
+        let comments = connection
+            .run(move |conn| {
+                comment::table
+                    .filter(comment::task_id.eq(task_id))
+                    .order(comment::id)
+                    .load::<Comment>(conn)
+            })
+            .await?;
+        Ok(thread(Self::responses(comments, connection).await?))
+    }
+
+    /// Only the author can edit a comment, the mentions are parsed again
+    pub async fn update(
+        id: i32,
+        body: String,
+        author: i32,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let mentions = resolve_mentions(&body, connection).await;
+        connection
+            .run(move |conn| {
+                diesel::update(
+                    comment::table
+                        .filter(comment::id.eq(id))
+                        .filter(comment::author.eq(author))
+                        .filter(comment::deleted.eq(0)),
+                )
+                .set((
+                    comment::body.eq(body),
+                    comment::mentions.eq(mentions),
+                    comment::edited.eq(get_date()),
+                ))
+                .execute(conn)
+            })
+            .await
+    }
+
+    /// Only the author can delete a comment, its text is removed when it has replies
+    pub async fn delete(id: i32, author: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                let own = comment::table
+                    .filter(comment::id.eq(id))
+                    .filter(comment::author.eq(author));
+                let replies = comment::table
+                    .filter(comment::parent.eq(id))
+                    .count()
+                    .get_result::<i64>(conn)?;
+                if replies > 0 {
+                    diesel::update(own.filter(comment::deleted.eq(0)))
+                        .set((
+                            comment::body.eq(""),
+                            comment::mentions.eq(""),
+                            comment::deleted.eq(1),
+                        ))
+                        .execute(conn)
+                } else {
+                    diesel::delete(own).execute(conn)
+                }
+            })
+            .await
+    }
+
+    pub async fn delete_task_comments(task_id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(comment::table.filter(comment::task_id.eq(task_id))).execute(conn)
+            })
+            .await
+    }
+
+    /// Page of the comments on the board's tasks and the number of all of them
+    pub async fn board(
+        board_type: String,
+        board_id: i32,
+        page: Page,
+        connection: &Connection,
+    ) -> QueryResult<(Vec<CommentActivity>, i64)> {
+        let (comments, tasks, total) = connection
+            .run(move |conn| {
+                let lists = list::table
+                    .filter(list::board_type.eq(board_type))
+                    .filter(list::board.eq(board_id))
+                    .select(list::id)
+                    .load::<Option<i32>>(conn)?;
+                let tasks = task::table
+                    .filter(task::list.eq_any(lists.into_iter().flatten()))
+                    .select((task::id, task::name))
+                    .load::<(Option<i32>, String)>(conn)?
+                    .into_iter()
+                    .filter_map(|(id, name)| Some((id?, name)))
+                    .collect::<HashMap<_, _>>();
+                let ids = tasks.keys().copied().collect::<Vec<_>>();
+                let comments = comment::table
+                    .filter(comment::task_id.eq_any(ids))
+                    .filter(comment::deleted.eq(0));
+                let total = comments.clone().count().get_result::<i64>(conn)?;
+                let comments = comments
+                    .order(page.order())
+                    .offset(page.offset)
+                    .limit(page.sql_limit())
+                    .load::<Comment>(conn)?;
+                Ok::<_, diesel::result::Error>((comments, tasks, total))
+            })
+            .await?;
+        let activity = Self::responses(comments, connection)
+            .await?
+            .into_iter()
+            .map(|comment| CommentActivity {
+                task_name: tasks.get(&comment.task_id).cloned().unwrap_or_default(),
+                comment,
+            })
+            .collect();
+        Ok((activity, total))
+    }
+
+    /// Responses with the names of the authors, without replies
+    pub async fn responses(
+        comments: Vec<Comment>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<CommentResponse>> {
+        let authors = comments.iter().map(|c| c.author).collect::<Vec<_>>();
+        let names = connection
+            .run(move |conn| {
+                users::table
+                    .filter(users::id.eq_any(authors))
+                    .select((users::id, users::username))
+                    .load::<(Option<i32>, String)>(conn)
+            })
+            .await?
+            .into_iter()
+            .filter_map(|(id, name)| Some((id?, name)))
+            .collect::<HashMap<_, _>>();
+        Ok(comments
+            .into_iter()
+            .filter_map(|comment| {
+                Some(CommentResponse {
+                    id: comment.id?,
+                    task_id: comment.task_id,
+                    parent: comment.parent,
+                    author: comment.author,
+                    author_name: names.get(&comment.author).cloned().unwrap_or_default(),
+                    mentions: comment
+                        .mentions
+                        .split_whitespace()
+                        .map(|name| name.to_owned())
+                        .collect(),
+                    body: comment.body,
+                    created: comment.created,
+                    edited: comment.edited,
+                    deleted: comment.deleted == 1,
+                    replies: Vec::new(),
+                })
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn comment(id: i32, parent: Option<i32>, deleted: bool) -> CommentResponse {
+        CommentResponse {
+            id,
+            task_id: 1,
+            parent,
+            author: 1,
+            author_name: "ala".to_owned(),
+            body: format!("comment {}", id),
+            mentions: Vec::new(),
+            created: "".to_owned(),
+            edited: None,
+            deleted,
+            replies: Vec::new(),
+        }
+    }
+
+    fn ids(comments: &[CommentResponse]) -> Vec<(i32, Vec<i32>)> {
+        comments
+            .iter()
+            .map(|c| (c.id, c.replies.iter().map(|r| r.id).collect()))
+            .collect()
+    }
+
+    #[test]
+    fn test_parse_mentions() {
+        assert_eq!(
+            parse_mentions("@ala, could you ask @bob.smith? cc @ala"),
+            vec!["ala".to_owned(), "bob.smith".to_owned()]
+        );
+        assert!(parse_mentions("mail ala@example.com or @ or @@").is_empty());
+        assert_eq!(parse_mentions("(@kot_1)"), vec!["kot_1".to_owned()]);
+    }
+
+    #[test]
+    fn test_thread() {
+        let comments = thread(vec![
+            comment(1, None, false),
+            comment(2, Some(1), false),
+            comment(3, None, true),
+            comment(4, Some(2), false),
+            comment(5, Some(9), false),
+            comment(6, None, true),
+            comment(7, Some(6), false),
+        ]);
+        assert_eq!(
+            ids(&comments),
+            vec![(1, vec![2]), (5, vec![]), (6, vec![7])]
+        );
+        assert_eq!(ids(&comments[0].replies), vec![(2, vec![4])]);
+    }
+}
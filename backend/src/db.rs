@@ -1,4 +1,20 @@
 use diesel::SqliteConnection;
+use rocket::{Phase, Rocket};
+use rocket_sync_db_pools::ConnectionPool;
 
 #[database("sqlite_database")]
 pub struct Connection(SqliteConnection);
+
+/// Pool of the database for background tasks, which take a connection for each run
+#[derive(Clone)]
+pub struct Pool(ConnectionPool<Connection, SqliteConnection>);
+
+impl Pool {
+    pub fn get<P: Phase>(rocket: &Rocket<P>) -> Option<Pool> {
+        Connection::pool(rocket).cloned().map(Pool)
+    }
+
+    pub async fn connection(&self) -> Option<Connection> {
+        self.0.get().await.map(Connection)
+    }
+}
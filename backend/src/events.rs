@@ -0,0 +1,316 @@
+use crate::comment::CommentResponse;
+use crate::db::Connection;
+use crate::list::List;
+use crate::milestone::Milestone;
+use crate::task::Task;
+use rocket::request::{self, FromRequest, Request};
+use rocket::serde::json::{serde_json, Value};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::sync::broadcast::{self, Receiver, Sender};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::sync::{Arc, Mutex};
+
+/// Events kept for clients resuming after a dropped connection
+const HISTORY: usize = 512;
+
+/// Change of a board, sent to the clients watching it
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct BoardEvent {
+    pub id: u64,
+    pub board_type: String,
+    pub board_id: i32,
+    pub kind: String,   // task, list, milestone, comment or board
+    pub action: String, // created, updated, deleted, or reset when the client has to reload the board
+    pub entity: i32,
+    pub data: Option<Value>, // The entity after the change, None when it was deleted
+}
+
+struct History {
+    events: VecDeque<BoardEvent>,
+    last_id: u64,
+}
+
+/// Board events of the server, managed by rocket
+#[derive(Clone)]
+pub struct Events {
+    sender: Sender<BoardEvent>,
+    history: Arc<Mutex<History>>,
+}
+
+impl Default for Events {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Events {
+    pub fn new() -> Self {
+        Events {
+            sender: broadcast::channel(HISTORY).0,
+            history: Arc::new(Mutex::new(History {
+                events: VecDeque::new(),
+                last_id: 0,
+            })),
+        }
+    }
+
+    pub fn publish(
+        &self,
+        board_type: &str,
+        board_id: i32,
+        kind: &str,
+        action: &str,
+        entity: i32,
+        data: Option<Value>,
+    ) {
+        let mut history = self.history.lock().unwrap();
+        history.last_id += 1;
+        let event = BoardEvent {
+            id: history.last_id,
+            board_type: board_type.to_owned(),
+            board_id,
+            kind: kind.to_owned(),
+            action: action.to_owned(),
+            entity,
+            data,
+        };
+        if history.events.len() == HISTORY {
+            history.events.pop_front();
+        }
+        history.events.push_back(event.clone());
+        // Sent under the lock, so a subscriber gets every event exactly once
+        let _ = self.sender.send(event);
+    }
+
+    /// Receiver of the new events and the board's events after `last_id`, a reset event
+    /// instead when some of them are no longer kept or the server restarted
+    pub fn subscribe(
+        &self,
+        board_type: &str,
+        board_id: i32,
+        last_id: Option<u64>,
+    ) -> (Vec<BoardEvent>, Receiver<BoardEvent>) {
+        let history = self.history.lock().unwrap();
+        let receiver = self.sender.subscribe();
+        let last_id = match last_id {
+            Some(last_id) => last_id,
+            None => return (Vec::new(), receiver),
+        };
+        let oldest = history.events.front().map_or(history.last_id + 1, |e| e.id);
+        if last_id > history.last_id || last_id + 1 < oldest {
+            let reset = Self::reset(board_type, board_id, history.last_id);
+            return (vec![reset], receiver);
+        }
+        let missed = history
+            .events
+            .iter()
+            .filter(|event| event.id > last_id && event.is_on(board_type, board_id))
+            .cloned()
+            .collect();
+        (missed, receiver)
+    }
+
+    /// Tells the client to reload the board, the id lets it resume from here afterwards
+    pub fn reset(board_type: &str, board_id: i32, id: u64) -> BoardEvent {
+        BoardEvent {
+            id,
+            board_type: board_type.to_owned(),
+            board_id,
+            kind: "board".to_owned(),
+            action: "reset".to_owned(),
+            entity: board_id,
+            data: None,
+        }
+    }
+
+    /// Task event on the board of its list, followed by its parent, whose children's
+    /// progress changed, and the new stats of the milestones the task was or is in
+    pub async fn task(
+        &self,
+        action: &str,
+        task: &Task,
+        milestones: &[Option<i32>],
+        connection: &Connection,
+    ) {
+        let list = match List::get_by_id(task.list, connection).await {
+            Ok(list) => list,
+            Err(_) => return,
+        };
+        self.publish_task(action, task, &list, connection).await;
+        if let Some(parent) = task.parent {
+            if let Ok(parent) = Task::get_single(parent, connection).await {
+                self.publish_task("updated", &parent, &list, connection)
+                    .await;
+            }
+        }
+        let mut milestones = milestones.iter().flatten().collect::<Vec<_>>();
+        milestones.sort();
+        milestones.dedup();
+        for &id in milestones {
+            self.milestone("updated", id, &list.board_type, list.board, connection)
+                .await;
+        }
+    }
+
+    /// With the attachments, like the task endpoints send it
+    async fn publish_task(&self, action: &str, task: &Task, list: &List, connection: &Connection) {
+        let data = match action {
+            "deleted" => None,
+            _ => Task::responses(vec![task.clone()], connection)
+                .await
+                .ok()
+                .map(|tasks| serde_json::to_value(&tasks[0]).unwrap()),
+        };
+        self.publish(
+            &list.board_type,
+            list.board,
+            "task",
+            action,
+            task.id.unwrap_or_default(),
+            data,
+        );
+    }
+
+    /// Comment event on the board of its task, the data has no replies
+    pub async fn comment(&self, action: &str, comment: &CommentResponse, connection: &Connection) {
+        let task = match Task::get_single(comment.task_id, connection).await {
+            Ok(task) => task,
+            Err(_) => return,
+        };
+        let list = match List::get_by_id(task.list, connection).await {
+            Ok(list) => list,
+            Err(_) => return,
+        };
+        let data = (action != "deleted").then(|| serde_json::to_value(comment).unwrap());
+        self.publish(
+            &list.board_type,
+            list.board,
+            "comment",
+            action,
+            comment.id,
+            data,
+        );
+    }
+
+    pub fn list(&self, action: &str, list: &List) {
+        let data = (action != "deleted").then(|| serde_json::to_value(list).unwrap());
+        self.publish(
+            &list.board_type,
+            list.board,
+            "list",
+            action,
+            list.id.unwrap_or_default(),
+            data,
+        );
+    }
+
+    /// Milestone event with its stats, as the board's snapshot has them
+    pub async fn milestone(
+        &self,
+        action: &str,
+        id: i32,
+        board_type: &str,
+        board_id: i32,
+        connection: &Connection,
+    ) {
+        let data = match action {
+            "deleted" => None,
+            _ => Milestone::get(board_id, board_type.to_owned(), connection)
+                .await
+                .ok()
+                .and_then(|milestones| milestones.into_iter().find(|m| m.id == Some(id)))
+                .map(|milestone| serde_json::to_value(milestone).unwrap()),
+        };
+        self.publish(board_type, board_id, "milestone", action, id, data);
+    }
+}
+
+impl BoardEvent {
+    pub fn is_on(&self, board_type: &str, board_id: i32) -> bool {
+        self.board_type == board_type && self.board_id == board_id
+    }
+}
+
+/// Event the client resumes after, from the `Last-Event-ID` header the browser sends when
+/// it reconnects by itself or from `?last_event_id=` when the client reconnects
+pub struct LastEventId(pub Option<u64>);
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for LastEventId {
+    type Error = Infallible;
+
+    async fn from_request(request: &'a Request<'_>) -> request::Outcome<LastEventId, Infallible> {
+        let header = request
+            .headers()
+            .get_one("Last-Event-ID")
+            .and_then(|id| id.trim().parse().ok());
+        let query = request
+            .query_value::<u64>("last_event_id")
+            .and_then(|id| id.ok());
+        request::Outcome::Success(LastEventId(header.or(query)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn publish(events: &Events, board_id: i32, entity: i32) {
+        events.publish("private", board_id, "task", "updated", entity, None);
+    }
+
+    fn entities(events: Vec<BoardEvent>) -> Vec<(String, i32)> {
+        events
+            .into_iter()
+            .map(|event| (event.kind, event.entity))
+            .collect()
+    }
+
+    #[test]
+    fn test_resume() {
+        let events = Events::new();
+        publish(&events, 1, 10);
+        publish(&events, 2, 20);
+        publish(&events, 1, 11);
+
+        let (missed, _) = events.subscribe("private", 1, None);
+        assert!(missed.is_empty());
+        let (missed, _) = events.subscribe("private", 1, Some(0));
+        assert_eq!(
+            entities(missed),
+            vec![("task".to_owned(), 10), ("task".to_owned(), 11)]
+        );
+        let (missed, _) = events.subscribe("private", 1, Some(1));
+        assert_eq!(entities(missed), vec![("task".to_owned(), 11)]);
+        let (missed, _) = events.subscribe("team", 1, Some(0));
+        assert!(missed.is_empty());
+        let (missed, _) = events.subscribe("private", 1, Some(3));
+        assert!(missed.is_empty());
+    }
+
+    #[test]
+    fn test_reset() {
+        let events = Events::new();
+        // Ids from before a restart of the server
+        let (missed, _) = events.subscribe("private", 1, Some(5));
+        assert_eq!(missed, vec![Events::reset("private", 1, 0)]);
+
+        for i in 0..HISTORY + 2 {
+            publish(&events, 1, i as i32);
+        }
+        let (missed, _) = events.subscribe("private", 1, Some(1));
+        assert_eq!(entities(missed), vec![("board".to_owned(), 1)]);
+        let (missed, _) = events.subscribe("private", 1, Some(2));
+        assert_eq!(missed.len(), HISTORY);
+    }
+
+    #[test]
+    fn test_live() {
+        let events = Events::new();
+        let (_, mut receiver) = events.subscribe("private", 1, None);
+        publish(&events, 1, 10);
+        let event = receiver.try_recv().unwrap();
+        assert_eq!((event.id, event.entity), (1, 10));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::db::Connection;
+use crate::schema::saved_filter;
+use crate::types::{SavedFilterData, TaskFilter};
+use diesel::prelude::*;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::serde::json::serde_json;
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, Debug)]
+#[table_name = "saved_filter"]
+pub struct SavedFilter {
+    pub id: Option<i32>,
+    pub name: String,
+    pub user_id: i32,
+    pub board_type: String,
+    pub board_id: i32,
+    pub shared: i32,
+    pub filter: String, // TaskFilter as json
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SavedFilterResponse {
+    pub id: i32,
+    pub name: String,
+    pub user_id: i32,
+    pub shared: bool,
+    pub filter: TaskFilter,
+}
+
+impl SavedFilter {
+    pub async fn create(
+        data: SavedFilterData,
+        user_id: i32,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let filter = SavedFilter {
+            id: None,
+            name: data.name,
+            user_id,
+            board_type: data.board_type,
+            board_id: data.board_id,
+            shared: data.shared as i32,
+            filter: serde_json::to_string(&data.filter).unwrap(),
+        };
+        connection
+            .run(|conn| {
+                diesel::insert_into(saved_filter::table)
+                    .values(filter)
+                    .execute(conn)
+            })
+            .await
+    }
+
+    /// Filters of the board saved by the user or shared with the team
+    pub async fn get(
+        board_type: String,
+        board_id: i32,
+        user_id: i32,
+        connection: &Connection,
+    ) -> QueryResult<Vec<SavedFilterResponse>> {
+        let filters = connection
+            .run(move |conn| {
+                saved_filter::table
+                    .filter(saved_filter::board_type.eq(board_type))
+                    .filter(saved_filter::board_id.eq(board_id))
+                    .filter(
+                        saved_filter::user_id
+                            .eq(user_id)
+                            .or(saved_filter::shared.eq(1)),
+                    )
+                    .order(saved_filter::name)
+                    .load::<SavedFilter>(conn)
+            })
+            .await?;
+        Ok(filters
+            .into_iter()
+            .filter_map(|filter| {
+                Some(SavedFilterResponse {
+                    id: filter.id?,
+                    name: filter.name,
+                    user_id: filter.user_id,
+                    shared: filter.shared == 1,
+                    filter: serde_json::from_str(&filter.filter).ok()?,
+                })
+            })
+            .collect())
+    }
+
+    /// Only the author can delete a filter
+    pub async fn delete(id: i32, user_id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(
+                    saved_filter::table
+                        .filter(saved_filter::id.eq(id))
+                        .filter(saved_filter::user_id.eq(user_id)),
+                )
+                .execute(conn)
+            })
+            .await
+    }
+}
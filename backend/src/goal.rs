@@ -0,0 +1,165 @@
+use crate::db::Connection;
+use crate::schema::timer_goal;
+use crate::timer::{Timer, TimerSession};
+use crate::utils::get_time;
+use diesel::prelude::*;
+use diesel::AsChangeset;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+pub const DAY: i32 = 24 * 60 * 60;
+pub const WEEK: i32 = 7 * DAY;
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug, Clone)]
+#[table_name = "timer_goal"]
+pub struct TimerGoal {
+    pub id: Option<i32>,
+    pub user_id: i32,
+    pub period: String,        // "day" or "week"
+    pub category: Option<i32>, // None counts all tracked time
+    pub target: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct GoalProgress {
+    pub goal: TimerGoal,
+    pub tracked: i32,
+    pub streak: i32,
+}
+
+/// Number of the day or week containing `time`. Weeks start on Monday, both in UTC.
+pub fn period_index(period: &str, time: i32) -> i32 {
+    match period {
+        // 1970-01-01 was a Thursday
+        "week" => (time + 3 * DAY).div_euclid(WEEK),
+        _ => time.div_euclid(DAY),
+    }
+}
+
+/// Consecutive periods before `current` in which the target was met, plus `current` itself
+/// once it is met. An unfinished current period does not break the streak.
+pub fn streak(totals: &HashMap<i32, i32>, current: i32, target: i32) -> i32 {
+    let reached = |index: i32| totals.get(&index).copied().unwrap_or(0) >= target;
+    let mut index = if reached(current) {
+        current
+    } else {
+        current - 1
+    };
+    let mut streak = 0;
+    while reached(index) {
+        streak += 1;
+        index -= 1;
+    }
+    streak
+}
+
+impl TimerGoal {
+    pub async fn create(goal: TimerGoal, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(|conn| {
+                diesel::insert_into(timer_goal::table)
+                    .values(goal)
+                    .execute(conn)
+            })
+            .await
+    }
+
+    pub async fn get(user_id: i32, connection: &Connection) -> QueryResult<Vec<TimerGoal>> {
+        connection
+            .run(move |conn| {
+                timer_goal::table
+                    .filter(timer_goal::user_id.eq(user_id))
+                    .load::<TimerGoal>(conn)
+            })
+            .await
+    }
+
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<TimerGoal> {
+        connection
+            .run(move |conn| timer_goal::table.filter(timer_goal::id.eq(id)).first(conn))
+            .await
+    }
+
+    pub async fn delete(id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(timer_goal::table.filter(timer_goal::id.eq(id))).execute(conn)
+            })
+            .await
+    }
+
+    /// Time tracked towards every goal of the user in the current period. A session counts
+    /// in the period it started in, running timers are included up to now.
+    pub async fn progress(user_id: i32, connection: &Connection) -> QueryResult<Vec<GoalProgress>> {
+        let goals = Self::get(user_id, connection).await?;
+        let mut sessions = TimerSession::get_by_user(user_id, None, None, connection)
+            .await?
+            .into_iter()
+            .map(|session| (session.category, session.start, session.duration))
+            .collect::<Vec<_>>();
+        let now = get_time();
+        for timer in Timer::get_timers(user_id, None, connection).await? {
+            if timer.status == "active" {
+                let start = timer.start.unwrap_or(now);
+                sessions.push((timer.category, start, now - start));
+            }
+        }
+        Ok(goals
+            .into_iter()
+            .map(|goal| {
+                let mut totals = HashMap::<i32, i32>::new();
+                for (category, start, duration) in sessions.iter() {
+                    if goal.category.is_none() || goal.category == *category {
+                        *totals
+                            .entry(period_index(&goal.period, *start))
+                            .or_insert(0) += duration;
+                    }
+                }
+                let current = period_index(&goal.period, now);
+                GoalProgress {
+                    tracked: totals.get(&current).copied().unwrap_or(0),
+                    streak: streak(&totals, current, goal.target),
+                    goal,
+                }
+            })
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_period_index() {
+        // Monday 2022-06-06 00:00 and Sunday 2022-06-12 23:59 UTC
+        assert_eq!(
+            period_index("week", 1654473600),
+            period_index("week", 1655078399)
+        );
+        assert_ne!(
+            period_index("week", 1654473600),
+            period_index("week", 1654473599)
+        );
+        assert_eq!(
+            period_index("day", 1654473600),
+            period_index("day", 1654559999)
+        );
+        assert_ne!(
+            period_index("day", 1654473600),
+            period_index("day", 1654559999 + 1)
+        );
+    }
+
+    #[test]
+    fn test_streak() {
+        let totals = HashMap::from([(7, 3600), (8, 4000), (9, 100), (10, 3600), (11, 7200)]);
+        assert_eq!(streak(&totals, 11, 3600), 2);
+        // The current period is still running
+        assert_eq!(streak(&totals, 12, 3600), 2);
+        assert_eq!(streak(&totals, 13, 3600), 0);
+        assert_eq!(streak(&totals, 9, 3600), 2);
+    }
+}
@@ -1,12 +1,13 @@
 use crate::db::Connection;
 use crate::schema::list;
+use crate::task::last_insert_rowid;
 use diesel::prelude::*;
 use diesel::AsChangeset;
 use diesel::Insertable;
 use diesel::Queryable;
 use rocket::serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug)]
+#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug, Clone)]
 #[table_name = "list"]
 pub struct List {
     pub id: Option<i32>,
@@ -16,10 +17,22 @@ pub struct List {
 }
 
 impl List {
-    pub async fn create(list: List, connection: &Connection) -> QueryResult<usize> {
+    /// Id of the new list
+    pub async fn create(list: List, connection: &Connection) -> QueryResult<i32> {
         println!("{:?}", list);
         connection
-            .run(|conn| diesel::insert_into(list::table).values(list).execute(conn))
+            .run(|conn| {
+                diesel::insert_into(list::table)
+                    .values(list)
+                    .execute(conn)?;
+                diesel::select(last_insert_rowid).get_result::<i32>(conn)
+            })
+            .await
+    }
+
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<List> {
+        connection
+            .run(move |conn| list::table.filter(list::id.eq(id)).first(conn))
             .await
     }
 
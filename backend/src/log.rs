@@ -1,4 +1,5 @@
 use crate::db::Connection;
+use crate::page::Page;
 use crate::schema::log;
 use crate::task::Task;
 use crate::utils::get_date;
@@ -24,22 +25,35 @@ pub struct Log {
     pub subtasks: String,
     pub points: i32,
     pub tags: String,
+    pub done: i32,
+    pub milestone: Option<i32>,
+    pub changes: Option<String>, // JSON of the fields a patch changed, None for full updates
 }
 
 impl Log {
+    pub const SORT: [(&'static str, &'static str); 1] = [("created", "log.id")];
+
     pub async fn create(log: Log, connection: &Connection) -> QueryResult<usize> {
         connection
             .run(|conn| diesel::insert_into(log::table).values(log).execute(conn))
             .await
     }
 
-    pub async fn get(id: i32, connection: &Connection) -> QueryResult<Vec<Log>> {
+    /// Page of the task's logs and the number of all of them
+    pub async fn get(id: i32, page: Page, connection: &Connection) -> QueryResult<(Vec<Log>, i64)> {
         connection
             .run(move |conn| {
-                log::table
+                let total = log::table
+                    .filter(log::task_id.eq(id))
+                    .count()
+                    .get_result(conn)?;
+                let logs = log::table
                     .filter(log::task_id.eq(id))
-                    .order(log::timestamp.desc())
-                    .load::<Log>(conn)
+                    .order(page.order())
+                    .offset(page.offset)
+                    .limit(page.sql_limit())
+                    .load::<Log>(conn)?;
+                Ok((logs, total))
             })
             .await
     }
@@ -59,6 +73,9 @@ impl Log {
             subtasks: task.subtasks,
             points: task.points,
             tags: task.tags,
+            done: task.done,
+            milestone: task.milestone,
+            changes: None,
         }
     }
 }
@@ -1,30 +1,70 @@
 use crate::auth::ApiKey;
 use crate::types::{BoardUpdate, PrivateBoardData, TeamBoardData, TeamBoardWithName, TeamData};
-use board::{PrivateBoard, TeamBoard};
-use db::Connection;
+use attachment::{Attachment, AttachmentConfig, Download, UploadError};
+use billing::{invoice_lines, to_csv, to_pdf, BillingRate, BillingSummary};
+use board::{has_board_access, PrivateBoard, TeamBoard, BOARD_SORT};
+use burndown::Burndown;
+use category::{CategoryReport, TimerCategory};
+use chrono::{Datelike, NaiveDate};
+use comment::{Comment, CommentActivity, CommentResponse};
+use db::{Connection, Pool};
+use events::{BoardEvent, Events, LastEventId};
+use filter::{SavedFilter, SavedFilterResponse};
+use goal::{GoalProgress, TimerGoal};
 use list::List;
 use log::Log;
-use milestone::{Milestone, MilestoneResponse};
+use milestone::{Milestone, MilestoneResponse, MilestoneTasks, RoadmapItem};
+use page::{PageQuery, Paged};
+use query::ParseError;
+use recurrence::{Frequency, Recurrence, Rule};
+use rocket::data::Capped;
+use rocket::form::Form;
+use rocket::fs::{NamedFile, TempFile};
 use rocket::futures::future::join_all;
-use rocket::http::Status;
+use rocket::http::{ContentType, Status};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::serde_json;
 use rocket::serde::json::Json;
+use rocket::tokio::select;
+use rocket::tokio::sync::broadcast::error::RecvError;
+use rocket::{Shutdown, State};
+use search::{fts_query, search, SearchResult};
+use snapshot::{IfMatch, IfNoneMatch, Snapshot, SnapshotResponse};
 use team::Team;
-use timer::Timer;
-use types::{Credentials, TaskFilter, TimerData, TokenResponse};
-use utils::get_time;
+use timer::{Timer, TimerSession, TimerSettings};
+use types::{
+    CategoryAssignment, CategoryData, CommentData, Credentials, FieldError, GoalData,
+    MilestonePredecessors, MilestoneTasksUpdate, MilestoneUpdate, PomodoroConfig, RateData,
+    RecurrenceData, SavedFilterData, SessionBilling, TaskBlockers, TaskFilter, TaskPatch,
+    TimerBilling, TimerData, TimerReview, TimerSettingsData, TokenResponse,
+};
+use utils::{get_time, is_color};
 
 use self::auth::crypto::sha2::Sha256;
 use self::auth::jwt::{Header, Registered, Token};
-use task::Task;
+use task::{Task, TaskResponse};
 use user::User;
 
+pub mod attachment;
 pub mod auth;
+pub mod billing;
 pub mod board;
+pub mod burndown;
+pub mod category;
+pub mod comment;
 pub mod db;
+pub mod events;
+pub mod filter;
+pub mod goal;
 pub mod list;
 pub mod log;
 pub mod milestone;
+pub mod page;
+pub mod query;
+pub mod recurrence;
 pub mod schema;
+pub mod search;
+pub mod snapshot;
 pub mod task;
 pub mod team;
 pub mod timer;
@@ -42,7 +82,9 @@ extern crate rocket_sync_db_pools;
 
 use rocket::fairing::AdHoc;
 use rocket::{Build, Rocket};
+use std::collections::HashMap;
 use std::error::Error;
+use std::time::Duration;
 
 use rocket::http::Method;
 use rocket_cors::{AllowedOrigins, CorsOptions};
@@ -97,6 +139,7 @@ async fn private_board(
                 id: None,
                 owner: user_id,
                 name: data.name.clone(),
+                version: 0,
             };
             match PrivateBoard::create(board, &connection).await {
                 Ok(cnt) => Ok(Json(cnt > 0)),
@@ -124,6 +167,7 @@ async fn team_board(
         id: None,
         owner: data.owner,
         name: data.name.clone(),
+        version: 0,
     };
     match TeamBoard::create(board, &connection).await {
         Ok(cnt) => Ok(Json(cnt > 0)),
@@ -160,14 +204,24 @@ async fn owned(connection: Connection, key: ApiKey) -> Result<Json<Vec<Team>>, S
     }
 }
 
-#[get("/private_board/get")]
+#[get("/private_board/get?<offset>&<limit>&<sort>")]
 async fn get_private_boards(
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
     connection: Connection,
     key: ApiKey,
-) -> Result<Json<Vec<PrivateBoard>>, Status> {
+) -> Result<Paged<PrivateBoard>, Status> {
+    let page = PageQuery {
+        offset,
+        limit,
+        sort,
+    }
+    .parse(&BOARD_SORT, "created")
+    .ok_or(Status::BadRequest)?;
     let user_id = User::get_username_id(key.0, &connection).await;
-    match User::get_private_boards(user_id.unwrap(), &connection).await {
-        Ok(boards) => Ok(Json(boards)),
+    match User::get_private_boards(user_id.unwrap(), page, &connection).await {
+        Ok((boards, total)) => Ok(Paged::new(boards, total)),
         Err(sth) => {
             println!("{:?}", sth);
             Err(Status::NotFound)
@@ -175,22 +229,56 @@ async fn get_private_boards(
     }
 }
 
-#[get("/team_board/get")]
+/// Team boards come from several teams, so they are sorted in memory
+#[get("/team_board/get?<offset>&<limit>&<sort>")]
 async fn get_team_boards(
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
     connection: Connection,
     key: ApiKey,
-) -> Result<Json<Vec<TeamBoardWithName>>, Status> {
+) -> Result<Paged<TeamBoardWithName>, Status> {
+    let page = PageQuery {
+        offset,
+        limit,
+        sort,
+    }
+    .parse(&BOARD_SORT, "created")
+    .ok_or(Status::BadRequest)?;
     let user_id = User::get_username_id(key.0, &connection).await;
-    match Team::get_teams_boards(user_id.unwrap(), &connection).await {
-        Ok(boards) => Ok(Json(boards)),
-        _ => Err(Status::NotFound),
+    let mut boards = match Team::get_teams_boards(user_id.unwrap(), &connection).await {
+        Ok(boards) => boards,
+        _ => return Err(Status::NotFound),
+    };
+    match page.field.as_str() {
+        "name" => boards.sort_by(|a, b| a.name.cmp(&b.name).then(a.id.cmp(&b.id))),
+        _ => boards.sort_by_key(|board| board.id),
     }
+    if page.descending {
+        boards.reverse();
+    }
+    let total = boards.len() as i64;
+    Ok(Paged::new(page.slice(boards), total))
 }
 
-#[get("/logs/get/<id>")]
-async fn get_logs(id: i32, connection: Connection, _key: ApiKey) -> Result<Json<Vec<Log>>, Status> {
-    match Log::get(id, &connection).await {
-        Ok(logs) => Ok(Json(logs)),
+#[get("/logs/get/<id>?<offset>&<limit>&<sort>")]
+async fn get_logs(
+    id: i32,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
+    connection: Connection,
+    _key: ApiKey,
+) -> Result<Paged<Log>, Status> {
+    let page = PageQuery {
+        offset,
+        limit,
+        sort,
+    }
+    .parse(&Log::SORT, "-created")
+    .ok_or(Status::BadRequest)?;
+    match Log::get(id, page, &connection).await {
+        Ok((logs, total)) => Ok(Paged::new(logs, total)),
         _ => Err(Status::NotFound),
     }
 }
@@ -198,14 +286,19 @@ async fn get_logs(id: i32, connection: Connection, _key: ApiKey) -> Result<Json<
 #[post("/new_list", data = "<data>")]
 async fn new_list(
     data: Json<List>,
+    events: &State<Events>,
     connection: Connection,
     _key: ApiKey,
 ) -> Result<Json<bool>, Status> {
-    let list = List {
+    let mut list = List {
         ..data.into_inner()
     };
-    match List::create(list, &connection).await {
-        Ok(cnt) => Ok(Json(cnt > 0)),
+    match List::create(list.clone(), &connection).await {
+        Ok(id) => {
+            list.id = Some(id);
+            events.list("created", &list);
+            Ok(Json(true))
+        }
         Err(x) => {
             println!("{:?}", x);
             Err(Status::NotFound)
@@ -226,35 +319,69 @@ async fn get_list(
     }
 }
 
-#[get("/task/get/<id>")]
+#[get("/task/get/<id>?<offset>&<limit>&<sort>")]
 async fn get_tasks(
     id: i32,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
     connection: Connection,
     _key: ApiKey,
-) -> Result<Json<Vec<Task>>, Status> {
-    match Task::get(id, &connection).await {
-        Ok(tasks) => Ok(Json(tasks)),
+) -> Result<Paged<TaskResponse>, Status> {
+    let page = PageQuery {
+        offset,
+        limit,
+        sort,
+    }
+    .parse(&Task::SORT, "created")
+    .ok_or(Status::BadRequest)?;
+    let (tasks, total) = Task::get_page(id, page, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    match Task::responses(tasks, &connection).await {
+        Ok(tasks) => Ok(Paged::new(tasks, total)),
         _ => Err(Status::NotFound),
     }
 }
 
+#[derive(Responder)]
+enum FilterError {
+    #[response(status = 400)]
+    Query(Json<ParseError>),
+    Status(Status),
+}
+
 #[post("/task/get/<id>", data = "<data>")]
 async fn filter_tasks(
     id: i32,
     data: Json<TaskFilter>,
     connection: Connection,
     _key: ApiKey,
-) -> Result<Json<Vec<Task>>, Status> {
-    match Task::filter(id, data.into_inner(), &connection).await {
-        Ok(tasks) => Ok(Json(tasks)),
-        _ => Err(Status::NotFound),
+) -> Result<Json<Vec<TaskResponse>>, FilterError> {
+    if let Err(e) = query::parse(&data.query) {
+        return Err(FilterError::Query(Json(e)));
+    }
+    let tasks = Task::filter(id, data.into_inner(), &connection).await;
+    match tasks {
+        Ok(tasks) => Task::responses(tasks, &connection)
+            .await
+            .map(Json)
+            .map_err(|_| FilterError::Status(Status::NotFound)),
+        _ => Err(FilterError::Status(Status::NotFound)),
     }
 }
 
 #[get("/task/<id>")]
-async fn get_task(id: i32, connection: Connection, _key: ApiKey) -> Result<Json<Task>, Status> {
-    match Task::get_single(id, &connection).await {
-        Ok(task) => Ok(Json(task)),
+async fn get_task(
+    id: i32,
+    connection: Connection,
+    _key: ApiKey,
+) -> Result<Json<TaskResponse>, Status> {
+    let task = Task::get_single(id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    match Task::responses(vec![task], &connection).await {
+        Ok(mut tasks) => Ok(Json(tasks.remove(0))),
         _ => Err(Status::NotFound),
     }
 }
@@ -262,9 +389,12 @@ async fn get_task(id: i32, connection: Connection, _key: ApiKey) -> Result<Json<
 #[get("/private/delete/<id>")]
 async fn delete_private(
     id: i32,
+    config: &State<AttachmentConfig>,
     connection: Connection,
     _key: ApiKey,
 ) -> Result<Json<bool>, Status> {
+    let _ =
+        Attachment::delete_board_attachments("private".to_string(), id, config, &connection).await;
     match PrivateBoard::delete(id, &connection).await {
         Ok(_) => Ok(Json(true)),
         _ => Err(Status::NotFound),
@@ -274,9 +404,11 @@ async fn delete_private(
 #[get("/team_board/delete/<id>")]
 async fn delete_team_board(
     id: i32,
+    config: &State<AttachmentConfig>,
     connection: Connection,
     _key: ApiKey,
 ) -> Result<Json<bool>, Status> {
+    let _ = Attachment::delete_board_attachments("team".to_string(), id, config, &connection).await;
     match TeamBoard::delete(id, &connection).await {
         Ok(_) => Ok(Json(true)),
         _ => Err(Status::NotFound),
@@ -284,9 +416,32 @@ async fn delete_team_board(
 }
 
 #[get("/task/delete/<id>")]
-async fn delete_task(id: i32, connection: Connection, _key: ApiKey) -> Result<Json<bool>, Status> {
+async fn delete_task(
+    id: i32,
+    config: &State<AttachmentConfig>,
+    events: &State<Events>,
+    connection: Connection,
+    _key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let task = Task::get_single(id, &connection).await;
+    let related = Task::dependencies(vec![id], &connection)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .map(|(task, blocker)| if task == id { blocker } else { task })
+        .chain(Task::children(id, &connection).await.unwrap_or_default())
+        .collect();
+    let _ = Attachment::delete_task_attachments(id, config, &connection).await;
     match Task::delete(id, &connection).await {
-        Ok(_) => Ok(Json(true)),
+        Ok(_) => {
+            if let Ok(task) = task {
+                events
+                    .task("deleted", &task, &[task.milestone], &connection)
+                    .await;
+            }
+            publish_tasks(related, events, &connection).await;
+            Ok(Json(true))
+        }
         _ => Err(Status::NotFound),
     }
 }
@@ -294,30 +449,366 @@ async fn delete_task(id: i32, connection: Connection, _key: ApiKey) -> Result<Js
 #[post("/task/create", data = "<data>")]
 async fn create_task(
     data: Json<Task>,
+    events: &State<Events>,
     connection: Connection,
     _ket: ApiKey,
 ) -> Result<Json<bool>, Status> {
-    let task = Task {
+    let mut task = Task {
         ..data.into_inner()
     };
-    match Task::create(task, &connection).await {
-        Ok(cnt) => Ok(Json(cnt > 0)),
+    if !valid_parent(&task, &connection).await {
+        return Err(Status::BadRequest);
+    }
+    match Task::create(task.clone(), &connection).await {
+        Ok(id) => {
+            task.id = Some(id);
+            events
+                .task("created", &task, &[task.milestone], &connection)
+                .await;
+            Ok(Json(true))
+        }
         _ => Err(Status::NotFound),
     }
 }
 
-#[post("/task/update", data = "<data>")]
+/// Update of a task or board made to an older version than the current one
+#[derive(Responder)]
+enum UpdateError<T: rocket::serde::Serialize> {
+    #[response(status = 409)]
+    Conflict(Json<T>), // The current version, for the client to resolve the conflict
+    #[response(status = 400)]
+    Invalid(Json<FieldError>),
+    Status(Status),
+}
+
+/// Marking a task done while its blockers are open needs `force`
+#[post("/task/update?<force>", data = "<data>")]
 async fn update_task(
     data: Json<Task>,
+    force: Option<bool>,
+    if_match: IfMatch,
+    events: &State<Events>,
     connection: Connection,
     _ket: ApiKey,
-) -> Result<Json<bool>, Status> {
-    let task = Task {
+) -> Result<Json<bool>, UpdateError<Task>> {
+    let mut task = Task {
+        version: if_match.0.unwrap_or(data.version),
         ..data.into_inner()
     };
-    match Task::update(task, &connection).await {
-        Ok(cnt) => Ok(Json(cnt > 0)),
-        _ => Err(Status::NotFound),
+    let previous = match task.id {
+        Some(id) => Task::get_single(id, &connection).await.ok(),
+        None => None,
+    };
+    if let Some(previous) = &previous {
+        if is_blocked(previous, task.done, force, &connection).await {
+            return Err(UpdateError::Invalid(Json(blocked_error())));
+        }
+    }
+    if !valid_parent(&task, &connection).await {
+        return Err(UpdateError::Invalid(Json(parent_error())));
+    }
+    match (Task::update(task.clone(), &connection).await, previous) {
+        (Ok(0), Some(current)) => Err(UpdateError::Conflict(Json(current))),
+        (Ok(0), None) | (Err(_), _) => Err(UpdateError::Status(Status::NotFound)),
+        (Ok(_), previous) => {
+            task.version += 1;
+            let done = previous.as_ref().map(|previous| previous.done);
+            let parent = previous.as_ref().and_then(|previous| previous.parent);
+            let previous = previous.and_then(|previous| previous.milestone);
+            events
+                .task("updated", &task, &[previous, task.milestone], &connection)
+                .await;
+            if done != Some(task.done) {
+                publish_blocked(task.id.unwrap_or_default(), events, &connection).await;
+            }
+            if parent != task.parent {
+                publish_tasks(parent.into_iter().collect(), events, &connection).await;
+            }
+            Ok(Json(true))
+        }
+    }
+}
+
+/// JSON Merge Patch of a task, only the fields sent are changed and logged
+#[patch("/task/<id>?<force>", data = "<data>")]
+async fn patch_task(
+    id: i32,
+    data: Json<serde_json::Value>,
+    force: Option<bool>,
+    if_match: IfMatch,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Task>, UpdateError<Task>> {
+    let not_found = || UpdateError::Status(Status::NotFound);
+    let invalid =
+        |field: &str, message: &str| UpdateError::Invalid(Json(FieldError::new(field, message)));
+    let current = Task::get_single(id, &connection)
+        .await
+        .map_err(|_| not_found())?;
+    let board = List::get_by_id(current.list, &connection)
+        .await
+        .map_err(|_| not_found())?;
+    if get_board_user(&board.board_type, board.board, key, &connection)
+        .await
+        .is_none()
+    {
+        return Err(not_found());
+    }
+    let patch = serde_json::from_value::<TaskPatch>(data.into_inner())
+        .map_err(|error| invalid("", &error.to_string()))?;
+    if if_match.0.or(patch.version).unwrap_or(current.version) != current.version {
+        return Err(UpdateError::Conflict(Json(current)));
+    }
+    let mut task = patch
+        .apply(current.clone())
+        .map_err(|error| UpdateError::Invalid(Json(error)))?;
+    if is_blocked(&current, task.done, force, &connection).await {
+        return Err(UpdateError::Invalid(Json(blocked_error())));
+    }
+    if task.parent != current.parent && !valid_parent(&task, &connection).await {
+        return Err(UpdateError::Invalid(Json(parent_error())));
+    }
+    // Tasks stay on their board
+    if task.list != current.list {
+        match List::get_by_id(task.list, &connection).await {
+            Ok(list) if list.board_type == board.board_type && list.board == board.board => {}
+            _ => return Err(invalid("list", "isn't a list of the task's board")),
+        }
+    }
+    if let (Some(milestone), true) = (task.milestone, task.milestone != current.milestone) {
+        match Milestone::get_by_id(milestone, &connection).await {
+            Ok(m) if m.board_type == board.board_type && m.board_id == board.board => {}
+            _ => {
+                return Err(invalid(
+                    "milestone",
+                    "isn't a milestone of the task's board",
+                ))
+            }
+        }
+    }
+    let changes = serde_json::to_string(&patch).unwrap();
+    match Task::patch(task.clone(), changes, &connection).await {
+        Ok(0) => match Task::get_single(id, &connection).await {
+            Ok(current) => Err(UpdateError::Conflict(Json(current))),
+            Err(_) => Err(not_found()),
+        },
+        Ok(_) => {
+            task.version += 1;
+            events
+                .task(
+                    "updated",
+                    &task,
+                    &[current.milestone, task.milestone],
+                    &connection,
+                )
+                .await;
+            if current.done != task.done {
+                publish_blocked(id, events, &connection).await;
+            }
+            if current.parent != task.parent {
+                publish_tasks(current.parent.into_iter().collect(), events, &connection).await;
+            }
+            Ok(Json(task))
+        }
+        Err(_) => Err(not_found()),
+    }
+}
+
+/// Whether the task can't be marked done, some of its blockers aren't done and the caller
+/// didn't force it
+async fn is_blocked(
+    current: &Task,
+    done: i32,
+    force: Option<bool>,
+    connection: &Connection,
+) -> bool {
+    if done != 1 || current.done == 1 || force == Some(true) {
+        return false;
+    }
+    match Task::responses(vec![current.clone()], connection).await {
+        Ok(tasks) => tasks[0].blocked,
+        Err(_) => false,
+    }
+}
+
+fn blocked_error() -> FieldError {
+    FieldError::new("done", "some of the blocking tasks aren't done")
+}
+
+/// Whether the parent is a task of the same board which isn't among the task's descendants
+async fn valid_parent(task: &Task, connection: &Connection) -> bool {
+    let parent = match task.parent {
+        Some(parent) => parent,
+        None => return true,
+    };
+    let parent_list = match Task::get_single(parent, connection).await {
+        Ok(parent) => List::get_by_id(parent.list, connection).await,
+        Err(e) => Err(e),
+    };
+    match (List::get_by_id(task.list, connection).await, parent_list) {
+        (Ok(list), Ok(other))
+            if list.board_type == other.board_type && list.board == other.board =>
+        {
+            matches!(
+                Task::can_have_parent(task.id, parent, connection).await,
+                Ok(true)
+            )
+        }
+        _ => false,
+    }
+}
+
+fn parent_error() -> FieldError {
+    FieldError::new(
+        "parent",
+        "isn't a task of the board, or is one of the task's children",
+    )
+}
+
+/// Sends the tasks as they're saved now, their dependencies changed
+async fn publish_tasks(mut ids: Vec<i32>, events: &Events, connection: &Connection) {
+    ids.sort_unstable();
+    ids.dedup();
+    for id in ids {
+        if let Ok(task) = Task::get_single(id, connection).await {
+            events.task("updated", &task, &[], connection).await;
+        }
+    }
+}
+
+/// Sends the tasks the task blocks, after it was done or reopened
+async fn publish_blocked(id: i32, events: &Events, connection: &Connection) {
+    let blocked = Task::dependencies(vec![id], connection)
+        .await
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|&(_, blocker)| blocker == id)
+        .map(|(task, _)| task)
+        .collect();
+    publish_tasks(blocked, events, connection).await;
+}
+
+/// Replaces the tasks blocking the task, they can be on any list of its board
+#[post("/task/blockers/<id>", data = "<data>")]
+async fn task_blockers(
+    id: i32,
+    data: Json<TaskBlockers>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let task = Task::get_single(id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    let board = List::get_by_id(task.list, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    if get_board_user(&board.board_type, board.board, key, &connection)
+        .await
+        .is_none()
+    {
+        return Err(Status::NotFound);
+    }
+    for &blocker in data.blockers.iter() {
+        let list = match Task::get_single(blocker, &connection).await {
+            Ok(blocker) => List::get_by_id(blocker.list, &connection).await.ok(),
+            Err(_) => None,
+        };
+        match list {
+            Some(list) if list.board_type == board.board_type && list.board == board.board => {}
+            _ => return Err(Status::NotFound),
+        }
+    }
+    // The previous and the new blockers list the task in `blocks`
+    let mut changed = Task::dependencies(vec![id], &connection)
+        .await
+        .map_err(|_| Status::NotFound)?
+        .into_iter()
+        .filter(|&(task, _)| task == id)
+        .map(|(_, blocker)| blocker)
+        .collect::<Vec<_>>();
+    changed.extend(data.blockers.iter().chain([&id]));
+    match Task::set_blockers(id, data.into_inner().blockers, &connection).await {
+        Ok(true) => {
+            publish_tasks(changed, events, &connection).await;
+            Ok(Json(true))
+        }
+        Ok(false) => Err(Status::BadRequest),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+/// The task with its list, when the user can see its board
+async fn get_task_access(id: i32, key: ApiKey, connection: &Connection) -> Option<(Task, List)> {
+    let task = Task::get_single(id, connection).await.ok()?;
+    let list = List::get_by_id(task.list, connection).await.ok()?;
+    get_board_user(&list.board_type, list.board, key, connection).await?;
+    Some((task, list))
+}
+
+/// Makes the task repeat, its next instances are created on the list by the scheduler
+#[post("/recurrence/set/<id>", data = "<data>")]
+async fn set_recurrence(
+    id: i32,
+    data: Json<RecurrenceData>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, UpdateError<Task>> {
+    let (task, board) = get_task_access(id, key, &connection)
+        .await
+        .ok_or(UpdateError::Status(Status::NotFound))?;
+    match List::get_by_id(data.list, &connection).await {
+        Ok(list) if list.board_type == board.board_type && list.board == board.board => {}
+        _ => {
+            return Err(UpdateError::Invalid(Json(FieldError::new(
+                "list",
+                "isn't a list of the task's board",
+            ))))
+        }
+    }
+    let rule = Rule::parse(&data.rule)
+        .map_err(|message| UpdateError::Invalid(Json(FieldError::new("rule", &message))))?;
+    let mut text = data.rule.trim().to_owned();
+    // Monthly series keep the day of the first deadline in shorter months
+    if let (Frequency::Monthly, None, Ok(deadline)) = (
+        rule.frequency,
+        rule.month_day,
+        NaiveDate::parse_from_str(&task.deadline, "%Y-%m-%d"),
+    ) {
+        text = format!(
+            "{};BYMONTHDAY={}",
+            text.trim_end_matches(';'),
+            deadline.day()
+        );
+    }
+    match Recurrence::set(id, text, data.list, &connection).await {
+        Ok(_) => {
+            events.task("updated", &task, &[], &connection).await;
+            Ok(Json(true))
+        }
+        Err(_) => Err(UpdateError::Status(Status::NotFound)),
+    }
+}
+
+/// Stops the series of the task, the instances created so far stay
+#[get("/recurrence/delete/<id>")]
+async fn delete_recurrence(
+    id: i32,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let (task, _) = get_task_access(id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    match Recurrence::delete(id, &connection).await {
+        Ok(_) => {
+            events.task("updated", &task, &[], &connection).await;
+            Ok(Json(true))
+        }
+        Err(_) => Err(Status::NotFound),
     }
 }
 
@@ -325,12 +816,21 @@ async fn update_task(
 async fn update_private(
     data: Json<BoardUpdate>,
     id: i32,
+    if_match: IfMatch,
     connection: Connection,
     _ket: ApiKey,
-) -> Result<Json<bool>, Status> {
-    match PrivateBoard::update(data.into_inner(), id, &connection).await {
-        Ok(cnt) => Ok(Json(cnt > 0)),
-        _ => Err(Status::NotFound),
+) -> Result<Json<bool>, UpdateError<PrivateBoard>> {
+    let board = BoardUpdate {
+        version: if_match.0.unwrap_or(data.version),
+        ..data.into_inner()
+    };
+    match PrivateBoard::update(board, id, &connection).await {
+        Ok(0) => match PrivateBoard::get_by_id(id, &connection).await {
+            Ok(current) => Err(UpdateError::Conflict(Json(current))),
+            Err(_) => Err(UpdateError::Status(Status::NotFound)),
+        },
+        Ok(_) => Ok(Json(true)),
+        _ => Err(UpdateError::Status(Status::NotFound)),
     }
 }
 
@@ -338,19 +838,41 @@ async fn update_private(
 async fn update_team(
     data: Json<BoardUpdate>,
     id: i32,
+    if_match: IfMatch,
     connection: Connection,
     _ket: ApiKey,
-) -> Result<Json<bool>, Status> {
-    match TeamBoard::update(data.into_inner(), id, &connection).await {
-        Ok(cnt) => Ok(Json(cnt > 0)),
-        _ => Err(Status::NotFound),
+) -> Result<Json<bool>, UpdateError<TeamBoard>> {
+    let board = BoardUpdate {
+        version: if_match.0.unwrap_or(data.version),
+        ..data.into_inner()
+    };
+    match TeamBoard::update(board, id, &connection).await {
+        Ok(0) => match TeamBoard::get_by_id(id, &connection).await {
+            Ok(current) => Err(UpdateError::Conflict(Json(current))),
+            Err(_) => Err(UpdateError::Status(Status::NotFound)),
+        },
+        Ok(_) => Ok(Json(true)),
+        _ => Err(UpdateError::Status(Status::NotFound)),
     }
 }
 
 #[get("/list_delete/<id>")]
-async fn delete_list(id: i32, connection: Connection, _ket: ApiKey) -> Result<Json<bool>, Status> {
+async fn delete_list(
+    id: i32,
+    config: &State<AttachmentConfig>,
+    events: &State<Events>,
+    connection: Connection,
+    _ket: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let list = List::get_by_id(id, &connection).await;
+    let _ = Attachment::delete_list_attachments(vec![id], config, &connection).await;
     match List::delete(id, &connection).await {
-        Ok(cnt) => Ok(Json(cnt > 0)),
+        Ok(cnt) => {
+            if let (Ok(list), true) = (list, cnt > 0) {
+                events.list("deleted", &list);
+            }
+            Ok(Json(cnt > 0))
+        }
         _ => Err(Status::NotFound),
     }
 }
@@ -365,15 +887,33 @@ async fn timer_create(
     if user_id.is_none() {
         return Err(Status::NotFound);
     }
-    let name = data.into_inner().name;
+    let data = data.into_inner();
+    let (kind, config) = match data.pomodoro {
+        Some(config) if config.is_valid() => ("pomodoro", config),
+        Some(_) => return Err(Status::BadRequest),
+        None => ("simple", PomodoroConfig::default()),
+    };
     let time = get_time();
     let timer = Timer {
         id: None,
-        name,
+        name: data.name,
         user_id: user_id.unwrap(),
         status: "active".to_owned(),
         time: 0,
         start: Some(time),
+        review: 0,
+        kind: kind.to_owned(),
+        work: config.work,
+        short_break: config.short_break,
+        long_break: config.long_break,
+        cycles: config.cycles,
+        pomodoros: 0,
+        category: None,
+        billable: 0,
+        client: None,
+        board_id: None,
+        board_type: None,
+        review_session: None,
     };
     match Timer::create(timer, &connection).await {
         Ok(cnt) => Ok(Json(cnt > 0)),
@@ -404,54 +944,1336 @@ async fn timer_update(id: i32, connection: Connection, _key: ApiKey) -> Result<J
     }
 }
 
-#[get("/timers/get")]
-async fn get_timers(connection: Connection, key: ApiKey) -> Result<Json<Vec<Timer>>, Status> {
+#[get("/timer/start/<id>")]
+async fn timer_start(id: i32, connection: Connection, key: ApiKey) -> Result<Json<bool>, Status> {
+    set_timer_status(id, "active", connection, key).await
+}
+
+#[get("/timer/pause/<id>")]
+async fn timer_pause(id: i32, connection: Connection, key: ApiKey) -> Result<Json<bool>, Status> {
+    set_timer_status(id, "paused", connection, key).await
+}
+
+#[get("/timer/stop/<id>")]
+async fn timer_stop(id: i32, connection: Connection, key: ApiKey) -> Result<Json<bool>, Status> {
+    set_timer_status(id, "stopped", connection, key).await
+}
+
+async fn set_timer_status(
+    id: i32,
+    status: &'static str,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let timer = get_own_timer(id, key, &connection).await;
+    if timer.is_none() {
+        return Err(Status::NotFound);
+    }
+    match Timer::set_status(timer.unwrap(), status, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timers/get?<category>&<offset>&<limit>&<sort>")]
+async fn get_timers(
+    category: Option<i32>,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Paged<Timer>, Status> {
+    let page = PageQuery {
+        offset,
+        limit,
+        sort,
+    }
+    .parse(&Timer::SORT, "created")
+    .ok_or(Status::BadRequest)?;
     let user_id = User::get_username_id(key.0, &connection).await;
     if user_id.is_none() {
         return Err(Status::NotFound);
     }
-    match Timer::get_timers(user_id.unwrap(), &connection).await {
-        Ok(timers) => Ok(Json(timers)),
+    match Timer::get_timers_page(user_id.unwrap(), category, page, &connection).await {
+        Ok((timers, total)) => Ok(Paged::new(timers, total)),
         _ => Err(Status::NotFound),
     }
 }
 
-#[get("/milestone/get/<id>/<board_type>")]
-async fn get_milestones(
+/// Timer with the given id, if it belongs to the caller
+async fn get_own_timer(id: i32, key: ApiKey, connection: &Connection) -> Option<Timer> {
+    let user_id = User::get_username_id(key.0, connection).await;
+    match Timer::get_by_id(id, connection).await {
+        Ok(timer) if user_id == Some(timer.user_id) => Some(timer),
+        _ => None,
+    }
+}
+
+#[post("/timer/pomodoro/<id>", data = "<data>")]
+async fn timer_pomodoro(
     id: i32,
-    board_type: String,
+    data: Json<PomodoroConfig>,
     connection: Connection,
-    _key: ApiKey,
-) -> Result<Json<Vec<MilestoneResponse>>, Status> {
-    match Milestone::get(id, board_type, &connection).await {
-        Ok(milestones) => Ok(Json(milestones)),
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    if get_own_timer(id, key, &connection).await.is_none() {
+        return Err(Status::NotFound);
+    }
+    if !data.is_valid() {
+        return Err(Status::BadRequest);
+    }
+    match Timer::set_pomodoro(id, data.into_inner(), &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
         _ => Err(Status::NotFound),
     }
 }
 
-#[post("/milestone/create", data = "<data>")]
-async fn milestone_create(
-    data: Json<Milestone>,
+#[get("/timer/settings")]
+async fn get_timer_settings(
     connection: Connection,
-    _key: ApiKey,
+    key: ApiKey,
+) -> Result<Json<TimerSettingsData>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match TimerSettings::get(user_id.unwrap(), &connection).await {
+        Ok(settings) => Ok(Json(TimerSettingsData {
+            idle_limit: settings.idle_limit,
+        })),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/timer/settings", data = "<data>")]
+async fn timer_settings(
+    data: Json<TimerSettingsData>,
+    connection: Connection,
+    key: ApiKey,
 ) -> Result<Json<bool>, Status> {
-    match Milestone::create(data.into_inner(), &connection).await {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    if data.idle_limit <= 0 {
+        return Err(Status::BadRequest);
+    }
+    let settings = TimerSettings {
+        id: None,
+        user_id: user_id.unwrap(),
+        idle_limit: data.idle_limit,
+    };
+    match TimerSettings::set(settings, &connection).await {
         Ok(cnt) => Ok(Json(cnt > 0)),
-        Err(_) => Err(Status::NotFound),
+        _ => Err(Status::NotFound),
     }
 }
 
-async fn run_migrations(rocket: Rocket<Build>) -> Rocket<Build> {
-    embed_migrations!();
+#[post("/timer/review/<id>", data = "<data>")]
+async fn timer_review(
+    id: i32,
+    data: Json<TimerReview>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let timer = get_own_timer(id, key, &connection).await;
+    if timer.is_none() {
+        return Err(Status::NotFound);
+    }
+    if !data.is_valid() || timer.as_ref().unwrap().review == 0 {
+        return Err(Status::BadRequest);
+    }
+    match Timer::review(timer.unwrap(), data.session, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
 
-    let conn = Connection::get_one(&rocket)
-        .await
-        .expect("database connection");
-    conn.run(|c| embedded_migrations::run(c))
-        .await
-        .expect("can run migrations");
+#[get("/timer/categories")]
+async fn get_categories(
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<TimerCategory>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match TimerCategory::get(user_id.unwrap(), &connection).await {
+        Ok(categories) => Ok(Json(categories)),
+        _ => Err(Status::NotFound),
+    }
+}
 
-    rocket
+#[post("/timer/category/create", data = "<data>")]
+async fn category_create(
+    data: Json<CategoryData>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    if data.name.is_empty() || !is_color(&data.color) {
+        return Err(Status::BadRequest);
+    }
+    if let Some(team) = data.team {
+        if Team::has_access(team, user_id.unwrap(), &connection)
+            .await
+            .is_err()
+        {
+            return Err(Status::NotFound);
+        }
+    }
+    let data = data.into_inner();
+    let category = TimerCategory {
+        id: None,
+        name: data.name,
+        color: data.color,
+        user_id: if data.team.is_none() { user_id } else { None },
+        team_id: data.team,
+    };
+    match TimerCategory::create(category, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timer/category/delete/<id>")]
+async fn category_delete(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() || !TimerCategory::is_visible(id, user_id.unwrap(), &connection).await {
+        return Err(Status::NotFound);
+    }
+    match TimerCategory::delete(id, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/timer/category/<id>", data = "<data>")]
+async fn timer_category(
+    id: i32,
+    data: Json<CategoryAssignment>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let timer = get_own_timer(id, key, &connection).await;
+    if timer.is_none() {
+        return Err(Status::NotFound);
+    }
+    if let Some(category) = data.category {
+        if !TimerCategory::is_visible(category, timer.unwrap().user_id, &connection).await {
+            return Err(Status::NotFound);
+        }
+    }
+    match Timer::set_category(id, data.category, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timer/sessions/<id>")]
+async fn timer_sessions(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<TimerSession>>, Status> {
+    if get_own_timer(id, key, &connection).await.is_none() {
+        return Err(Status::NotFound);
+    }
+    match TimerSession::get(id, &connection).await {
+        Ok(sessions) => Ok(Json(sessions)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/timer/session/category/<id>", data = "<data>")]
+async fn session_category(
+    id: i32,
+    data: Json<CategoryAssignment>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    let session = TimerSession::get_by_id(id, &connection).await;
+    if session.is_err() || user_id != Some(session.unwrap().user_id) {
+        return Err(Status::NotFound);
+    }
+    if let Some(category) = data.category {
+        if !TimerCategory::is_visible(category, user_id.unwrap(), &connection).await {
+            return Err(Status::NotFound);
+        }
+    }
+    match TimerSession::set_category(id, data.category, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timers/report?<from>&<to>")]
+async fn timers_report(
+    from: Option<i32>,
+    to: Option<i32>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<CategoryReport>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match TimerCategory::report(user_id.unwrap(), from, to, &connection).await {
+        Ok(report) => Ok(Json(report)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timer/goals")]
+async fn get_goals(connection: Connection, key: ApiKey) -> Result<Json<Vec<TimerGoal>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match TimerGoal::get(user_id.unwrap(), &connection).await {
+        Ok(goals) => Ok(Json(goals)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/timer/goal/create", data = "<data>")]
+async fn goal_create(
+    data: Json<GoalData>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    if !data.is_valid() {
+        return Err(Status::BadRequest);
+    }
+    if let Some(category) = data.category {
+        if !TimerCategory::is_visible(category, user_id.unwrap(), &connection).await {
+            return Err(Status::NotFound);
+        }
+    }
+    let data = data.into_inner();
+    let goal = TimerGoal {
+        id: None,
+        user_id: user_id.unwrap(),
+        period: data.period,
+        category: data.category,
+        target: data.target,
+    };
+    match TimerGoal::create(goal, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timer/goal/delete/<id>")]
+async fn goal_delete(id: i32, connection: Connection, key: ApiKey) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    let goal = TimerGoal::get_by_id(id, &connection).await;
+    if goal.is_err() || user_id != Some(goal.unwrap().user_id) {
+        return Err(Status::NotFound);
+    }
+    match TimerGoal::delete(id, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/timer/goals/progress")]
+async fn goals_progress(
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<GoalProgress>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match TimerGoal::progress(user_id.unwrap(), &connection).await {
+        Ok(progress) => Ok(Json(progress)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/timer/billing/<id>", data = "<data>")]
+async fn timer_billing(
+    id: i32,
+    data: Json<TimerBilling>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let timer = get_own_timer(id, key, &connection).await;
+    if timer.is_none() {
+        return Err(Status::NotFound);
+    }
+    if data.board_type.is_some() != data.board_id.is_some() {
+        return Err(Status::BadRequest);
+    }
+    if let (Some(board_type), Some(board_id)) = (&data.board_type, data.board_id) {
+        if !has_board_access(board_type, board_id, timer.unwrap().user_id, &connection).await {
+            return Err(Status::NotFound);
+        }
+    }
+    match Timer::set_billing(id, data.into_inner(), &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/timer/session/billable/<id>", data = "<data>")]
+async fn session_billable(
+    id: i32,
+    data: Json<SessionBilling>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    let session = TimerSession::get_by_id(id, &connection).await;
+    if session.is_err() || user_id != Some(session.unwrap().user_id) {
+        return Err(Status::NotFound);
+    }
+    match TimerSession::set_billable(id, data.billable, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/billing/rates")]
+async fn get_rates(connection: Connection, key: ApiKey) -> Result<Json<Vec<BillingRate>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match BillingRate::get(user_id.unwrap(), &connection).await {
+        Ok(rates) => Ok(Json(rates)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/billing/rate", data = "<data>")]
+async fn set_rate(
+    data: Json<RateData>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    let user_id = user_id.unwrap();
+    if !data.is_valid() {
+        return Err(Status::BadRequest);
+    }
+    let allowed = match data.scope.as_str() {
+        "user" => true,
+        "team" => Team::has_access(data.scope_id, user_id, &connection)
+            .await
+            .is_ok(),
+        "private_board" => has_board_access("private", data.scope_id, user_id, &connection).await,
+        _ => has_board_access("team", data.scope_id, user_id, &connection).await,
+    };
+    if !allowed {
+        return Err(Status::NotFound);
+    }
+    let data = data.into_inner();
+    let rate = BillingRate {
+        id: None,
+        scope_id: if data.scope == "user" {
+            user_id
+        } else {
+            data.scope_id
+        },
+        scope: data.scope,
+        rate: data.rate,
+        currency: data.currency.to_uppercase(),
+        rounding: data.rounding,
+        rounding_mode: data.rounding_mode,
+    };
+    match BillingRate::set(rate, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/billing/rate/delete/<id>")]
+async fn rate_delete(id: i32, connection: Connection, key: ApiKey) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() || !BillingRate::is_visible(id, user_id.unwrap(), &connection).await {
+        return Err(Status::NotFound);
+    }
+    match BillingRate::delete(id, &connection).await {
+        Ok(cnt) => Ok(Json(cnt > 0)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/billing/summary?<from>&<to>&<client>")]
+async fn billing_summary(
+    from: Option<i32>,
+    to: Option<i32>,
+    client: Option<String>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<BillingSummary>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match BillingRate::summary(user_id.unwrap(), from, to, client, &connection).await {
+        Ok(summary) => Ok(Json(summary)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/billing/export/<format>?<from>&<to>&<client>")]
+async fn billing_export(
+    format: String,
+    from: Option<i32>,
+    to: Option<i32>,
+    client: Option<String>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<(ContentType, Vec<u8>), Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    let summary = BillingRate::summary(user_id.unwrap(), from, to, client, &connection).await;
+    if summary.is_err() {
+        return Err(Status::NotFound);
+    }
+    let summary = summary.unwrap();
+    match format.as_str() {
+        "csv" => Ok((ContentType::CSV, to_csv(&summary).into_bytes())),
+        "pdf" => {
+            let title = format!(
+                "Invoice {} - {}",
+                from.map_or("".to_owned(), billing::format_date),
+                to.map_or("".to_owned(), billing::format_date)
+            );
+            Ok((ContentType::PDF, to_pdf(&invoice_lines(&summary, &title))))
+        }
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/milestone/get/<id>/<board_type>")]
+async fn get_milestones(
+    id: i32,
+    board_type: String,
+    connection: Connection,
+    _key: ApiKey,
+) -> Result<Json<Vec<MilestoneResponse>>, Status> {
+    match Milestone::get(id, board_type, &connection).await {
+        Ok(milestones) => Ok(Json(milestones)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[post("/milestone/create", data = "<data>")]
+async fn milestone_create(
+    data: Json<Milestone>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none()
+        || !has_board_access(
+            &data.board_type,
+            data.board_id,
+            user_id.unwrap(),
+            &connection,
+        )
+        .await
+    {
+        return Err(Status::NotFound);
+    }
+    if !data.is_valid() {
+        return Err(Status::BadRequest);
+    }
+    let (board_type, board_id) = (data.board_type.clone(), data.board_id);
+    match Milestone::create(data.into_inner(), &connection).await {
+        Ok(id) => {
+            events
+                .milestone("created", id, &board_type, board_id, &connection)
+                .await;
+            Ok(Json(true))
+        }
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+/// Milestone with the given id, if the caller can access its board
+async fn get_milestone(id: i32, key: ApiKey, connection: &Connection) -> Option<Milestone> {
+    let user_id = User::get_username_id(key.0, connection).await?;
+    get_user_milestone(id, user_id, connection).await
+}
+
+async fn get_user_milestone(id: i32, user_id: i32, connection: &Connection) -> Option<Milestone> {
+    let milestone = Milestone::get_by_id(id, connection).await.ok()?;
+    if has_board_access(
+        &milestone.board_type,
+        milestone.board_id,
+        user_id,
+        connection,
+    )
+    .await
+    {
+        Some(milestone)
+    } else {
+        None
+    }
+}
+
+#[post("/milestone/update/<id>", data = "<data>")]
+async fn milestone_update(
+    id: i32,
+    data: Json<MilestoneUpdate>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let milestone = match get_milestone(id, key, &connection).await {
+        Some(milestone) => milestone,
+        None => return Err(Status::NotFound),
+    };
+    if !data.is_valid() {
+        return Err(Status::BadRequest);
+    }
+    match Milestone::update(id, data.into_inner(), &connection).await {
+        Ok(cnt) => {
+            events
+                .milestone(
+                    "updated",
+                    id,
+                    &milestone.board_type,
+                    milestone.board_id,
+                    &connection,
+                )
+                .await;
+            Ok(Json(cnt > 0))
+        }
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/milestone/delete/<id>")]
+async fn milestone_delete(
+    id: i32,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let milestone = match get_milestone(id, key, &connection).await {
+        Some(milestone) => milestone,
+        None => return Err(Status::NotFound),
+    };
+    match Milestone::delete(id, &connection).await {
+        Ok(cnt) => {
+            // Clients clear the milestone of its tasks themselves
+            events
+                .milestone(
+                    "deleted",
+                    id,
+                    &milestone.board_type,
+                    milestone.board_id,
+                    &connection,
+                )
+                .await;
+            Ok(Json(cnt > 0))
+        }
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/milestone/tasks/<id>")]
+async fn milestone_tasks(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<MilestoneTasks>, Status> {
+    let milestone = get_milestone(id, key, &connection).await;
+    if milestone.is_none() {
+        return Err(Status::NotFound);
+    }
+    match Milestone::get_tasks(milestone.unwrap(), &connection).await {
+        Ok(tasks) => Ok(Json(tasks)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[post("/milestone/tasks/<id>", data = "<data>")]
+async fn milestone_set_tasks(
+    id: i32,
+    data: Json<MilestoneTasksUpdate>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let milestone = match get_milestone(id, key, &connection).await {
+        Some(milestone) => milestone,
+        None => return Err(Status::NotFound),
+    };
+    let (board_type, board_id) = (milestone.board_type.clone(), milestone.board_id);
+    let tasks = Milestone::get_tasks(milestone, &connection).await;
+    if tasks.is_err() {
+        return Err(Status::NotFound);
+    }
+    // Tasks from the milestone's board can be added, only its own tasks removed
+    let (mut assigned, mut available) = (HashMap::new(), HashMap::new());
+    for list in tasks.unwrap().lists {
+        assigned.extend(list.tasks.into_iter().map(|task| (task.id.unwrap(), task)));
+        available.extend(
+            list.available
+                .into_iter()
+                .map(|task| (task.id.unwrap(), task)),
+        );
+    }
+    let take = |tasks: &mut HashMap<i32, Task>, ids: &Vec<i32>| {
+        ids.iter()
+            .map(|id| tasks.remove(id))
+            .collect::<Option<Vec<_>>>()
+    };
+    let (add, remove) = (
+        take(&mut available, &data.add),
+        take(&mut assigned, &data.remove),
+    );
+    if add.is_none() || remove.is_none() {
+        return Err(Status::BadRequest);
+    }
+    let (add, remove) = (add.unwrap(), remove.unwrap());
+    let moved = add
+        .iter()
+        .map(|task| (task.clone(), Some(id)))
+        .chain(remove.iter().map(|task| (task.clone(), None)))
+        .collect::<Vec<_>>();
+    match Milestone::set_tasks(id, add, remove, &connection).await {
+        Ok(cnt) => {
+            let mut milestones = vec![id];
+            for (mut task, milestone) in moved {
+                milestones.extend(task.milestone);
+                task.milestone = milestone;
+                task.version += 1;
+                events.task("updated", &task, &[], &connection).await;
+            }
+            milestones.sort_unstable();
+            milestones.dedup();
+            for milestone in milestones {
+                events
+                    .milestone("updated", milestone, &board_type, board_id, &connection)
+                    .await;
+            }
+            Ok(Json(cnt > 0))
+        }
+        // Somebody changed one of the tasks meanwhile
+        Err(diesel::result::Error::RollbackTransaction) => Err(Status::Conflict),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/milestone/burndown/<id>")]
+async fn milestone_burndown(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Burndown>, Status> {
+    let milestone = get_milestone(id, key, &connection).await;
+    if milestone.is_none() {
+        return Err(Status::NotFound);
+    }
+    match Burndown::get(milestone.unwrap(), &connection).await {
+        Ok(burndown) => Ok(Json(burndown)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[post("/milestone/predecessors/<id>", data = "<data>")]
+async fn milestone_predecessors(
+    id: i32,
+    data: Json<MilestonePredecessors>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = match User::get_username_id(key.0, &connection).await {
+        Some(user_id) => user_id,
+        None => return Err(Status::NotFound),
+    };
+    let mut board = None;
+    for milestone in data.predecessors.iter().chain([&id]) {
+        match get_user_milestone(*milestone, user_id, &connection).await {
+            Some(milestone) => board = Some((milestone.board_type, milestone.board_id)),
+            None => return Err(Status::NotFound),
+        }
+    }
+    match Milestone::set_predecessors(id, data.into_inner().predecessors, &connection).await {
+        Ok(true) => {
+            if let Some((board_type, board_id)) = board {
+                events
+                    .milestone("updated", id, &board_type, board_id, &connection)
+                    .await;
+            }
+            Ok(Json(true))
+        }
+        Ok(false) => Err(Status::BadRequest),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/roadmap/board/<board_type>/<id>")]
+async fn board_roadmap(
+    board_type: String,
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<RoadmapItem>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() || !has_board_access(&board_type, id, user_id.unwrap(), &connection).await
+    {
+        return Err(Status::NotFound);
+    }
+    let name = match board_type.as_str() {
+        "private" => PrivateBoard::get_by_id(id, &connection)
+            .await
+            .map(|b| b.name),
+        _ => TeamBoard::get_by_id(id, &connection).await.map(|b| b.name),
+    };
+    if name.is_err() {
+        return Err(Status::NotFound);
+    }
+    match Milestone::roadmap(vec![(board_type, id, name.unwrap())], &connection).await {
+        Ok(roadmap) => Ok(Json(roadmap)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/roadmap/team/<id>")]
+async fn team_roadmap(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<RoadmapItem>>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none()
+        || Team::has_access(id, user_id.unwrap(), &connection)
+            .await
+            .is_err()
+    {
+        return Err(Status::NotFound);
+    }
+    let boards = match TeamBoard::get_by_team(id, &connection).await {
+        Ok(boards) => boards,
+        Err(_) => return Err(Status::NotFound),
+    };
+    let boards = boards
+        .into_iter()
+        .map(|board| ("team".to_owned(), board.id.unwrap(), board.name))
+        .collect();
+    match Milestone::roadmap(boards, &connection).await {
+        Ok(roadmap) => Ok(Json(roadmap)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/search?<q>&<board_type>&<board>&<limit>")]
+async fn search_tasks(
+    q: String,
+    board_type: Option<String>,
+    board: Option<i32>,
+    limit: Option<i32>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<SearchResult>>, Status> {
+    let user_id = match User::get_username_id(key.0, &connection).await {
+        Some(user_id) => user_id,
+        None => return Err(Status::NotFound),
+    };
+    let board = match (board_type, board) {
+        (Some(board_type), Some(board)) => {
+            if !has_board_access(&board_type, board, user_id, &connection).await {
+                return Err(Status::NotFound);
+            }
+            Some((board_type, board))
+        }
+        _ => None,
+    };
+    let query = match fts_query(&q) {
+        Some(query) => query,
+        None => return Ok(Json(Vec::new())),
+    };
+    let limit = limit.unwrap_or(50).clamp(1, 200);
+    match search(query, user_id, board, limit, &connection).await {
+        Ok(results) => Ok(Json(results)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+/// Id of the caller, if they can access the board
+async fn get_board_user(
+    board_type: &str,
+    board_id: i32,
+    key: ApiKey,
+    connection: &Connection,
+) -> Option<i32> {
+    let user_id = User::get_username_id(key.0, connection).await?;
+    if has_board_access(board_type, board_id, user_id, connection).await {
+        Some(user_id)
+    } else {
+        None
+    }
+}
+
+/// Id of the caller, if they can access the task's board
+async fn get_task_user(task_id: i32, key: ApiKey, connection: &Connection) -> Option<i32> {
+    let task = Task::get_single(task_id, connection).await.ok()?;
+    let list = List::get_by_id(task.list, connection).await.ok()?;
+    get_board_user(&list.board_type, list.board, key, connection).await
+}
+
+#[post("/task/filter/<board_type>/<id>", data = "<data>")]
+async fn filter_board_tasks(
+    board_type: String,
+    id: i32,
+    data: Json<TaskFilter>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<TaskResponse>>, FilterError> {
+    if get_board_user(&board_type, id, key, &connection)
+        .await
+        .is_none()
+    {
+        return Err(FilterError::Status(Status::NotFound));
+    }
+    if let Err(e) = query::parse(&data.query) {
+        return Err(FilterError::Query(Json(e)));
+    }
+    let tasks = Task::filter_board(board_type, id, data.into_inner(), &connection).await;
+    match tasks {
+        Ok(tasks) => Task::responses(tasks, &connection)
+            .await
+            .map(Json)
+            .map_err(|_| FilterError::Status(Status::NotFound)),
+        Err(_) => Err(FilterError::Status(Status::NotFound)),
+    }
+}
+
+/// The whole board at once, 304 when the client's ETag is current
+#[get("/boards/<board_type>/<id>/snapshot")]
+async fn board_snapshot(
+    board_type: String,
+    id: i32,
+    if_none_match: IfNoneMatch,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<SnapshotResponse, Status> {
+    if get_board_user(&board_type, id, key, &connection)
+        .await
+        .is_none()
+    {
+        return Err(Status::NotFound);
+    }
+    match Snapshot::get(board_type, id, &connection).await {
+        Ok(snapshot) => Ok(SnapshotResponse::new(snapshot, if_none_match)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+/// Short-lived ticket for the urls which can't send the token in a header
+#[get("/ticket")]
+async fn ticket(key: ApiKey) -> Result<Json<TokenResponse>, Status> {
+    auth::create_ticket(key.0)
+        .map(|ticket| Json(TokenResponse::new(true, ticket)))
+        .map_err(|_| Status::InternalServerError)
+}
+
+/// Server-sent events of the board's tasks, lists and milestones. EventSource can't set
+/// headers, so a ticket comes in the query.
+#[get("/boards/<board_type>/<id>/events?<ticket>")]
+async fn board_events(
+    board_type: String,
+    id: i32,
+    ticket: String,
+    last_event_id: LastEventId,
+    events: &State<Events>,
+    mut shutdown: Shutdown,
+    connection: Connection,
+) -> Result<EventStream![], Status> {
+    let key = match auth::read_ticket(&ticket) {
+        Ok(username) => ApiKey(username),
+        Err(_) => return Err(Status::Unauthorized),
+    };
+    if get_board_user(&board_type, id, key, &connection)
+        .await
+        .is_none()
+    {
+        return Err(Status::NotFound);
+    }
+    let resume = last_event_id.0;
+    let (missed, mut receiver) = events.subscribe(&board_type, id, resume);
+    let event = |event: &BoardEvent| {
+        Event::data(serde_json::to_string(event).unwrap()).id(event.id.to_string())
+    };
+    Ok(EventStream! {
+        let mut last_id = resume.unwrap_or(0);
+        for missed in missed {
+            last_id = missed.id;
+            yield event(&missed);
+        }
+        loop {
+            let received = select! {
+                received = receiver.recv() => received,
+                _ = &mut shutdown => break,
+            };
+            match received {
+                Ok(received) if received.is_on(&board_type, id) => {
+                    last_id = received.id;
+                    yield event(&received);
+                }
+                Ok(_) => continue,
+                // Too slow to keep up, the client has to reload the board
+                Err(RecvError::Lagged(_)) => yield event(&Events::reset(&board_type, id, last_id)),
+                Err(RecvError::Closed) => break,
+            }
+        }
+    })
+}
+
+#[get("/filter/get/<board_type>/<id>")]
+async fn get_saved_filters(
+    board_type: String,
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<SavedFilterResponse>>, Status> {
+    let user_id = match get_board_user(&board_type, id, key, &connection).await {
+        Some(user_id) => user_id,
+        None => return Err(Status::NotFound),
+    };
+    match SavedFilter::get(board_type, id, user_id, &connection).await {
+        Ok(filters) => Ok(Json(filters)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[post("/filter/create", data = "<data>")]
+async fn saved_filter_create(
+    data: Json<SavedFilterData>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    if !data.is_valid() {
+        return Err(Status::BadRequest);
+    }
+    let user_id = match get_board_user(&data.board_type, data.board_id, key, &connection).await {
+        Some(user_id) => user_id,
+        None => return Err(Status::NotFound),
+    };
+    match SavedFilter::create(data.into_inner(), user_id, &connection).await {
+        Ok(_) => Ok(Json(true)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/filter/delete/<id>")]
+async fn saved_filter_delete(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection).await;
+    if user_id.is_none() {
+        return Err(Status::NotFound);
+    }
+    match SavedFilter::delete(id, user_id.unwrap(), &connection).await {
+        Ok(1) => Ok(Json(true)),
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/comments/get/<id>")]
+async fn task_comments(
+    id: i32,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Vec<CommentResponse>>, Status> {
+    get_task_user(id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    match Comment::get(id, &connection).await {
+        Ok(comments) => Ok(Json(comments)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[post("/comment/create/<id>", data = "<data>")]
+async fn comment_create(
+    id: i32,
+    data: Json<CommentData>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = get_task_user(id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    if data.body.trim().is_empty() {
+        return Err(Status::BadRequest);
+    }
+    // Replies stay on the task of the comment they answer
+    if let Some(parent) = data.parent {
+        match Comment::get_by_id(parent, &connection).await {
+            Ok(parent) if parent.task_id == id => {}
+            _ => return Err(Status::BadRequest),
+        }
+    }
+    let comment = Comment::create(id, data.into_inner(), user_id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    publish_comment("created", comment, events, &connection).await;
+    Ok(Json(true))
+}
+
+#[post("/comment/update/<id>", data = "<data>")]
+async fn comment_update(
+    id: i32,
+    data: Json<CommentData>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    if data.body.trim().is_empty() {
+        return Err(Status::BadRequest);
+    }
+    match Comment::update(id, data.into_inner().body, user_id, &connection).await {
+        Ok(1) => {
+            publish_comment("updated", id, events, &connection).await;
+            Ok(Json(true))
+        }
+        _ => Err(Status::NotFound),
+    }
+}
+
+#[get("/comment/delete/<id>")]
+async fn comment_delete(
+    id: i32,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let user_id = User::get_username_id(key.0, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    let comment = Comment::get_by_id(id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    match Comment::delete(id, user_id, &connection).await {
+        Ok(1) => {
+            let deleted = Comment::get_by_id(id, &connection).await.is_err();
+            match Comment::responses(vec![comment], &connection).await {
+                Ok(comments) if deleted => {
+                    events.comment("deleted", &comments[0], &connection).await
+                }
+                _ => publish_comment("updated", id, events, &connection).await,
+            }
+            Ok(Json(true))
+        }
+        _ => Err(Status::NotFound),
+    }
+}
+
+/// Sends the comment as it's saved now to the clients watching its board
+async fn publish_comment(action: &str, id: i32, events: &Events, connection: &Connection) {
+    let comment = match Comment::get_by_id(id, connection).await {
+        Ok(comment) => comment,
+        Err(_) => return,
+    };
+    if let Ok(comments) = Comment::responses(vec![comment], connection).await {
+        events.comment(action, &comments[0], connection).await;
+    }
+}
+
+/// Newest comments on the board's tasks
+#[get("/boards/<board_type>/<id>/comments?<offset>&<limit>&<sort>")]
+async fn board_comments(
+    board_type: String,
+    id: i32,
+    offset: Option<i64>,
+    limit: Option<i64>,
+    sort: Option<String>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Paged<CommentActivity>, Status> {
+    get_board_user(&board_type, id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    let page = PageQuery {
+        offset,
+        limit,
+        sort,
+    }
+    .parse(&Comment::SORT, "-created")
+    .ok_or(Status::BadRequest)?;
+    match Comment::board(board_type, id, page, &connection).await {
+        Ok((comments, total)) => Ok(Paged::new(comments, total)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+/// Multipart upload, the first field of the form is the file
+#[post("/attachment/create/<id>", data = "<file>")]
+async fn attachment_create(
+    id: i32,
+    mut file: Form<Capped<TempFile<'_>>>,
+    config: &State<AttachmentConfig>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<Attachment>, Status> {
+    let user_id = get_task_user(id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    // Cut at Rocket's `file` limit
+    if !file.is_complete() {
+        return Err(Status::PayloadTooLarge);
+    }
+    let task = Task::get_single(id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    let created = Attachment::create(&task, &mut file, user_id, config, &connection).await;
+    let attachment = match created {
+        Ok(attachment) => Attachment::get_by_id(attachment, &connection)
+            .await
+            .map_err(|_| Status::NotFound)?,
+        Err(UploadError::TooLarge | UploadError::QuotaExceeded) => {
+            return Err(Status::PayloadTooLarge)
+        }
+        Err(UploadError::Io) => return Err(Status::InternalServerError),
+    };
+    events
+        .task("updated", &task, &[task.milestone], &connection)
+        .await;
+    Ok(Json(attachment))
+}
+
+/// The file of the attachment. Images are loaded by `<img>`, which can't set headers,
+/// so a ticket can also come in the query.
+#[get("/attachment/<id>?<ticket>")]
+async fn attachment_download(
+    id: i32,
+    ticket: Option<String>,
+    key: Option<ApiKey>,
+    config: &State<AttachmentConfig>,
+    connection: Connection,
+) -> Result<Download, Status> {
+    let key = match (key, ticket.map(|ticket| auth::read_ticket(&ticket))) {
+        (Some(key), _) => key,
+        (None, Some(Ok(username))) => ApiKey(username),
+        _ => return Err(Status::Unauthorized),
+    };
+    let attachment = Attachment::get_by_id(id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    get_task_user(attachment.task_id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    match NamedFile::open(config.path(id)).await {
+        Ok(file) => Ok(Download::new(file, &attachment)),
+        Err(_) => Err(Status::NotFound),
+    }
+}
+
+#[get("/attachment/delete/<id>")]
+async fn attachment_delete(
+    id: i32,
+    config: &State<AttachmentConfig>,
+    events: &State<Events>,
+    connection: Connection,
+    key: ApiKey,
+) -> Result<Json<bool>, Status> {
+    let attachment = Attachment::get_by_id(id, &connection)
+        .await
+        .map_err(|_| Status::NotFound)?;
+    get_task_user(attachment.task_id, key, &connection)
+        .await
+        .ok_or(Status::NotFound)?;
+    match Attachment::delete(id, config, &connection).await {
+        Ok(1) => {
+            if let Ok(task) = Task::get_single(attachment.task_id, &connection).await {
+                events
+                    .task("updated", &task, &[task.milestone], &connection)
+                    .await;
+            }
+            Ok(Json(true))
+        }
+        _ => Err(Status::NotFound),
+    }
+}
+
+async fn run_migrations(rocket: Rocket<Build>) -> Rocket<Build> {
+    embed_migrations!();
+
+    let conn = Connection::get_one(&rocket)
+        .await
+        .expect("database connection");
+    conn.run(|c| embedded_migrations::run(c))
+        .await
+        .expect("can run migrations");
+
+    rocket
+}
+
+async fn stop_idle_timers(pool: Pool) {
+    let mut interval = rocket::tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        let connection = match pool.connection().await {
+            Some(connection) => connection,
+            None => {
+                rocket::error!("Stopping idle timers: no database connection");
+                continue;
+            }
+        };
+        if let Err(x) = Timer::stop_idle(&connection).await {
+            rocket::error!("Stopping idle timers: {}", x);
+        }
+    }
+}
+
+/// Creates the next instances of the recurring tasks which are done or due
+async fn create_recurring_tasks(events: Events, pool: Pool) {
+    let mut interval = rocket::tokio::time::interval(Duration::from_secs(60));
+    loop {
+        interval.tick().await;
+        let connection = match pool.connection().await {
+            Some(connection) => connection,
+            None => {
+                rocket::error!("Creating recurring tasks: no database connection");
+                continue;
+            }
+        };
+        match Recurrence::create_next(&connection).await {
+            Ok(tasks) => {
+                for task in tasks {
+                    events.task("created", &task, &[], &connection).await;
+                }
+            }
+            Err(x) => rocket::error!("Creating recurring tasks: {}", x),
+        }
+    }
 }
 
 #[rocket::main]
@@ -466,6 +2288,8 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 .collect(),
         )
         .allow_credentials(true)
+        // Read by the frontend, which is served from another origin
+        .expose_headers(["X-Total-Count"].iter().map(|h| h.to_string()).collect())
         .to_cors()?;
 
     let _ = rocket::build()
@@ -499,12 +2323,82 @@ async fn main() -> Result<(), Box<dyn Error>> {
                 timer_create,
                 timer_delete,
                 timer_update,
-                get_timers
+                get_timers,
+                get_timer_settings,
+                timer_settings,
+                timer_review,
+                timer_pomodoro,
+                get_categories,
+                category_create,
+                category_delete,
+                timer_category,
+                timer_sessions,
+                session_category,
+                timers_report,
+                get_goals,
+                goal_create,
+                goal_delete,
+                goals_progress,
+                timer_start,
+                timer_pause,
+                timer_stop,
+                timer_billing,
+                session_billable,
+                get_rates,
+                set_rate,
+                rate_delete,
+                billing_summary,
+                billing_export,
+                milestone_update,
+                milestone_delete,
+                milestone_tasks,
+                milestone_set_tasks,
+                milestone_burndown,
+                milestone_predecessors,
+                board_roadmap,
+                team_roadmap,
+                search_tasks,
+                filter_board_tasks,
+                get_saved_filters,
+                saved_filter_create,
+                saved_filter_delete,
+                board_snapshot,
+                board_events,
+                ticket,
+                patch_task,
+                task_comments,
+                comment_create,
+                comment_update,
+                comment_delete,
+                board_comments,
+                attachment_create,
+                attachment_download,
+                attachment_delete,
+                task_blockers,
+                set_recurrence,
+                delete_recurrence
             ],
         )
         .attach(cors)
+        .manage(Events::new())
         .attach(Connection::fairing())
         .attach(AdHoc::on_ignite("Run Migrations", run_migrations))
+        .attach(AdHoc::on_ignite("Attachment storage", attachment::storage))
+        .attach(AdHoc::on_liftoff("Stop idle timers", |rocket| {
+            Box::pin(async move {
+                if let Some(pool) = Pool::get(rocket) {
+                    rocket::tokio::spawn(stop_idle_timers(pool));
+                }
+            })
+        }))
+        .attach(AdHoc::on_liftoff("Create recurring tasks", |rocket| {
+            Box::pin(async move {
+                let events = rocket.state::<Events>().cloned();
+                if let (Some(pool), Some(events)) = (Pool::get(rocket), events) {
+                    rocket::tokio::spawn(create_recurring_tasks(events, pool));
+                }
+            })
+        }))
         .launch()
         .await?;
 
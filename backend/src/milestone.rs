@@ -1,13 +1,17 @@
 use crate::db::Connection;
 use crate::list::List;
-use crate::schema::milestone;
-use crate::task::Task;
-use crate::utils::matches;
+use crate::log::Log;
+use crate::schema::{log, milestone, milestone_dependency, task};
+use crate::task::{last_insert_rowid, Task};
+use crate::types::MilestoneUpdate;
+use crate::utils::{matches, reaches};
 use diesel::prelude::*;
+use diesel::sql_types::{Integer, Text};
 use diesel::AsChangeset;
 use diesel::Insertable;
 use diesel::Queryable;
 use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 #[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug)]
 #[table_name = "milestone"]
@@ -16,6 +20,52 @@ pub struct Milestone {
     pub name: String,
     pub board_id: i32,
     pub board_type: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub start_date: Option<String>, // YYYY-MM-DD, like task deadlines
+    #[serde(default)]
+    pub due_date: Option<String>,
+    #[serde(default = "open")]
+    pub status: String, // "open" or "closed"
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ListTasks {
+    pub list: List,
+    pub tasks: Vec<Task>,     // Tasks of the milestone
+    pub available: Vec<Task>, // Other tasks of the list, which can be added to it
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct MilestoneTasks {
+    pub milestone: MilestoneResponse,
+    pub lists: Vec<ListTasks>,
+}
+
+/// Milestone of a roadmap, with the name of its board
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RoadmapItem {
+    pub board_name: String,
+    pub milestone: MilestoneResponse,
+}
+
+#[derive(QueryableByName, Debug)]
+struct MilestoneStats {
+    #[sql_type = "Integer"]
+    milestone: i32,
+    #[sql_type = "Integer"]
+    total: i32,
+    #[sql_type = "Integer"]
+    done: i32,
+    #[sql_type = "Integer"]
+    points_total: i32,
+    #[sql_type = "Integer"]
+    points_done: i32,
+}
+
+fn open() -> String {
+    "open".to_owned()
 }
 
 #[derive(Clone, Serialize, Deserialize, Debug)]
@@ -24,70 +74,337 @@ pub struct MilestoneResponse {
     pub name: String,
     pub done: i32,
     pub total: i32,
+    #[serde(default)]
+    pub points_done: i32,
+    #[serde(default)]
+    pub points_total: i32,
+    #[serde(default)]
+    pub predecessors: Vec<i32>,
     pub board_id: i32,
     pub board_type: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub due_date: Option<String>,
+    pub status: String,
 }
 
 impl Milestone {
-    pub async fn create(milestone: Milestone, connection: &Connection) -> QueryResult<usize> {
+    /// New milestones follow the rules of updates
+    pub fn is_valid(&self) -> bool {
+        MilestoneUpdate {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            start_date: self.start_date.clone(),
+            due_date: self.due_date.clone(),
+            status: self.status.clone(),
+        }
+        .is_valid()
+    }
+
+    /// Id of the new milestone
+    pub async fn create(milestone: Milestone, connection: &Connection) -> QueryResult<i32> {
         connection
             .run(|conn| {
                 diesel::insert_into(milestone::table)
                     .values(milestone)
+                    .execute(conn)?;
+                diesel::select(last_insert_rowid).get_result::<i32>(conn)
+            })
+            .await
+    }
+
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<Milestone> {
+        connection
+            .run(move |conn| milestone::table.filter(milestone::id.eq(id)).first(conn))
+            .await
+    }
+
+    pub async fn update(
+        id: i32,
+        data: MilestoneUpdate,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::update(milestone::table.filter(milestone::id.eq(id)))
+                    .set((
+                        milestone::name.eq(data.name),
+                        milestone::description.eq(data.description),
+                        milestone::start_date.eq(data.start_date),
+                        milestone::due_date.eq(data.due_date),
+                        milestone::status.eq(data.status),
+                    ))
                     .execute(conn)
             })
             .await
     }
 
+    /// Tasks of the milestone stay on the board without a milestone
+    pub async fn delete(id: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(
+                    milestone_dependency::table.filter(
+                        milestone_dependency::milestone
+                            .eq(id)
+                            .or(milestone_dependency::predecessor.eq(id)),
+                    ),
+                )
+                .execute(conn)?;
+                diesel::update(task::table.filter(task::milestone.eq(id)))
+                    .set((
+                        task::milestone.eq(None::<i32>),
+                        task::version.eq(task::version + 1),
+                    ))
+                    .execute(conn)?;
+                diesel::delete(milestone::table.filter(milestone::id.eq(id))).execute(conn)
+            })
+            .await
+    }
+
+    /// Tasks of the milestone's board, grouped by list
+    pub async fn get_tasks(
+        milestone: Milestone,
+        connection: &Connection,
+    ) -> QueryResult<MilestoneTasks> {
+        let id = milestone.id.unwrap();
+        let lists = List::get(milestone.board_type.clone(), milestone.board_id, connection).await?;
+        let mut result = Vec::new();
+        for list in lists {
+            let (tasks, available) = Task::get(list.id.unwrap(), connection)
+                .await?
+                .into_iter()
+                .partition(|task| matches(task.milestone, id));
+            result.push(ListTasks {
+                list,
+                tasks,
+                available,
+            });
+        }
+        Ok(MilestoneTasks {
+            milestone: Self::get_stats(milestone, connection).await?,
+            lists: result,
+        })
+    }
+
+    /// Moves the tasks into and out of the milestone, all of them or none. When any of them
+    /// isn't at its version anymore, nothing is moved and the result is `RollbackTransaction`.
+    pub async fn set_tasks(
+        id: i32,
+        add: Vec<Task>,
+        remove: Vec<Task>,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::Connection::transaction(conn, || {
+                    let mut cnt = 0;
+                    for (task, milestone) in add
+                        .into_iter()
+                        .map(|task| (task, Some(id)))
+                        .chain(remove.into_iter().map(|task| (task, None)))
+                    {
+                        let task = Task { milestone, ..task };
+                        let updated = diesel::update(
+                            task::table
+                                .filter(task::id.eq(task.id))
+                                .filter(task::version.eq(task.version)),
+                        )
+                        .set(Task {
+                            version: task.version + 1,
+                            ..task.clone()
+                        })
+                        .execute(conn)?;
+                        if updated == 0 {
+                            return Err(diesel::result::Error::RollbackTransaction);
+                        }
+                        let log =
+                            Log::from_task(task.clone(), task.id.unwrap(), "updated".to_owned());
+                        diesel::insert_into(log::table).values(log).execute(conn)?;
+                        cnt += updated;
+                    }
+                    Ok(cnt)
+                })
+            })
+            .await
+    }
+
     pub async fn get(
         board_id: i32,
         board_type: String,
         connection: &Connection,
     ) -> QueryResult<Vec<MilestoneResponse>> {
+        let board = board_type.clone();
         let milestones = connection
             .run(move |conn| {
                 milestone::table
                     .filter(milestone::board_id.eq(board_id))
-                    .filter(milestone::board_type.eq(board_type))
+                    .filter(milestone::board_type.eq(board))
                     .load::<Milestone>(conn)
             })
             .await?;
+        let stats = Self::board_stats(board_id, board_type, connection).await?;
+        let ids = milestones.iter().map(|m| m.id.unwrap()).collect();
+        let mut predecessors = Self::get_predecessors(ids, connection).await?;
+        Ok(milestones
+            .into_iter()
+            .map(|milestone| {
+                let id = milestone.id.unwrap();
+                let predecessors = predecessors.remove(&id).unwrap_or_default();
+                Self::response(milestone, stats.get(&id), predecessors)
+            })
+            .collect())
+    }
+
+    /// Predecessors of each of the milestones
+    async fn get_predecessors(
+        ids: Vec<i32>,
+        connection: &Connection,
+    ) -> QueryResult<HashMap<i32, Vec<i32>>> {
+        let dependencies = connection
+            .run(move |conn| {
+                milestone_dependency::table
+                    .filter(milestone_dependency::milestone.eq_any(ids))
+                    .select((
+                        milestone_dependency::milestone,
+                        milestone_dependency::predecessor,
+                    ))
+                    .order(milestone_dependency::predecessor)
+                    .load::<(i32, i32)>(conn)
+            })
+            .await?;
+        let mut result = HashMap::<i32, Vec<i32>>::new();
+        for (milestone, predecessor) in dependencies {
+            result.entry(milestone).or_default().push(predecessor);
+        }
+        Ok(result)
+    }
+
+    /// Replaces the predecessors of the milestone, returns false if that would create a cycle
+    pub async fn set_predecessors(
+        id: i32,
+        predecessors: Vec<i32>,
+        connection: &Connection,
+    ) -> QueryResult<bool> {
+        connection
+            .run(move |conn| {
+                diesel::Connection::transaction(conn, || {
+                    let edges = milestone_dependency::table
+                        .filter(milestone_dependency::milestone.ne(id))
+                        .select((
+                            milestone_dependency::milestone,
+                            milestone_dependency::predecessor,
+                        ))
+                        .load::<(i32, i32)>(conn)?;
+                    if predecessors.iter().any(|&p| reaches(&edges, p, id)) {
+                        return Ok(false);
+                    }
+                    diesel::delete(
+                        milestone_dependency::table.filter(milestone_dependency::milestone.eq(id)),
+                    )
+                    .execute(conn)?;
+                    for predecessor in predecessors {
+                        diesel::replace_into(milestone_dependency::table)
+                            .values((
+                                milestone_dependency::milestone.eq(id),
+                                milestone_dependency::predecessor.eq(predecessor),
+                            ))
+                            .execute(conn)?;
+                    }
+                    Ok(true)
+                })
+            })
+            .await
+    }
+
+    /// Milestones of the boards ordered by their dates, undated ones last
+    pub async fn roadmap(
+        boards: Vec<(String, i32, String)>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<RoadmapItem>> {
         let mut result = Vec::new();
-        for milestone in milestones {
-            let stats = Self::get_stats(milestone, connection).await?;
-            result.push(stats);
+        for (board_type, board_id, board_name) in boards {
+            for milestone in Self::get(board_id, board_type, connection).await? {
+                result.push(RoadmapItem {
+                    board_name: board_name.clone(),
+                    milestone,
+                });
+            }
         }
+        result.sort_by_key(|item| {
+            let milestone = &item.milestone;
+            let date = milestone.start_date.clone().or(milestone.due_date.clone());
+            (date.is_none(), date, milestone.id)
+        });
         Ok(result)
     }
 
+    /// Task counts of every milestone of the board, only tasks on the board's lists are counted.
+    /// Tasks without points are stored with -1, they count as 0
+    async fn board_stats(
+        board_id: i32,
+        board_type: String,
+        connection: &Connection,
+    ) -> QueryResult<HashMap<i32, MilestoneStats>> {
+        let stats = connection
+            .run(move |conn| {
+                diesel::sql_query(
+                    "SELECT milestone.id AS milestone, \
+                        COUNT(task.id) AS total, \
+                        COALESCE(SUM(task.done = 1), 0) AS done, \
+                        COALESCE(SUM(MAX(task.points, 0)), 0) AS points_total, \
+                        COALESCE(SUM(CASE WHEN task.done = 1 THEN MAX(task.points, 0) END), 0) \
+                            AS points_done \
+                    FROM milestone \
+                    LEFT JOIN list ON list.board = milestone.board_id \
+                        AND list.board_type = milestone.board_type \
+                    LEFT JOIN task ON task.list = list.id AND task.milestone = milestone.id \
+                    WHERE milestone.board_id = ? AND milestone.board_type = ? \
+                    GROUP BY milestone.id",
+                )
+                .bind::<Integer, _>(board_id)
+                .bind::<Text, _>(board_type)
+                .load::<MilestoneStats>(conn)
+            })
+            .await?;
+        Ok(stats
+            .into_iter()
+            .map(|stats| (stats.milestone, stats))
+            .collect())
+    }
+
     pub async fn get_stats(
         milestone: Milestone,
         connection: &Connection,
     ) -> QueryResult<MilestoneResponse> {
-        let board_type = milestone.board_type.clone();
-        let lists = List::get(milestone.board_type, milestone.board_id, connection).await?;
-        let mut tasks = Vec::new();
-        for list in lists {
-            let mut tasks_on_list = Task::get(list.id.unwrap(), connection).await?;
-            tasks.append(&mut tasks_on_list);
-        }
+        let stats =
+            Self::board_stats(milestone.board_id, milestone.board_type.clone(), connection).await?;
         let id = milestone.id.unwrap();
-        let total = tasks
-            .clone()
-            .into_iter()
-            .filter(|task| matches(task.milestone, id))
-            .count() as i32;
-        let done = tasks
-            .into_iter()
-            .filter(|task| task.done == 1 && matches(task.milestone, id))
-            .count() as i32;
-        Ok(MilestoneResponse {
-            total,
-            done,
+        let mut predecessors = Self::get_predecessors(vec![id], connection).await?;
+        let predecessors = predecessors.remove(&id).unwrap_or_default();
+        Ok(Self::response(milestone, stats.get(&id), predecessors))
+    }
+
+    fn response(
+        milestone: Milestone,
+        stats: Option<&MilestoneStats>,
+        predecessors: Vec<i32>,
+    ) -> MilestoneResponse {
+        let stat = |field: fn(&MilestoneStats) -> i32| stats.map(field).unwrap_or(0);
+        MilestoneResponse {
+            total: stat(|stats| stats.total),
+            done: stat(|stats| stats.done),
+            points_total: stat(|stats| stats.points_total),
+            points_done: stat(|stats| stats.points_done),
+            predecessors,
             id: milestone.id,
             board_id: milestone.board_id,
             name: milestone.name,
-            board_type,
-        })
+            board_type: milestone.board_type,
+            description: milestone.description,
+            start_date: milestone.start_date,
+            due_date: milestone.due_date,
+            status: milestone.status,
+        }
     }
 }
@@ -0,0 +1,139 @@
+use diesel::dsl::sql;
+use diesel::expression::SqlLiteral;
+use diesel::sql_types::Integer;
+use rocket::http::Header;
+use rocket::serde::json::Json;
+use rocket::serde::Serialize;
+
+const MAX_LIMIT: i64 = 500;
+
+/// `?offset=&limit=&sort=` of the listing endpoints, `sort=-name` sorts descending
+#[derive(Debug, Default)]
+pub struct PageQuery {
+    pub offset: Option<i64>,
+    pub limit: Option<i64>,
+    pub sort: Option<String>,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Page {
+    pub offset: i64,
+    pub limit: Option<i64>, // None returns everything after the offset
+    pub field: String,
+    pub descending: bool,
+    order: String,
+}
+
+impl PageQuery {
+    /// `columns` maps the sortable fields to the SQL ordering them, None if the query is invalid
+    pub fn parse(self, columns: &[(&str, &str)], default: &str) -> Option<Page> {
+        let sort = self.sort.unwrap_or_else(|| default.to_owned());
+        let (field, descending) = match sort.strip_prefix('-') {
+            Some(field) => (field, true),
+            None => (sort.as_str(), false),
+        };
+        let column = columns.iter().find(|column| column.0 == field)?.1;
+        let offset = self.offset.unwrap_or(0);
+        if offset < 0 || matches!(self.limit, Some(l) if l <= 0 || l > MAX_LIMIT) {
+            return None;
+        }
+        let direction = if descending { "DESC" } else { "ASC" };
+        Some(Page {
+            offset,
+            limit: self.limit,
+            field: field.to_owned(),
+            descending,
+            order: format!("{} {}, id", column, direction), // id keeps pages stable
+        })
+    }
+}
+
+impl Page {
+    /// ORDER BY clause, the sql comes from the whitelisted columns only
+    pub fn order(&self) -> SqlLiteral<Integer> {
+        sql(&self.order)
+    }
+
+    /// LIMIT for diesel, -1 means no limit in SQLite
+    pub fn sql_limit(&self) -> i64 {
+        self.limit.unwrap_or(-1)
+    }
+
+    /// The page of a list sorted in memory
+    pub fn slice<T>(&self, items: Vec<T>) -> Vec<T> {
+        let items = items.into_iter().skip(self.offset as usize);
+        match self.limit {
+            Some(limit) => items.take(limit as usize).collect(),
+            None => items.collect(),
+        }
+    }
+}
+
+/// Page of a listing, the number of all items is sent in `X-Total-Count`
+#[derive(Responder)]
+pub struct Paged<T: Serialize> {
+    items: Json<Vec<T>>,
+    total: Header<'static>,
+}
+
+impl<T: Serialize> Paged<T> {
+    pub fn new(items: Vec<T>, total: i64) -> Self {
+        Paged {
+            items: Json(items),
+            total: Header::new("X-Total-Count", total.to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const COLUMNS: [(&str, &str); 2] = [("name", "task.name"), ("created", "task.id")];
+
+    fn query(offset: Option<i64>, limit: Option<i64>, sort: Option<&str>) -> PageQuery {
+        PageQuery {
+            offset,
+            limit,
+            sort: sort.map(|sort| sort.to_owned()),
+        }
+    }
+
+    #[test]
+    fn test_page_parse() {
+        let page = query(None, None, None).parse(&COLUMNS, "created").unwrap();
+        assert_eq!(page.offset, 0);
+        assert_eq!(page.sql_limit(), -1);
+        assert_eq!(page.order, "task.id ASC, id");
+        let page = query(Some(10), Some(5), Some("-name"))
+            .parse(&COLUMNS, "created")
+            .unwrap();
+        assert_eq!((page.field.as_str(), page.descending), ("name", true));
+        assert_eq!(page.order, "task.name DESC, id");
+
+        assert!(query(None, None, Some("points"))
+            .parse(&COLUMNS, "created")
+            .is_none());
+        assert!(query(Some(-1), None, None)
+            .parse(&COLUMNS, "created")
+            .is_none());
+        assert!(query(None, Some(0), None)
+            .parse(&COLUMNS, "created")
+            .is_none());
+        assert!(query(None, Some(MAX_LIMIT + 1), None)
+            .parse(&COLUMNS, "created")
+            .is_none());
+    }
+
+    #[test]
+    fn test_page_slice() {
+        let page = query(Some(1), Some(2), None)
+            .parse(&COLUMNS, "created")
+            .unwrap();
+        assert_eq!(page.slice(vec![1, 2, 3, 4]), vec![2, 3]);
+        let page = query(Some(3), None, None)
+            .parse(&COLUMNS, "created")
+            .unwrap();
+        assert_eq!(page.slice(vec![1, 2, 3, 4]), vec![4]);
+    }
+}
@@ -0,0 +1,377 @@
+use crate::schema::{milestone, task};
+use crate::task::{blocked_ids, epic_ids};
+use chrono::NaiveDate;
+use diesel::dsl::not;
+use diesel::prelude::*;
+use diesel::sql_types::Bool;
+use diesel::sqlite::Sqlite;
+use rocket::serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Task filter language, terms are separated by spaces and all of them must match:
+///
+/// - `word` or `"some words"`: name contains the text
+/// - `name:`, `tag:`, `place:`, `note:`: the field contains the text
+/// - `@alice`: alice is assigned
+/// - `milestone:"v1"`: the task belongs to the milestone named v1
+/// - `done`: the task is done
+/// - `blocked`: some of the task's blockers aren't done
+/// - `epic`: the task has children
+/// - `points>=3`, `due<2026-11-01`: comparisons with `=`, `<`, `<=`, `>`, `>=` (or `:` for `=`)
+/// - `-term`: negates the term
+#[derive(Debug, PartialEq, Clone)]
+pub enum Condition {
+    Name(String),
+    Tag(String),
+    Place(String),
+    Note(String),
+    Member(String),
+    Milestone(String),
+    Done,
+    Blocked,
+    Epic,
+    Points(Op, i32),
+    Due(Op, String),
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Op {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Term {
+    pub negated: bool,
+    pub condition: Condition,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct ParseError {
+    pub position: usize, // Character of the query where the error is, counted from 1
+    pub message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at position {}", self.message, self.position)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+const OPERATORS: [(&str, Op); 6] = [
+    (">=", Op::Ge),
+    ("<=", Op::Le),
+    (">", Op::Gt),
+    ("<", Op::Lt),
+    ("=", Op::Eq),
+    (":", Op::Eq),
+];
+
+/// Splits the query into terms with their positions, quotes group words into one term
+fn tokenize(query: &str) -> Result<Vec<(usize, String)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().enumerate().peekable();
+    while let Some((start, c)) = chars.next() {
+        if c.is_whitespace() {
+            continue;
+        }
+        let mut token = c.to_string();
+        let mut quote = (c == '"').then_some(start);
+        while let Some(&(i, c)) = chars.peek() {
+            if c.is_whitespace() && quote.is_none() {
+                break;
+            }
+            if c == '"' {
+                quote = match quote {
+                    Some(_) => None,
+                    None => Some(i),
+                };
+            }
+            token.push(c);
+            chars.next();
+        }
+        if let Some(position) = quote {
+            return Err(ParseError {
+                position: position + 1,
+                message: "unterminated quote".to_owned(),
+            });
+        }
+        tokens.push((start, token));
+    }
+    Ok(tokens)
+}
+
+fn unquote(value: &str) -> String {
+    value.replace('"', "")
+}
+
+fn parse_term(position: usize, token: &str) -> Result<Term, ParseError> {
+    let error = |offset: usize, message: String| ParseError {
+        position: position + offset + 1,
+        message,
+    };
+    let (negated, token) = match token.strip_prefix('-') {
+        Some(rest) if !rest.is_empty() => (true, rest),
+        _ => (false, token),
+    };
+    let offset = negated as usize;
+    if let Some(member) = token.strip_prefix('@') {
+        if member.is_empty() {
+            return Err(error(offset, "missing name after `@`".to_owned()));
+        }
+        return Ok(Term {
+            negated,
+            condition: Condition::Member(unquote(member)),
+        });
+    }
+
+    let key_length = token
+        .find(|c: char| !c.is_ascii_alphabetic())
+        .unwrap_or(token.len());
+    let (key, rest) = token.split_at(key_length);
+    let operator = OPERATORS
+        .iter()
+        .find(|(symbol, _)| !key.is_empty() && rest.starts_with(symbol));
+    let (symbol, op) = match operator {
+        Some(&(symbol, op)) => (symbol, op),
+        None if token == "done" => {
+            return Ok(Term {
+                negated,
+                condition: Condition::Done,
+            })
+        }
+        None if token == "blocked" => {
+            return Ok(Term {
+                negated,
+                condition: Condition::Blocked,
+            })
+        }
+        None if token == "epic" => {
+            return Ok(Term {
+                negated,
+                condition: Condition::Epic,
+            })
+        }
+        None => {
+            return Ok(Term {
+                negated,
+                condition: Condition::Name(unquote(token)),
+            })
+        }
+    };
+    let value = unquote(&rest[symbol.len()..]);
+    let value_offset = offset + key.len() + symbol.len();
+    if value.is_empty() {
+        return Err(error(
+            value_offset,
+            format!("missing value after `{}{}`", key, symbol),
+        ));
+    }
+    let text = |condition: fn(String) -> Condition| {
+        if symbol == ":" {
+            Ok(condition(value.clone()))
+        } else {
+            Err(error(
+                offset + key.len(),
+                format!("`{}` only supports `:`", key),
+            ))
+        }
+    };
+    let condition = match key {
+        "name" => text(Condition::Name)?,
+        "tag" => text(Condition::Tag)?,
+        "place" => text(Condition::Place)?,
+        "note" => text(Condition::Note)?,
+        "milestone" => text(Condition::Milestone)?,
+        "points" => match value.parse::<i32>() {
+            Ok(points) => Condition::Points(op, points),
+            Err(_) => {
+                return Err(error(
+                    value_offset,
+                    format!("expected a number, found `{}`", value),
+                ))
+            }
+        },
+        "due" => match NaiveDate::parse_from_str(&value, "%Y-%m-%d") {
+            Ok(_) => Condition::Due(op, value),
+            Err(_) => {
+                return Err(error(
+                    value_offset,
+                    format!("expected a date like 2026-11-01, found `{}`", value),
+                ))
+            }
+        },
+        _ => return Err(error(offset, format!("unknown field `{}`", key))),
+    };
+    Ok(Term { negated, condition })
+}
+
+pub fn parse(query: &str) -> Result<Vec<Term>, ParseError> {
+    tokenize(query)?
+        .into_iter()
+        .map(|(position, token)| parse_term(position, &token))
+        .collect()
+}
+
+pub type Predicate = Box<dyn BoxableExpression<task::table, Sqlite, SqlType = Bool>>;
+
+macro_rules! compare {
+    ($column:expr, $op:expr, $value:expr) => {
+        match $op {
+            Op::Eq => Box::new($column.eq($value)) as Predicate,
+            Op::Lt => Box::new($column.lt($value)),
+            Op::Le => Box::new($column.le($value)),
+            Op::Gt => Box::new($column.gt($value)),
+            Op::Ge => Box::new($column.ge($value)),
+        }
+    };
+}
+
+fn predicate(term: Term, conn: &SqliteConnection) -> QueryResult<Predicate> {
+    let like = |text: &str| format!("%{}%", text);
+    let negated = term.negated;
+    // Nullable text columns, a missing value doesn't contain anything
+    macro_rules! contains {
+        ($column:expr, $text:expr) => {
+            if negated {
+                Box::new($column.is_null().or($column.not_like(like(&$text))))
+            } else {
+                Box::new($column.like(like(&$text)))
+            }
+        };
+    }
+    let positive: Predicate = match term.condition {
+        Condition::Place(text) => return Ok(contains!(task::place, text)),
+        Condition::Note(text) => return Ok(contains!(task::note, text)),
+        Condition::Member(name) => return Ok(contains!(task::members, name)),
+        Condition::Milestone(name) => {
+            let milestones = milestone::table
+                .filter(milestone::name.eq(name))
+                .select(milestone::id)
+                .load::<Option<i32>>(conn)?
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>();
+            if negated {
+                return Ok(Box::new(
+                    task::milestone
+                        .is_null()
+                        .or(not(task::milestone.eq_any(milestones))),
+                ));
+            }
+            Box::new(task::milestone.eq_any(milestones))
+        }
+        Condition::Name(text) => Box::new(task::name.like(like(&text))),
+        Condition::Tag(text) => Box::new(task::tags.like(like(&text))),
+        Condition::Done => Box::new(task::done.eq(1)),
+        Condition::Blocked => Box::new(task::id.eq_any(blocked_ids())),
+        Condition::Epic => Box::new(task::id.eq_any(epic_ids())),
+        Condition::Points(op, points) => compare!(task::points, op, points),
+        Condition::Due(op, date) => Box::new(task::deadline.ne("").and(compare!(
+            task::deadline,
+            op,
+            date
+        ))),
+    };
+    if negated {
+        Ok(Box::new(not(positive)))
+    } else {
+        Ok(positive)
+    }
+}
+
+/// Predicate matching when any of the predicates does, None when there are none
+pub fn any(predicates: impl Iterator<Item = Predicate>) -> Option<Predicate> {
+    predicates.reduce(|any, predicate| Box::new(any.or(predicate)))
+}
+
+/// Narrows the query to the tasks matching every term
+pub fn apply(
+    mut query: task::BoxedQuery<'static, Sqlite>,
+    terms: Vec<Term>,
+    conn: &SqliteConnection,
+) -> QueryResult<task::BoxedQuery<'static, Sqlite>> {
+    for term in terms {
+        query = query.filter(predicate(term, conn)?);
+    }
+    Ok(query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn term(negated: bool, condition: Condition) -> Term {
+        Term { negated, condition }
+    }
+
+    #[test]
+    fn test_parse() {
+        let terms = parse(r#"tag:bug points>=3 due<2026-11-01 @alice -done milestone:"v1""#);
+        assert_eq!(
+            terms,
+            Ok(vec![
+                term(false, Condition::Tag("bug".to_owned())),
+                term(false, Condition::Points(Op::Ge, 3)),
+                term(false, Condition::Due(Op::Lt, "2026-11-01".to_owned())),
+                term(false, Condition::Member("alice".to_owned())),
+                term(true, Condition::Done),
+                term(false, Condition::Milestone("v1".to_owned())),
+            ])
+        );
+        assert_eq!(parse("-blocked"), Ok(vec![term(true, Condition::Blocked)]));
+        assert_eq!(parse("epic"), Ok(vec![term(false, Condition::Epic)]));
+    }
+
+    #[test]
+    fn test_parse_words() {
+        assert_eq!(
+            parse(r#"  fix "login page" -note:"needs review" points:2"#),
+            Ok(vec![
+                term(false, Condition::Name("fix".to_owned())),
+                term(false, Condition::Name("login page".to_owned())),
+                term(true, Condition::Note("needs review".to_owned())),
+                term(false, Condition::Points(Op::Eq, 2)),
+            ])
+        );
+        assert_eq!(parse(""), Ok(vec![]));
+        assert_eq!(
+            parse("- 3"),
+            Ok(vec![
+                term(false, Condition::Name("-".to_owned())),
+                term(false, Condition::Name("3".to_owned())),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let error = |query: &str| parse(query).unwrap_err();
+        assert_eq!(
+            error("tag:bug colour:red"),
+            ParseError {
+                position: 9,
+                message: "unknown field `colour`".to_owned()
+            }
+        );
+        assert_eq!(error("points>=many").position, 9);
+        assert_eq!(
+            error("points>=many").message,
+            "expected a number, found `many`"
+        );
+        assert_eq!(
+            error("due<tomorrow").to_string(),
+            "expected a date like 2026-11-01, found `tomorrow` at position 5"
+        );
+        assert_eq!(error("tag:").message, "missing value after `tag:`");
+        assert_eq!(error("-tag>bug").position, 5);
+        assert_eq!(error(r#"a milestone:"v1"#).message, "unterminated quote");
+        assert_eq!(error(r#"a milestone:"v1"#).position, 13);
+        assert_eq!(error("@").message, "missing name after `@`");
+    }
+}
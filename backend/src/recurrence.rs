@@ -0,0 +1,411 @@
+use crate::db::Connection;
+use crate::list::List;
+use crate::log::Log;
+use crate::schema::{recurrence, task};
+use crate::task::{last_insert_rowid, Task};
+use chrono::{Datelike, Duration, Local, NaiveDate, Weekday};
+use diesel::prelude::*;
+use diesel::Insertable;
+use diesel::Queryable;
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum Frequency {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
+/// Subset of an iCalendar RRULE: `FREQ=DAILY`, `WEEKLY` or `MONTHLY` with `INTERVAL`,
+/// `BYDAY` (weekly), `BYMONTHDAY` (monthly), `COUNT` and `UNTIL`, e.g. `FREQ=WEEKLY;BYDAY=MO,TH`
+#[derive(Debug, PartialEq, Clone)]
+pub struct Rule {
+    pub frequency: Frequency,
+    pub interval: u32,
+    pub weekdays: Vec<Weekday>,
+    pub month_day: Option<u32>, // Months shorter than that use their last day
+    pub count: Option<i32>,     // Instances of the series, the first one included
+    pub until: Option<NaiveDate>,
+}
+
+fn weekday(value: &str) -> Result<Weekday, String> {
+    match value.trim().to_ascii_uppercase().as_str() {
+        "MO" => Ok(Weekday::Mon),
+        "TU" => Ok(Weekday::Tue),
+        "WE" => Ok(Weekday::Wed),
+        "TH" => Ok(Weekday::Thu),
+        "FR" => Ok(Weekday::Fri),
+        "SA" => Ok(Weekday::Sat),
+        "SU" => Ok(Weekday::Sun),
+        _ => Err(format!("unknown day `{}`, expected MO to SU", value)),
+    }
+}
+
+fn positive(value: &str, name: &str) -> Result<u32, String> {
+    value
+        .parse()
+        .ok()
+        .filter(|&number| number > 0)
+        .ok_or_else(|| format!("{} must be a positive number", name))
+}
+
+impl Rule {
+    pub fn parse(rule: &str) -> Result<Rule, String> {
+        let rule = rule.trim();
+        let rule = rule.strip_prefix("RRULE:").unwrap_or(rule);
+        let mut frequency = None;
+        let mut parsed = Rule {
+            frequency: Frequency::Daily,
+            interval: 1,
+            weekdays: Vec::new(),
+            month_day: None,
+            count: None,
+            until: None,
+        };
+        for part in rule.split(';').filter(|part| !part.is_empty()) {
+            let (key, value) = part
+                .split_once('=')
+                .ok_or_else(|| format!("expected NAME=VALUE, got `{}`", part))?;
+            match key.to_ascii_uppercase().as_str() {
+                "FREQ" => {
+                    frequency = match value.to_ascii_uppercase().as_str() {
+                        "DAILY" => Some(Frequency::Daily),
+                        "WEEKLY" => Some(Frequency::Weekly),
+                        "MONTHLY" => Some(Frequency::Monthly),
+                        _ => return Err(format!("unsupported frequency `{}`", value)),
+                    }
+                }
+                "INTERVAL" => parsed.interval = positive(value, "INTERVAL")?,
+                "BYDAY" => {
+                    parsed.weekdays = value.split(',').map(weekday).collect::<Result<_, _>>()?
+                }
+                "BYMONTHDAY" => {
+                    let day = positive(value, "BYMONTHDAY")?;
+                    if day > 31 {
+                        return Err("BYMONTHDAY must be at most 31".to_owned());
+                    }
+                    parsed.month_day = Some(day);
+                }
+                "COUNT" => parsed.count = Some(positive(value, "COUNT")? as i32),
+                "UNTIL" => {
+                    // Only the date of a date-time is used
+                    let date = value.get(..8).unwrap_or(value);
+                    parsed.until = Some(
+                        NaiveDate::parse_from_str(date, "%Y%m%d")
+                            .map_err(|_| "UNTIL must be a date like 20261231".to_owned())?,
+                    );
+                }
+                _ => return Err(format!("unsupported part `{}`", key)),
+            }
+        }
+        parsed.frequency = frequency.ok_or_else(|| "FREQ is required".to_owned())?;
+        if !parsed.weekdays.is_empty() && parsed.frequency != Frequency::Weekly {
+            return Err("BYDAY needs FREQ=WEEKLY".to_owned());
+        }
+        if parsed.month_day.is_some() && parsed.frequency != Frequency::Monthly {
+            return Err("BYMONTHDAY needs FREQ=MONTHLY".to_owned());
+        }
+        Ok(parsed)
+    }
+
+    /// Date of the occurrence after `date`, None once the rule ended
+    pub fn next(&self, date: NaiveDate) -> Option<NaiveDate> {
+        let interval = self.interval as i64;
+        let next = match self.frequency {
+            Frequency::Daily => date + Duration::days(interval),
+            Frequency::Weekly if self.weekdays.is_empty() => date + Duration::weeks(interval),
+            Frequency::Weekly => {
+                // The rest of this week, then the week `interval` weeks later
+                let week = |date: NaiveDate| (date.num_days_from_ce() as i64 - 1).div_euclid(7);
+                (1..=7 * interval)
+                    .map(|days| date + Duration::days(days))
+                    .find(|next| {
+                        self.weekdays.contains(&next.weekday())
+                            && (week(*next) - week(date)) % interval == 0
+                    })?
+            }
+            Frequency::Monthly => {
+                let day = self.month_day.unwrap_or_else(|| date.day());
+                let in_month = |year: i32, month: u32| {
+                    (1..=day)
+                        .rev()
+                        .find_map(|day| NaiveDate::from_ymd_opt(year, month, day))
+                };
+                match in_month(date.year(), date.month()) {
+                    Some(next) if next > date => next,
+                    _ => {
+                        let months = date.year() * 12 + date.month0() as i32 + interval as i32;
+                        in_month(months.div_euclid(12), months.rem_euclid(12) as u32 + 1)?
+                    }
+                }
+            }
+        };
+        match self.until {
+            Some(until) if next > until => None,
+            _ => Some(next),
+        }
+    }
+}
+
+/// Series of a recurring task, its instances are created one after another
+#[derive(Serialize, Deserialize, Queryable, Insertable, Debug, Clone, PartialEq)]
+#[table_name = "recurrence"]
+pub struct Recurrence {
+    pub id: Option<i32>,
+    pub task: i32, // Latest instance, the next one comes once it's done or due
+    pub rule: String,
+    pub list: i32,  // Where the next instances are created
+    pub count: i32, // Instances so far, the first one included
+}
+
+impl Recurrence {
+    /// Makes the task the latest instance of a new series
+    pub async fn set(
+        task: i32,
+        rule: String,
+        list: i32,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let recurrence = Recurrence {
+            id: None,
+            task,
+            rule,
+            list,
+            count: 1,
+        };
+        connection
+            .run(move |conn| {
+                diesel::replace_into(recurrence::table)
+                    .values(recurrence)
+                    .execute(conn)
+            })
+            .await
+    }
+
+    /// Stops the series of the task
+    pub async fn delete(task: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::delete(recurrence::table.filter(recurrence::task.eq(task))).execute(conn)
+            })
+            .await
+    }
+
+    /// Series of each of the tasks which are the latest instance of one
+    pub async fn get_tasks(
+        ids: Vec<i32>,
+        connection: &Connection,
+    ) -> QueryResult<HashMap<i32, Recurrence>> {
+        let recurrences = connection
+            .run(move |conn| {
+                recurrence::table
+                    .filter(recurrence::task.eq_any(ids))
+                    .load::<Recurrence>(conn)
+            })
+            .await?;
+        Ok(recurrences.into_iter().map(|r| (r.task, r)).collect())
+    }
+
+    /// Instance after the previous one, None once the series ended. Instances of a series
+    /// without deadlines don't have them either, the others are due after `today`.
+    pub fn next_task(&self, previous: &Task, today: NaiveDate) -> Option<Task> {
+        let rule = Rule::parse(&self.rule).ok()?;
+        if matches!(rule.count, Some(count) if self.count >= count) {
+            return None;
+        }
+        let deadline = match NaiveDate::parse_from_str(&previous.deadline, "%Y-%m-%d") {
+            Ok(mut deadline) => {
+                // Occurrences missed while the server was down are skipped
+                deadline = rule.next(deadline)?;
+                while deadline <= today {
+                    deadline = rule.next(deadline)?;
+                }
+                deadline.to_string()
+            }
+            Err(_) => String::new(),
+        };
+        Some(Task {
+            id: None,
+            list: self.list,
+            deadline,
+            done: 0,
+            milestone: None,
+            version: 0,
+            ..previous.clone()
+        })
+    }
+
+    /// Creates the next instances of the series whose latest instance is done or due,
+    /// a series which fails is logged and tried again on the next pass
+    pub async fn create_next(connection: &Connection) -> QueryResult<Vec<Task>> {
+        let today = Local::now().date_naive();
+        let recurrences = connection
+            .run(|conn| recurrence::table.load::<Recurrence>(conn))
+            .await?;
+        let mut created = Vec::new();
+        for recurrence in recurrences {
+            let latest = recurrence.task;
+            match Self::create_series_next(recurrence, today, connection).await {
+                Ok(Some(task)) => created.push(task),
+                Ok(None) => {}
+                Err(e) => rocket::error!("Repeating task {}: {}", latest, e),
+            }
+        }
+        Ok(created)
+    }
+
+    async fn create_series_next(
+        recurrence: Recurrence,
+        today: NaiveDate,
+        connection: &Connection,
+    ) -> QueryResult<Option<Task>> {
+        let previous = match Task::get_single(recurrence.task, connection).await {
+            Ok(previous) => previous,
+            Err(diesel::result::Error::NotFound) => {
+                Self::delete(recurrence.task, connection).await?;
+                return Ok(None);
+            }
+            Err(e) => return Err(e),
+        };
+        let due = !previous.deadline.is_empty() && previous.deadline <= today.to_string();
+        if previous.done == 0 && !due {
+            return Ok(None);
+        }
+        let task = match recurrence.next_task(&previous, today) {
+            Some(task) if List::get_by_id(task.list, connection).await.is_ok() => task,
+            _ => {
+                Self::delete(recurrence.task, connection).await?;
+                return Ok(None);
+            }
+        };
+        // The series moves to the new instance with it, or neither happens
+        let new_task = task.clone();
+        let id = connection
+            .run(move |conn| {
+                diesel::Connection::transaction::<_, diesel::result::Error, _>(conn, || {
+                    diesel::insert_into(task::table)
+                        .values(new_task)
+                        .execute(conn)?;
+                    let id = diesel::select(last_insert_rowid).get_result::<i32>(conn)?;
+                    diesel::update(recurrence::table.filter(recurrence::id.eq(recurrence.id)))
+                        .set((
+                            recurrence::task.eq(id),
+                            recurrence::count.eq(recurrence.count + 1),
+                        ))
+                        .execute(conn)?;
+                    Ok(id)
+                })
+            })
+            .await?;
+        // Logged as created, like the tasks made by users
+        let log = Log::from_task(task.clone(), id, "created".to_owned());
+        let _ = Log::create(log, connection).await;
+        Ok(Some(Task {
+            id: Some(id),
+            ..task
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(date: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(date, "%Y-%m-%d").unwrap()
+    }
+
+    fn dates(rule: &str, start: &str, n: usize) -> Vec<String> {
+        let rule = Rule::parse(rule).unwrap();
+        let mut dates = Vec::new();
+        let mut current = date(start);
+        while let Some(next) = rule.next(current).filter(|_| dates.len() < n) {
+            dates.push(next.to_string());
+            current = next;
+        }
+        dates
+    }
+
+    #[test]
+    fn test_parse() {
+        let rule = Rule::parse("RRULE:FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,th;COUNT=3").unwrap();
+        assert_eq!(rule.frequency, Frequency::Weekly);
+        assert_eq!(rule.interval, 2);
+        assert_eq!(rule.weekdays, vec![Weekday::Mon, Weekday::Thu]);
+        assert_eq!(rule.count, Some(3));
+        let rule = Rule::parse("FREQ=DAILY;UNTIL=20261231T235959Z").unwrap();
+        assert_eq!(rule.until, Some(date("2026-12-31")));
+
+        assert!(Rule::parse("").is_err());
+        assert!(Rule::parse("FREQ=YEARLY").is_err());
+        assert!(Rule::parse("FREQ=DAILY;INTERVAL=0").is_err());
+        assert!(Rule::parse("FREQ=DAILY;BYDAY=MO").is_err());
+        assert!(Rule::parse("FREQ=MONTHLY;BYMONTHDAY=32").is_err());
+        assert!(Rule::parse("FREQ=WEEKLY;BYHOUR=9").is_err());
+    }
+
+    #[test]
+    fn test_next() {
+        assert_eq!(
+            dates("FREQ=DAILY;INTERVAL=3", "2026-10-30", 2),
+            vec!["2026-11-02", "2026-11-05"]
+        );
+        // 2026-10-19 is a Monday
+        assert_eq!(
+            dates("FREQ=WEEKLY;BYDAY=MO,TH", "2026-10-19", 3),
+            vec!["2026-10-22", "2026-10-26", "2026-10-29"]
+        );
+        assert_eq!(
+            dates("FREQ=WEEKLY;INTERVAL=2;BYDAY=MO,TH", "2026-10-22", 2),
+            vec!["2026-11-02", "2026-11-05"]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=31", "2026-12-31", 3),
+            vec!["2027-01-31", "2027-02-28", "2027-03-31"]
+        );
+        assert_eq!(
+            dates("FREQ=MONTHLY;BYMONTHDAY=20", "2026-10-10", 2),
+            vec!["2026-10-20", "2026-11-20"]
+        );
+        assert_eq!(
+            dates("FREQ=WEEKLY;UNTIL=20261102", "2026-10-19", 5),
+            vec!["2026-10-26", "2026-11-02"]
+        );
+    }
+
+    #[test]
+    fn test_next_task() {
+        let task = Task {
+            id: Some(1),
+            name: "release notes".to_string(),
+            list: 1,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "2026-10-05".to_string(),
+            subtasks: "".to_string(),
+            points: 2,
+            tags: "".to_string(),
+            done: 1,
+            milestone: Some(3),
+            version: 7,
+            parent: None,
+        };
+        let mut recurrence = Recurrence {
+            id: Some(1),
+            task: 1,
+            rule: "FREQ=WEEKLY;COUNT=3".to_string(),
+            list: 2,
+            count: 2,
+        };
+        // The missed 2026-10-12 and 2026-10-19 are skipped
+        let next = recurrence.next_task(&task, date("2026-10-19")).unwrap();
+        assert_eq!((next.id, next.list, next.done), (None, 2, 0));
+        assert_eq!((next.deadline.as_str(), next.version), ("2026-10-26", 0));
+        assert_eq!(next.name, task.name);
+
+        recurrence.count = 3;
+        assert!(recurrence.next_task(&task, date("2026-10-19")).is_none());
+    }
+}
@@ -11,6 +11,7 @@ table! {
         id -> Nullable<Integer>,
         name -> Varchar,
         owner -> Integer,
+        version -> Integer,
     }
 }
 
@@ -35,6 +36,7 @@ table! {
         id -> Nullable<Integer>,
         name -> Varchar,
         owner -> Integer,
+        version -> Integer,
     }
 }
 
@@ -61,6 +63,8 @@ table! {
         tags -> Varchar,
         done -> Integer,
         milestone -> Nullable<Integer>,
+        version -> Integer,
+        parent -> Nullable<Integer>,
     }
 }
 
@@ -70,6 +74,10 @@ table! {
         name -> Varchar,
         board_id -> Integer,
         board_type -> Varchar,
+        description -> Nullable<Text>,
+        start_date -> Nullable<Varchar>,
+        due_date -> Nullable<Varchar>,
+        status -> Varchar,
     }
 }
 
@@ -88,6 +96,9 @@ table! {
         subtasks -> Varchar,
         points -> Integer,
         tags -> Varchar,
+        done -> Integer,
+        milestone -> Nullable<Integer>,
+        changes -> Nullable<Text>,
     }
 }
 
@@ -99,5 +110,134 @@ table! {
         status -> Varchar,
         time -> Integer,
         start -> Nullable<Integer>,
+        review -> Integer,
+        kind -> Varchar,
+        work -> Integer,
+        short_break -> Integer,
+        long_break -> Integer,
+        cycles -> Integer,
+        pomodoros -> Integer,
+        category -> Nullable<Integer>,
+        billable -> Integer,
+        client -> Nullable<Varchar>,
+        board_id -> Nullable<Integer>,
+        board_type -> Nullable<Varchar>,
+        review_session -> Nullable<Integer>,
+    }
+}
+
+table! {
+    timer_settings(id) {
+        id -> Nullable<Integer>,
+        user_id -> Integer,
+        idle_limit -> Integer,
+    }
+}
+
+table! {
+    timer_category(id) {
+        id -> Nullable<Integer>,
+        name -> Varchar,
+        color -> Varchar,
+        user_id -> Nullable<Integer>,
+        team_id -> Nullable<Integer>,
+    }
+}
+
+table! {
+    timer_session(id) {
+        id -> Nullable<Integer>,
+        timer_id -> Integer,
+        user_id -> Integer,
+        category -> Nullable<Integer>,
+        start -> Integer,
+        duration -> Integer,
+        billable -> Integer,
+    }
+}
+
+table! {
+    timer_goal(id) {
+        id -> Nullable<Integer>,
+        user_id -> Integer,
+        period -> Varchar,
+        category -> Nullable<Integer>,
+        target -> Integer,
+    }
+}
+
+table! {
+    billing_rate(id) {
+        id -> Nullable<Integer>,
+        scope -> Varchar,
+        scope_id -> Integer,
+        rate -> Integer,
+        currency -> Varchar,
+        rounding -> Integer,
+        rounding_mode -> Varchar,
+    }
+}
+
+table! {
+    milestone_dependency(id) {
+        id -> Nullable<Integer>,
+        milestone -> Integer,
+        predecessor -> Integer,
+    }
+}
+
+table! {
+    saved_filter(id) {
+        id -> Nullable<Integer>,
+        name -> Varchar,
+        user_id -> Integer,
+        board_type -> Varchar,
+        board_id -> Integer,
+        shared -> Integer,
+        filter -> Text,
+    }
+}
+
+table! {
+    comment(id) {
+        id -> Nullable<Integer>,
+        task_id -> Integer,
+        parent -> Nullable<Integer>,
+        author -> Integer,
+        body -> Text,
+        mentions -> Text,
+        created -> Varchar,
+        edited -> Nullable<Varchar>,
+        deleted -> Integer,
+    }
+}
+
+table! {
+    attachment(id) {
+        id -> Nullable<Integer>,
+        task_id -> Integer,
+        name -> Varchar,
+        content_type -> Varchar,
+        size -> BigInt,
+        uploader -> Integer,
+        created -> Varchar,
+    }
+}
+
+table! {
+    task_dependency(id) {
+        id -> Nullable<Integer>,
+        task -> Integer,
+        blocker -> Integer,
+    }
+}
+
+table! {
+    recurrence(id) {
+        id -> Nullable<Integer>,
+        task -> Integer,
+        rule -> Varchar,
+        list -> Integer,
+        count -> Integer,
     }
 }
@@ -0,0 +1,109 @@
+use crate::db::Connection;
+use diesel::prelude::*;
+use diesel::sql_types::{Double, Integer, Nullable, Text};
+use rocket::serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, QueryableByName, Debug)]
+pub struct SearchResult {
+    #[sql_type = "Integer"]
+    pub task_id: i32,
+    #[sql_type = "Text"]
+    pub name: String,
+    #[sql_type = "Integer"]
+    pub done: i32,
+    #[sql_type = "Integer"]
+    pub list_id: i32,
+    #[sql_type = "Text"]
+    pub list_name: String,
+    #[sql_type = "Integer"]
+    pub board_id: i32,
+    #[sql_type = "Text"]
+    pub board_type: String,
+    #[sql_type = "Nullable<Text>"]
+    pub board_name: Option<String>,
+    #[sql_type = "Text"]
+    pub snippet: String, // Matched text, with the matches in [brackets]
+    #[sql_type = "Double"]
+    pub rank: f64, // bm25, lower is more relevant
+}
+
+/// Turns user input into an FTS5 query matching every word as a prefix,
+/// so quotes and operators in the input can't break the query
+pub fn fts_query(input: &str) -> Option<String> {
+    let words = input
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(|word| format!("\"{}\"*", word))
+        .collect::<Vec<_>>();
+    if words.is_empty() {
+        None
+    } else {
+        Some(words.join(" "))
+    }
+}
+
+/// Tasks matching the query on the boards the user can access, most relevant first.
+/// `board` limits the search to a single board.
+pub async fn search(
+    query: String,
+    user_id: i32,
+    board: Option<(String, i32)>,
+    limit: i32,
+    connection: &Connection,
+) -> QueryResult<Vec<SearchResult>> {
+    let (board_type, board_id) = board.unwrap_or_default();
+    connection
+        .run(move |conn| {
+            diesel::sql_query(
+                "SELECT task.id AS task_id, task.name AS name, COALESCE(task.done, 0) AS done, \
+                    list.id AS list_id, list.name AS list_name, \
+                    list.board AS board_id, list.board_type AS board_type, \
+                    CASE list.board_type \
+                        WHEN 'private' THEN (SELECT name FROM private_board WHERE id = list.board) \
+                        ELSE (SELECT name FROM team_board WHERE id = list.board) \
+                    END AS board_name, \
+                    snippet(task_search, -1, '[', ']', '...', 12) AS snippet, \
+                    bm25(task_search) AS rank \
+                FROM task_search \
+                JOIN task ON task.id = task_search.rowid \
+                JOIN list ON list.id = task.list \
+                WHERE task_search MATCH ? \
+                    AND ((list.board_type = 'private' AND list.board IN \
+                            (SELECT id FROM private_board WHERE owner = ?)) \
+                        OR (list.board_type = 'team' AND list.board IN \
+                            (SELECT team_board.id FROM team_board \
+                            JOIN team_user ON team_user.team = team_board.owner \
+                            WHERE team_user.user = ?))) \
+                    AND (? = '' OR (list.board_type = ? AND list.board = ?)) \
+                ORDER BY rank \
+                LIMIT ?",
+            )
+            .bind::<Text, _>(query)
+            .bind::<Integer, _>(user_id)
+            .bind::<Integer, _>(user_id)
+            .bind::<Text, _>(board_type.clone())
+            .bind::<Text, _>(board_type)
+            .bind::<Integer, _>(board_id)
+            .bind::<Integer, _>(limit)
+            .load::<SearchResult>(conn)
+        })
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("login bug"),
+            Some("\"login\"* \"bug\"*".to_owned())
+        );
+        assert_eq!(
+            fts_query("\"oauth\" OR -x*"),
+            Some("\"oauth\"* \"OR\"* \"x\"*".to_owned())
+        );
+        assert_eq!(fts_query("  ;\" "), None);
+    }
+}
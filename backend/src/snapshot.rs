@@ -0,0 +1,170 @@
+use crate::board::{PrivateBoard, TeamBoard};
+use crate::db::Connection;
+use crate::list::List;
+use crate::milestone::{Milestone, MilestoneResponse};
+use crate::schema::{list, task};
+use crate::task::{Task, TaskResponse};
+use diesel::prelude::*;
+use rocket::http::Header;
+use rocket::request::{self, FromRequest, Request};
+use rocket::serde::json::{serde_json, Json};
+use rocket::serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::convert::Infallible;
+use std::hash::{Hash, Hasher};
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct SnapshotCounts {
+    pub lists: usize,
+    pub tasks: usize,
+    pub done: usize,
+    pub points: i32,
+    pub milestones: usize,
+}
+
+/// Everything needed to show a board, in one response
+#[derive(Serialize, Deserialize, Debug)]
+pub struct Snapshot {
+    pub board_type: String,
+    pub board_id: i32,
+    pub name: String,
+    pub lists: Vec<List>,
+    pub tasks: Vec<TaskResponse>,
+    pub milestones: Vec<MilestoneResponse>,
+    pub counts: SnapshotCounts,
+}
+
+impl Snapshot {
+    pub async fn get(
+        board_type: String,
+        board_id: i32,
+        connection: &Connection,
+    ) -> QueryResult<Snapshot> {
+        let name = match board_type.as_str() {
+            "private" => PrivateBoard::get_by_id(board_id, connection).await?.name,
+            _ => TeamBoard::get_by_id(board_id, connection).await?.name,
+        };
+        let board = board_type.clone();
+        let (lists, tasks) = connection
+            .run(move |conn| {
+                let lists = list::table
+                    .filter(list::board_type.eq(board))
+                    .filter(list::board.eq(board_id))
+                    .order(list::id)
+                    .load::<List>(conn)?;
+                let tasks = task::table
+                    .filter(task::list.eq_any(lists.iter().filter_map(|list| list.id)))
+                    .order(task::id)
+                    .load::<Task>(conn)?;
+                Ok::<_, diesel::result::Error>((lists, tasks))
+            })
+            .await?;
+        let milestones = Milestone::get(board_id, board_type.clone(), connection).await?;
+        let counts = SnapshotCounts {
+            lists: lists.len(),
+            tasks: tasks.len(),
+            done: tasks.iter().filter(|task| task.done == 1).count(),
+            points: tasks.iter().map(|task| task.points.max(0)).sum(),
+            milestones: milestones.len(),
+        };
+        let tasks = Task::responses(tasks, connection).await?;
+        Ok(Snapshot {
+            board_type,
+            board_id,
+            name,
+            lists,
+            tasks,
+            milestones,
+            counts,
+        })
+    }
+
+    /// Strong ETag, the hash of the snapshot's JSON
+    pub fn etag(&self) -> String {
+        let mut hasher = DefaultHasher::new();
+        serde_json::to_string(self)
+            .unwrap_or_default()
+            .hash(&mut hasher);
+        format!("\"{:016x}\"", hasher.finish())
+    }
+}
+
+/// `If-None-Match` header of the request
+pub struct IfNoneMatch(pub Option<String>);
+
+impl IfNoneMatch {
+    /// Whether the client already has the version with this ETag
+    pub fn matches(&self, etag: &str) -> bool {
+        self.0
+            .iter()
+            .flat_map(|header| header.split(','))
+            .map(|tag| tag.trim())
+            .any(|tag| tag == "*" || tag.trim_start_matches("W/") == etag)
+    }
+}
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for IfNoneMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'a Request<'_>) -> request::Outcome<IfNoneMatch, Infallible> {
+        let header = request.headers().get_one("If-None-Match");
+        request::Outcome::Success(IfNoneMatch(header.map(|header| header.to_owned())))
+    }
+}
+
+/// `If-Match` header with the version the client edited, quoted like an ETag or bare
+pub struct IfMatch(pub Option<i32>);
+
+#[rocket::async_trait]
+impl<'a> FromRequest<'a> for IfMatch {
+    type Error = Infallible;
+
+    async fn from_request(request: &'a Request<'_>) -> request::Outcome<IfMatch, Infallible> {
+        let version = request.headers().get_one("If-Match").and_then(|header| {
+            header
+                .trim()
+                .trim_start_matches("W/")
+                .trim_matches('"')
+                .parse()
+                .ok()
+        });
+        request::Outcome::Success(IfMatch(version))
+    }
+}
+
+#[derive(Responder)]
+pub enum SnapshotResponse {
+    Changed(Json<Snapshot>, Header<'static>, Header<'static>),
+    #[response(status = 304)]
+    NotModified((), Header<'static>, Header<'static>),
+}
+
+impl SnapshotResponse {
+    /// 304 without a body when the client's copy is current
+    pub fn new(snapshot: Snapshot, if_none_match: IfNoneMatch) -> SnapshotResponse {
+        let etag = snapshot.etag();
+        // The browser has to revalidate, the board changes all the time
+        let cache = Header::new("Cache-Control", "private, no-cache");
+        if if_none_match.matches(&etag) {
+            SnapshotResponse::NotModified((), Header::new("ETag", etag), cache)
+        } else {
+            SnapshotResponse::Changed(Json(snapshot), Header::new("ETag", etag), cache)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_if_none_match() {
+        let header = |value: &str| IfNoneMatch(Some(value.to_owned()));
+        assert!(header("\"a\"").matches("\"a\""));
+        assert!(header("\"b\", W/\"a\"").matches("\"a\""));
+        assert!(header("*").matches("\"a\""));
+        assert!(!header("\"b\"").matches("\"a\""));
+        assert!(!IfNoneMatch(None).matches("\"a\""));
+    }
+}
@@ -1,12 +1,22 @@
+use crate::attachment::Attachment;
+use crate::comment::Comment;
 use crate::db::Connection;
 use crate::log::Log;
-use crate::schema::task;
+use crate::page::Page;
+use crate::query::{self, Predicate};
+use crate::recurrence::Recurrence;
+use crate::schema::{list, recurrence, task, task_dependency};
 use crate::types::TaskFilter;
+use crate::utils::reaches;
+use diesel::dsl::not;
 use diesel::prelude::*;
+use diesel::sql_types::{Integer, Nullable};
+use diesel::sqlite::Sqlite;
 use diesel::AsChangeset;
 use diesel::Insertable;
 use diesel::Queryable;
 use rocket::serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
 no_arg_sql_function!(
     last_insert_rowid,
@@ -30,39 +40,106 @@ pub struct Task {
     pub tags: String,
     pub done: i32, // In sqlite we don't have boolean types
     pub milestone: Option<i32>,
+    #[serde(default)]
+    pub version: i32, // Incremented by every update, which has to name the version it changes
+    #[serde(default)]
+    pub parent: Option<i32>, // Epic the task belongs to, on the same board
+}
+
+/// Progress of the task's children, tasks without points count as 0
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+pub struct Rollup {
+    pub total: i32,
+    pub done: i32,
+    pub points_total: i32,
+    pub points_done: i32,
+}
+
+/// Task with its attachments, dependencies and recurrence, as the task endpoints send it
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TaskResponse {
+    #[serde(flatten)]
+    pub task: Task,
+    pub attachments: Vec<Attachment>,
+    pub blocked_by: Vec<i32>, // Tasks which have to be done first
+    pub blocks: Vec<i32>,
+    pub blocked: bool, // Some of `blocked_by` aren't done
+    pub children: Rollup,
+    pub recurrence: Option<Recurrence>, // Set on the latest instance of a series
+}
+
+/// Subselect of the ids of the tasks with children
+pub fn epic_ids() -> task::BoxedQuery<'static, Sqlite, Nullable<Integer>> {
+    task::table
+        .filter(task::parent.is_not_null())
+        .select(task::parent)
+        .into_boxed()
+}
+
+/// Subselect of the ids of the tasks with blockers which aren't done
+pub fn blocked_ids() -> task_dependency::BoxedQuery<'static, Sqlite, Nullable<Integer>> {
+    let open = task::table
+        .filter(task::done.eq(0))
+        .select(task::id)
+        .into_boxed::<Sqlite>();
+    task_dependency::table
+        .filter(task_dependency::blocker.nullable().eq_any(open))
+        .select(task_dependency::task.nullable())
+        .into_boxed()
 }
 
 impl Task {
-    pub async fn create(task: Task, connection: &Connection) -> QueryResult<usize> {
+    /// Id of the new task
+    pub async fn create(task: Task, connection: &Connection) -> QueryResult<i32> {
         let task_clone = task.clone();
-        let res = connection
+        let id = connection
             .run(|conn| {
                 diesel::insert_into(task::table)
                     .values(task_clone)
-                    .execute(conn)
+                    .execute(conn)?;
+                diesel::select(last_insert_rowid).get_result::<i32>(conn)
             })
-            .await;
-        if res.is_ok() {
-            let id = connection
-                .run(|conn| diesel::select(last_insert_rowid).get_result::<i32>(conn))
-                .await;
-            let log = Log::from_task(task, id.unwrap(), "created".to_owned());
-            let _ = Log::create(log, connection).await;
-        }
-        res
+            .await?;
+        let log = Log::from_task(task, id, "created".to_owned());
+        let _ = Log::create(log, connection).await;
+        Ok(id)
     }
 
+    /// Applies the task if it's still at its `version`, 0 rows when somebody changed it since
     pub async fn update(task: Task, connection: &Connection) -> QueryResult<usize> {
-        let task_clone = task.clone();
+        Self::save(task, None, connection).await
+    }
+
+    /// Update made by a patch, `changes` is the patch's JSON which its log keeps
+    pub async fn patch(task: Task, changes: String, connection: &Connection) -> QueryResult<usize> {
+        Self::save(task, Some(changes), connection).await
+    }
+
+    async fn save(
+        task: Task,
+        changes: Option<String>,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        let task_clone = Task {
+            version: task.version + 1,
+            ..task.clone()
+        };
         let res = connection
-            .run(|conn| {
-                diesel::update(task::table.filter(task::id.eq(task_clone.id.unwrap())))
-                    .set(task_clone)
-                    .execute(conn)
+            .run(move |conn| {
+                diesel::update(
+                    task::table
+                        .filter(task::id.eq(task_clone.id.unwrap()))
+                        .filter(task::version.eq(task_clone.version - 1)),
+                )
+                .set(task_clone)
+                .execute(conn)
             })
             .await;
-        if res.is_ok() {
-            let log = Log::from_task(task.clone(), task.id.unwrap(), "updated".to_owned());
+        if matches!(res, Ok(cnt) if cnt > 0) {
+            let log = Log {
+                changes,
+                ..Log::from_task(task.clone(), task.id.unwrap(), "updated".to_owned())
+            };
             let _ = Log::create(log, connection).await;
         }
         res
@@ -74,46 +151,204 @@ impl Task {
             .await
     }
 
-    pub async fn filter(
+    /// Sortable fields of the task listings
+    pub const SORT: [(&'static str, &'static str); 5] = [
+        ("deadline", "task.deadline"),
+        ("points", "task.points"),
+        ("name", "task.name"),
+        ("created", "task.id"),
+        (
+            "updated",
+            "(SELECT MAX(log.id) FROM log WHERE log.task_id = task.id)",
+        ),
+    ];
+
+    /// Page of the list's tasks and the number of all of them
+    pub async fn get_page(
         id: i32,
-        data: TaskFilter,
+        page: Page,
         connection: &Connection,
-    ) -> QueryResult<Vec<Task>> {
+    ) -> QueryResult<(Vec<Task>, i64)> {
         connection
             .run(move |conn| {
-                let mut query = task::table.into_boxed();
-                query = query.filter(task::name.like(format!("%{}%", data.name)));
-                query = query.filter(task::place.like(format!("%{}%", data.place)));
+                let total = task::table
+                    .filter(task::list.eq(id))
+                    .count()
+                    .get_result(conn)?;
+                let tasks = task::table
+                    .filter(task::list.eq(id))
+                    .order(page.order())
+                    .offset(page.offset)
+                    .limit(page.sql_limit())
+                    .load::<Task>(conn)?;
+                Ok((tasks, total))
+            })
+            .await
+    }
 
-                if let Some(mini) = data.points_min {
-                    query = query.filter(task::points.ge(mini));
-                }
-                if let Some(maxi) = data.points_max {
-                    query = query.filter(task::points.le(maxi));
-                }
+    /// Narrows the query to the tasks matching the filter
+    fn apply_filter(
+        mut query: task::BoxedQuery<'static, Sqlite>,
+        data: TaskFilter,
+        conn: &SqliteConnection,
+    ) -> QueryResult<task::BoxedQuery<'static, Sqlite>> {
+        let terms = query::parse(&data.query)
+            .map_err(|e| diesel::result::Error::QueryBuilderError(Box::new(e)))?;
+        query = query.filter(task::name.like(format!("%{}%", data.name)));
+        query = query.filter(task::place.like(format!("%{}%", data.place)));
 
-                if !data.deadline_start.is_empty() {
-                    query = query.filter(task::deadline.ge(data.deadline_start));
-                }
-                if !data.deadline_end.is_empty() {
-                    query = query.filter(task::deadline.le(data.deadline_end));
-                }
+        if let Some(mini) = data.points_min {
+            query = query.filter(task::points.ge(mini));
+        }
+        if let Some(maxi) = data.points_max {
+            query = query.filter(task::points.le(maxi));
+        }
 
-                for member in data.members.split(';') {
-                    query = query.filter(task::members.like(format!("%{}%", member)));
-                }
+        if !data.deadline_start.is_empty() {
+            query = query.filter(task::deadline.ge(data.deadline_start));
+        }
+        if !data.deadline_end.is_empty() {
+            query = query.filter(task::deadline.le(data.deadline_end));
+        }
 
-                for tag in data.tags.split(';') {
-                    query = query.filter(task::tags.like(format!("%{}%", tag)));
-                }
-                query.filter(task::list.eq(id)).load::<Task>(conn)
+        // Empty parts match everything, including tasks without members
+        let members = data
+            .members
+            .split(';')
+            .filter(|member| !member.is_empty())
+            .map(|member| format!("%{}%", member));
+        if data.members_any {
+            let members = members.map(|member| Box::new(task::members.like(member)) as Predicate);
+            if let Some(any) = query::any(members) {
+                query = query.filter(any);
+            }
+        } else {
+            for member in members {
+                query = query.filter(task::members.like(member));
+            }
+        }
+
+        let tags = data
+            .tags
+            .split(';')
+            .filter(|tag| !tag.is_empty())
+            .map(|tag| format!("%{}%", tag));
+        if data.tags_any {
+            let tags = tags.map(|tag| Box::new(task::tags.like(tag)) as Predicate);
+            if let Some(any) = query::any(tags) {
+                query = query.filter(any);
+            }
+        } else {
+            for tag in tags {
+                query = query.filter(task::tags.like(tag));
+            }
+        }
+
+        if let Some(done) = data.done {
+            query = query.filter(task::done.eq(done as i32));
+        }
+        if let Some(milestone) = data.milestone {
+            query = query.filter(task::milestone.eq(milestone));
+        }
+        if data.overdue {
+            let today = chrono::Local::now().date_naive().to_string();
+            query = query
+                .filter(task::deadline.ne(""))
+                .filter(task::deadline.lt(today))
+                .filter(task::done.eq(0));
+        }
+        if data.no_deadline {
+            query = query.filter(task::deadline.eq(""));
+        }
+        if data.unassigned {
+            query = query.filter(task::members.is_null().or(task::members.eq("")));
+        }
+        if data.open_subtasks {
+            query = query.filter(task::subtasks.ne("")).filter(task::done.eq(0));
+        }
+        if let Some(epic) = data.epic {
+            if epic {
+                query = query.filter(task::id.eq_any(epic_ids()));
+            } else {
+                query = query.filter(not(task::id.eq_any(epic_ids())));
+            }
+        }
+        if let Some(parent) = data.parent {
+            query = query.filter(task::parent.eq(parent));
+        }
+        if let Some(blocked) = data.blocked {
+            if blocked {
+                query = query.filter(task::id.eq_any(blocked_ids()));
+            } else {
+                query = query.filter(not(task::id.eq_any(blocked_ids())));
+            }
+        }
+        query::apply(query, terms, conn)
+    }
+
+    pub async fn filter(
+        id: i32,
+        data: TaskFilter,
+        connection: &Connection,
+    ) -> QueryResult<Vec<Task>> {
+        connection
+            .run(move |conn| {
+                Self::apply_filter(task::table.into_boxed(), data, conn)?
+                    .filter(task::list.eq(id))
+                    .load::<Task>(conn)
+            })
+            .await
+    }
+
+    /// Tasks matching the filter on every list of the board
+    pub async fn filter_board(
+        board_type: String,
+        board_id: i32,
+        data: TaskFilter,
+        connection: &Connection,
+    ) -> QueryResult<Vec<Task>> {
+        connection
+            .run(move |conn| {
+                let lists = list::table
+                    .filter(list::board_type.eq(board_type))
+                    .filter(list::board.eq(board_id))
+                    .select(list::id)
+                    .load::<Option<i32>>(conn)?;
+                Self::apply_filter(task::table.into_boxed(), data, conn)?
+                    .filter(task::list.eq_any(lists.into_iter().flatten()))
+                    .order((task::list, task::id))
+                    .load::<Task>(conn)
             })
             .await
     }
 
     pub async fn delete(id: i32, connection: &Connection) -> QueryResult<usize> {
+        // Logged so the milestone history sees the task leaving its scope
+        if let Ok(task) = Self::get_single(id, connection).await {
+            let log = Log::from_task(task, id, "deleted".to_owned());
+            let _ = Log::create(log, connection).await;
+        }
+        let _ = Comment::delete_task_comments(id, connection).await;
         connection
-            .run(move |conn| diesel::delete(task::table.filter(task::id.eq(id))).execute(conn))
+            .run(move |conn| {
+                diesel::delete(
+                    task_dependency::table.filter(
+                        task_dependency::task
+                            .eq(id)
+                            .or(task_dependency::blocker.eq(id)),
+                    ),
+                )
+                .execute(conn)?;
+                diesel::delete(recurrence::table.filter(recurrence::task.eq(id))).execute(conn)?;
+                // Children stay on the board without a parent
+                diesel::update(task::table.filter(task::parent.eq(id)))
+                    .set((
+                        task::parent.eq(None::<i32>),
+                        task::version.eq(task::version + 1),
+                    ))
+                    .execute(conn)?;
+                diesel::delete(task::table.filter(task::id.eq(id))).execute(conn)
+            })
             .await
     }
 
@@ -122,4 +357,157 @@ impl Task {
             .run(move |conn| task::table.filter(task::id.eq(id)).first(conn))
             .await
     }
+
+    /// (task, blocker) pairs where the tasks block or are blocked
+    pub async fn dependencies(
+        ids: Vec<i32>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<(i32, i32)>> {
+        connection
+            .run(move |conn| {
+                task_dependency::table
+                    .filter(
+                        task_dependency::task
+                            .eq_any(ids.clone())
+                            .or(task_dependency::blocker.eq_any(ids)),
+                    )
+                    .select((task_dependency::task, task_dependency::blocker))
+                    .order(task_dependency::blocker)
+                    .load::<(i32, i32)>(conn)
+            })
+            .await
+    }
+
+    /// Replaces the blockers of the task, returns false if that would create a cycle
+    pub async fn set_blockers(
+        id: i32,
+        blockers: Vec<i32>,
+        connection: &Connection,
+    ) -> QueryResult<bool> {
+        connection
+            .run(move |conn| {
+                diesel::Connection::transaction(conn, || {
+                    let edges = task_dependency::table
+                        .filter(task_dependency::task.ne(id))
+                        .select((task_dependency::task, task_dependency::blocker))
+                        .load::<(i32, i32)>(conn)?;
+                    if blockers.iter().any(|&blocker| reaches(&edges, blocker, id)) {
+                        return Ok(false);
+                    }
+                    diesel::delete(task_dependency::table.filter(task_dependency::task.eq(id)))
+                        .execute(conn)?;
+                    for blocker in blockers {
+                        diesel::replace_into(task_dependency::table)
+                            .values((
+                                task_dependency::task.eq(id),
+                                task_dependency::blocker.eq(blocker),
+                            ))
+                            .execute(conn)?;
+                    }
+                    Ok(true)
+                })
+            })
+            .await
+    }
+
+    /// Whether the task can be a child of the parent, false if that would create a cycle
+    pub async fn can_have_parent(
+        id: Option<i32>,
+        parent: i32,
+        connection: &Connection,
+    ) -> QueryResult<bool> {
+        let id = match id {
+            Some(id) => id,
+            None => return Ok(true),
+        };
+        connection
+            .run(move |conn| {
+                let edges = task::table
+                    .filter(task::parent.is_not_null())
+                    .filter(task::id.ne(id))
+                    .select((task::id, task::parent))
+                    .load::<(Option<i32>, Option<i32>)>(conn)?
+                    .into_iter()
+                    .filter_map(|(task, parent)| Some((task?, parent?)))
+                    .collect::<Vec<_>>();
+                Ok(!reaches(&edges, parent, id))
+            })
+            .await
+    }
+
+    /// Tasks whose parent is the task
+    pub async fn children(id: i32, connection: &Connection) -> QueryResult<Vec<i32>> {
+        connection
+            .run(move |conn| {
+                task::table
+                    .filter(task::parent.eq(id))
+                    .select(task::id)
+                    .load::<Option<i32>>(conn)
+            })
+            .await
+            .map(|ids| ids.into_iter().flatten().collect())
+    }
+
+    /// The tasks with their attachments, dependencies, recurrences and the progress of their children
+    pub async fn responses(
+        tasks: Vec<Task>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<TaskResponse>> {
+        let ids = tasks.iter().filter_map(|task| task.id).collect::<Vec<_>>();
+        let mut attachments = Attachment::get_tasks(ids.clone(), connection).await?;
+        let dependencies = Self::dependencies(ids.clone(), connection).await?;
+        let mut recurrences = Recurrence::get_tasks(ids.clone(), connection).await?;
+        let children = connection
+            .run(move |conn| {
+                task::table
+                    .filter(task::parent.eq_any(ids))
+                    .select((task::parent, task::done, task::points))
+                    .load::<(Option<i32>, i32, i32)>(conn)
+            })
+            .await?;
+        let mut rollups = HashMap::<i32, Rollup>::new();
+        for (parent, done, points) in children {
+            let rollup = rollups.entry(parent.unwrap_or_default()).or_default();
+            rollup.total += 1;
+            rollup.done += done;
+            rollup.points_total += points.max(0);
+            rollup.points_done += points.max(0) * done;
+        }
+        let blockers = dependencies.iter().map(|d| d.1).collect::<Vec<_>>();
+        let open = connection
+            .run(move |conn| {
+                task::table
+                    .filter(task::id.eq_any(blockers))
+                    .filter(task::done.eq(0))
+                    .select(task::id)
+                    .load::<Option<i32>>(conn)
+            })
+            .await?;
+        Ok(tasks
+            .into_iter()
+            .map(|task| {
+                let id = task.id.unwrap_or_default();
+                let blocked_by = dependencies
+                    .iter()
+                    .filter(|d| d.0 == id)
+                    .map(|d| d.1)
+                    .collect::<Vec<_>>();
+                let mut blocks = dependencies
+                    .iter()
+                    .filter(|d| d.1 == id)
+                    .map(|d| d.0)
+                    .collect::<Vec<_>>();
+                blocks.sort_unstable();
+                TaskResponse {
+                    attachments: attachments.remove(&id).unwrap_or_default(),
+                    blocked: blocked_by.iter().any(|&b| open.contains(&Some(b))),
+                    blocked_by,
+                    blocks,
+                    children: rollups.remove(&id).unwrap_or_default(),
+                    recurrence: recurrences.remove(&id),
+                    task,
+                }
+            })
+            .collect())
+    }
 }
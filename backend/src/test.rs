@@ -3,6 +3,8 @@ use rocket::local::blocking::Client;
 use super::*;
 
 fn rocket() -> Rocket<Build> {
+    let attachments = std::env::temp_dir().join("attachments");
+    std::fs::create_dir_all(&attachments).unwrap();
     rocket::build()
         .mount(
             "/",
@@ -34,10 +36,69 @@ fn rocket() -> Rocket<Build> {
                 timer_create,
                 timer_delete,
                 timer_update,
-                get_timers
+                get_timers,
+                get_timer_settings,
+                timer_settings,
+                timer_review,
+                timer_pomodoro,
+                get_categories,
+                category_create,
+                category_delete,
+                timer_category,
+                timer_sessions,
+                session_category,
+                timers_report,
+                get_goals,
+                goal_create,
+                goal_delete,
+                goals_progress,
+                timer_start,
+                timer_pause,
+                timer_stop,
+                timer_billing,
+                session_billable,
+                get_rates,
+                set_rate,
+                rate_delete,
+                billing_summary,
+                billing_export,
+                milestone_update,
+                milestone_delete,
+                milestone_tasks,
+                milestone_set_tasks,
+                milestone_burndown,
+                milestone_predecessors,
+                board_roadmap,
+                team_roadmap,
+                search_tasks,
+                filter_board_tasks,
+                get_saved_filters,
+                saved_filter_create,
+                saved_filter_delete,
+                board_snapshot,
+                board_events,
+                ticket,
+                patch_task,
+                task_comments,
+                comment_create,
+                comment_update,
+                comment_delete,
+                board_comments,
+                attachment_create,
+                attachment_download,
+                attachment_delete,
+                task_blockers,
+                set_recurrence,
+                delete_recurrence
             ],
         )
         .attach(Connection::fairing())
+        .manage(Events::new())
+        .manage(AttachmentConfig {
+            dir: attachments,
+            max_file_size: 64,
+            max_board_size: 100,
+        })
 }
 
 fn get_test_user() -> Credentials {
@@ -56,6 +117,19 @@ fn login(client: &Client, user: &Credentials) -> TokenResponse {
         .unwrap()
 }
 
+fn ticket(client: &Client, token: &TokenResponse) -> String {
+    client
+        .get("/ticket")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            token.token.clone(),
+        ))
+        .dispatch()
+        .into_json::<TokenResponse>()
+        .unwrap()
+        .token
+}
+
 #[test]
 fn test_register() {
     let rocket = rocket();
@@ -184,6 +258,10 @@ fn test_milestone_creation() {
         name: "ala ma kota".to_string(),
         board_id: 1,
         board_type: "private".to_string(),
+        description: None,
+        start_date: None,
+        due_date: Some("2022-06-30".to_string()),
+        status: "open".to_string(),
     };
 
     client
@@ -209,6 +287,120 @@ fn test_milestone_creation() {
     assert_eq!(milestone.name, "ala ma kota");
     assert_eq!(milestone.done, 0);
     assert_eq!(milestone.total, 0);
+    assert_eq!(milestone.due_date, Some("2022-06-30".to_string()));
+
+    let invalid = [
+        Milestone {
+            id: None,
+            name: "starts after it's due".to_string(),
+            board_id: 1,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: Some("2022-07-01".to_string()),
+            due_date: Some("2022-06-30".to_string()),
+            status: "open".to_string(),
+        },
+        Milestone {
+            id: None,
+            name: "unknown status".to_string(),
+            board_id: 1,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: None,
+            due_date: Some("30.06.2022".to_string()),
+            status: "whatever".to_string(),
+        },
+    ];
+    for milestone in invalid {
+        let response = client
+            .post("/milestone/create")
+            .header(rocket::http::Header::new(
+                "Authorization",
+                token.token.clone(),
+            ))
+            .json(&milestone)
+            .dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+}
+
+#[test]
+fn test_milestone_update() {
+    test_milestone_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    let milestones = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap();
+    let id = milestones.last().unwrap().id.unwrap();
+    let mut update = MilestoneUpdate {
+        name: "Release".to_string(),
+        description: Some("First version".to_string()),
+        start_date: Some("2022-07-01".to_string()),
+        due_date: Some("2022-06-30".to_string()),
+        status: "closed".to_string(),
+    };
+    let response = client
+        .post(format!("/milestone/update/{}", id))
+        .header(auth())
+        .json(&update)
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    update.start_date = Some("2022-06-01".to_string());
+    let response = client
+        .post(format!("/milestone/update/{}", id))
+        .header(auth())
+        .json(&update)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let milestones = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap();
+    let milestone = milestones.iter().find(|m| m.id == Some(id)).unwrap();
+    assert_eq!(milestone.name, "Release");
+    assert_eq!(milestone.status, "closed");
+
+    // Another user can't touch the milestone
+    let other = Credentials {
+        username: "milestone_other".to_string(),
+        password: "test".to_string(),
+    };
+    let _ = client.post("/register").json(&other).dispatch();
+    let other_token = login(&client, &other);
+    let response = client
+        .get(format!("/milestone/delete/{}", id))
+        .header(rocket::http::Header::new(
+            "Authorization",
+            other_token.token,
+        ))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = client
+        .get(format!("/milestone/delete/{}", id))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let milestones = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap();
+    assert!(milestones.iter().all(|m| m.id != Some(id)));
 }
 
 #[test]
@@ -223,6 +415,7 @@ fn test_timer() {
 
     let timer = TimerData {
         name: "Ala ma kota".to_string(),
+        pomodoro: None,
     };
 
     client
@@ -345,6 +538,8 @@ fn test_list_and_task() {
         tags: "".to_string(),
         done: 0,
         milestone: None,
+        version: 0,
+        parent: None,
     };
 
     client
@@ -372,3 +567,3484 @@ fn test_list_and_task() {
     assert_eq!(0, task.done);
     assert!(task.milestone.is_none());
 }
+
+#[test]
+fn test_timer_settings() {
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+
+    let response = client
+        .post("/timer/settings")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            token.token.clone(),
+        ))
+        .json(&TimerSettingsData { idle_limit: 0 })
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    client
+        .post("/timer/settings")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            token.token.clone(),
+        ))
+        .json(&TimerSettingsData { idle_limit: 3600 })
+        .dispatch();
+    let settings = client
+        .get("/timer/settings")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            token.token.clone(),
+        ))
+        .dispatch()
+        .into_json::<TimerSettingsData>()
+        .unwrap();
+    assert_eq!(settings.idle_limit, 3600);
+
+    client
+        .post("/timer/create")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            token.token.clone(),
+        ))
+        .json(&TimerData {
+            name: "Ala ma kota".to_string(),
+            pomodoro: None,
+        })
+        .dispatch();
+    let timers = client
+        .get("/timers/get")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            token.token.clone(),
+        ))
+        .dispatch()
+        .into_json::<Vec<Timer>>()
+        .unwrap();
+    let timer = timers.last().unwrap();
+    assert_eq!(timer.review, 0);
+
+    // Nothing was stopped automatically, so there is nothing to correct
+    let response = client
+        .post(format!("/timer/review/{}", timer.id.unwrap()))
+        .header(rocket::http::Header::new("Authorization", token.token))
+        .json(&TimerReview { session: 60 })
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+}
+
+#[rocket::async_test]
+async fn test_stop_idle_timers() {
+    use crate::schema::timer;
+    use crate::timer::DEFAULT_IDLE_LIMIT;
+    use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+    use rocket::local::asynchronous::Client;
+
+    let client = Client::tracked(rocket()).await.expect("valid `Rocket`");
+    let connection = Connection::get_one(client.rocket()).await.unwrap();
+    // A user without settings, so the default limit applies
+    let user_id = -26;
+    let forgotten = |name: &str, idle: i32| Timer {
+        id: None,
+        name: name.to_string(),
+        user_id,
+        status: "active".to_string(),
+        time: 100,
+        start: Some(get_time() - idle),
+        review: 0,
+        kind: "simple".to_string(),
+        work: 0,
+        short_break: 0,
+        long_break: 0,
+        cycles: 0,
+        pomodoros: 0,
+        category: None,
+        billable: 0,
+        client: None,
+        board_id: None,
+        board_type: None,
+        review_session: None,
+    };
+    Timer::create(forgotten("forgotten", DEFAULT_IDLE_LIMIT + 60), &connection)
+        .await
+        .unwrap();
+    Timer::create(forgotten("running", 60), &connection)
+        .await
+        .unwrap();
+    Timer::stop_idle(&connection).await.unwrap();
+
+    let timers = connection
+        .run(move |conn| {
+            timer::table
+                .filter(timer::user_id.eq(user_id))
+                .order(timer::id)
+                .load::<Timer>(conn)
+        })
+        .await
+        .unwrap();
+    let (stopped, running) = (&timers[0], &timers[1]);
+    assert_eq!(stopped.status, "stopped");
+    assert_eq!(stopped.review, DEFAULT_IDLE_LIMIT);
+    assert_eq!(stopped.time, 100 + DEFAULT_IDLE_LIMIT);
+    let sessions = TimerSession::get(stopped.id.unwrap(), &connection)
+        .await
+        .unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].duration, DEFAULT_IDLE_LIMIT);
+    assert_eq!((running.status.as_str(), running.review), ("active", 0));
+
+    // Run again before the review, which still corrects the stopped session
+    let id = stopped.id.unwrap();
+    for status in ["active", "stopped"] {
+        let timer = Timer::get_by_id(id, &connection).await.unwrap();
+        Timer::set_status(timer, status, &connection).await.unwrap();
+    }
+    let timer = Timer::get_by_id(id, &connection).await.unwrap();
+    Timer::review(timer, 1800, &connection).await.unwrap();
+    let sessions = TimerSession::get(id, &connection).await.unwrap();
+    let durations = sessions.iter().map(|s| s.duration).collect::<Vec<_>>();
+    assert_eq!(durations, vec![0, 1800]);
+    let timer = Timer::get_by_id(id, &connection).await.unwrap();
+    assert_eq!(
+        (timer.time, timer.review, timer.review_session),
+        (1900, 0, None)
+    );
+
+    for timer in timers {
+        Timer::delete(timer.id.unwrap(), &connection).await.unwrap();
+    }
+}
+
+#[test]
+fn test_timer_categories() {
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    let response = client
+        .post("/timer/category/create")
+        .header(auth())
+        .json(&CategoryData {
+            name: "Meeting".to_string(),
+            color: "blue".to_string(),
+            team: None,
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    client
+        .post("/timer/category/create")
+        .header(auth())
+        .json(&CategoryData {
+            name: "Meeting".to_string(),
+            color: "#0000ff".to_string(),
+            team: None,
+        })
+        .dispatch();
+    let categories = client
+        .get("/timer/categories")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<TimerCategory>>()
+        .unwrap();
+    let category = categories.last().unwrap().id;
+
+    client
+        .post("/timer/create")
+        .header(auth())
+        .json(&TimerData {
+            name: "mtg".to_string(),
+            pomodoro: None,
+        })
+        .dispatch();
+    let timers = client
+        .get("/timers/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Timer>>()
+        .unwrap();
+    let id = timers.last().unwrap().id.unwrap();
+    let response = client
+        .post(format!("/timer/category/{}", id))
+        .header(auth())
+        .json(&CategoryAssignment { category })
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let timers = client
+        .get(format!("/timers/get?category={}", category.unwrap()))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Timer>>()
+        .unwrap();
+    assert_eq!(timers.len(), 1);
+    assert_eq!(timers[0].id, Some(id));
+
+    // Starting and stopping the timer records a session in its category
+    client
+        .get(format!("/timer/update/{}", id))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/timer/update/{}", id))
+        .header(auth())
+        .dispatch();
+    let sessions = client
+        .get(format!("/timer/sessions/{}", id))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<TimerSession>>()
+        .unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].category, category);
+
+    let report = client
+        .get("/timers/report")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<CategoryReport>>()
+        .unwrap();
+    assert!(report
+        .iter()
+        .any(|row| row.category == category && row.name == "Meeting"));
+}
+
+#[test]
+fn test_timer_goals() {
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    // A user of its own, the sessions of the other tests would count towards the goal
+    let user = Credentials {
+        username: "goals".to_string(),
+        password: "goals".to_string(),
+    };
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    let response = client
+        .post("/timer/goal/create")
+        .header(auth())
+        .json(&GoalData {
+            period: "month".to_string(),
+            category: None,
+            target: 3600,
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let response = client
+        .post("/timer/goal/create")
+        .header(auth())
+        .json(&GoalData {
+            period: "day".to_string(),
+            category: None,
+            target: 3600,
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    let progress = client
+        .get("/timer/goals/progress")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<GoalProgress>>()
+        .unwrap();
+    let goal = progress.last().unwrap();
+    assert_eq!(goal.goal.target, 3600);
+    assert!(goal.tracked < 3600);
+    assert_eq!(goal.streak, 0);
+
+    let response = client
+        .get(format!("/timer/goal/delete/{}", goal.goal.id.unwrap()))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+}
+
+#[test]
+fn test_timer_status() {
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/timer/create")
+        .header(auth())
+        .json(&TimerData {
+            name: "Widget".to_string(),
+            pomodoro: None,
+        })
+        .dispatch();
+    let timers = client
+        .get("/timers/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Timer>>()
+        .unwrap();
+    let id = timers.last().unwrap().id.unwrap();
+    let status = || {
+        client
+            .get("/timers/get")
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<Timer>>()
+            .unwrap()
+            .into_iter()
+            .find(|timer| timer.id == Some(id))
+            .unwrap()
+            .status
+    };
+
+    // Requests from two tabs must not toggle the timer back
+    for _ in 0..2 {
+        client
+            .get(format!("/timer/pause/{}", id))
+            .header(auth())
+            .dispatch();
+        assert_eq!(status(), "paused");
+    }
+    client
+        .get(format!("/timer/start/{}", id))
+        .header(auth())
+        .dispatch();
+    assert_eq!(status(), "active");
+    for _ in 0..2 {
+        client
+            .get(format!("/timer/stop/{}", id))
+            .header(auth())
+            .dispatch();
+        assert_eq!(status(), "stopped");
+    }
+    let sessions = client
+        .get(format!("/timer/sessions/{}", id))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<TimerSession>>()
+        .unwrap();
+    assert_eq!(sessions.len(), 2);
+}
+
+#[rocket::async_test]
+async fn test_concurrent_stops() {
+    use crate::schema::timer;
+    use diesel::{ExpressionMethods, QueryDsl, RunQueryDsl};
+    use rocket::local::asynchronous::Client;
+
+    let client = Client::tracked(rocket()).await.expect("valid `Rocket`");
+    let connection = Connection::get_one(client.rocket()).await.unwrap();
+    let user_id = -30;
+    Timer::create(
+        Timer {
+            id: None,
+            name: "two tabs".to_string(),
+            user_id,
+            status: "active".to_string(),
+            time: 0,
+            start: Some(get_time() - 60),
+            review: 0,
+            kind: "simple".to_string(),
+            work: 0,
+            short_break: 0,
+            long_break: 0,
+            cycles: 0,
+            pomodoros: 0,
+            category: None,
+            billable: 0,
+            client: None,
+            board_id: None,
+            board_type: None,
+            review_session: None,
+        },
+        &connection,
+    )
+    .await
+    .unwrap();
+    let id = connection
+        .run(move |conn| {
+            timer::table
+                .filter(timer::user_id.eq(user_id))
+                .select(timer::id)
+                .first::<Option<i32>>(conn)
+        })
+        .await
+        .unwrap()
+        .unwrap();
+
+    // Both tabs read the timer while it was active
+    let first = Timer::get_by_id(id, &connection).await.unwrap();
+    let second = Timer::get_by_id(id, &connection).await.unwrap();
+    assert_eq!(
+        Timer::set_status(first, "stopped", &connection).await,
+        Ok(1)
+    );
+    assert_eq!(
+        Timer::set_status(second, "stopped", &connection).await,
+        Ok(0)
+    );
+    let timer = Timer::get_by_id(id, &connection).await.unwrap();
+    assert!((60..=61).contains(&timer.time));
+    let sessions = TimerSession::get(id, &connection).await.unwrap();
+    assert_eq!(sessions.len(), 1);
+
+    Timer::delete(id, &connection).await.unwrap();
+}
+
+#[test]
+fn test_billing() {
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    let rate = |rounding: i32| RateData {
+        scope: "user".to_string(),
+        scope_id: 0,
+        rate: 10000,
+        currency: "pln".to_string(),
+        rounding,
+        rounding_mode: "up".to_string(),
+    };
+    let response = client
+        .post("/billing/rate")
+        .header(auth())
+        .json(&rate(RateData::MAX_ROUNDING + 1))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = client
+        .post("/billing/rate")
+        .header(auth())
+        .json(&rate(15))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let rates = client
+        .get("/billing/rates")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<BillingRate>>()
+        .unwrap();
+    assert!(rates
+        .iter()
+        .any(|rate| rate.scope == "user" && rate.currency == "PLN"));
+
+    client
+        .post("/timer/create")
+        .header(auth())
+        .json(&TimerData {
+            name: "Consulting".to_string(),
+            pomodoro: None,
+        })
+        .dispatch();
+    let timers = client
+        .get("/timers/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Timer>>()
+        .unwrap();
+    let id = timers.last().unwrap().id.unwrap();
+    let response = client
+        .post(format!("/timer/billing/{}", id))
+        .header(auth())
+        .json(&TimerBilling {
+            billable: true,
+            client: Some("ACME".to_string()),
+            board_id: None,
+            board_type: None,
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    client
+        .get(format!("/timer/stop/{}", id))
+        .header(auth())
+        .dispatch();
+
+    let summary = client
+        .get("/billing/summary?client=ACME")
+        .header(auth())
+        .dispatch()
+        .into_json::<BillingSummary>()
+        .unwrap();
+    assert_eq!(summary.lines.len(), 1);
+    assert_eq!(summary.lines[0].rate, 10000);
+    assert_eq!(summary.clients[0].client, "ACME");
+
+    let response = client
+        .get("/billing/export/csv?client=ACME")
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::CSV));
+    assert!(response
+        .into_string()
+        .unwrap()
+        .contains("ACME,0.00,0.00,100.00,PLN,0.00"));
+    let response = client.get("/billing/export/pdf").header(auth()).dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::PDF));
+    assert!(response.into_bytes().unwrap().starts_with(b"%PDF"));
+
+    client
+        .get(format!("/timer/delete/{}", id))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_milestone_tasks() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Milestone tasks".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Milestone tasks")
+        .unwrap()
+        .id
+        .unwrap();
+    for name in ["first", "second"] {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: None,
+                members: Some("test".to_string()),
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points: 3,
+                tags: "".to_string(),
+                done: 0,
+                milestone: None,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+    }
+    let tasks = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap();
+    let ids = tasks
+        .iter()
+        .map(|task| task.id.unwrap())
+        .collect::<Vec<_>>();
+
+    client
+        .post("/milestone/create")
+        .header(auth())
+        .json(&Milestone {
+            id: None,
+            name: "Bulk".to_string(),
+            board_id: 1,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: None,
+            due_date: None,
+            status: "open".to_string(),
+        })
+        .dispatch();
+    let milestone = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap()
+        .into_iter()
+        .find(|milestone| milestone.name == "Bulk")
+        .unwrap()
+        .id
+        .unwrap();
+
+    let response = client
+        .post(format!("/milestone/tasks/{}", milestone))
+        .header(auth())
+        .json(&MilestoneTasksUpdate {
+            add: vec![ids[0], -1],
+            remove: vec![],
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    client
+        .post(format!("/milestone/tasks/{}", milestone))
+        .header(auth())
+        .json(&MilestoneTasksUpdate {
+            add: ids.clone(),
+            remove: vec![],
+        })
+        .dispatch();
+    client
+        .post(format!("/milestone/tasks/{}", milestone))
+        .header(auth())
+        .json(&MilestoneTasksUpdate {
+            add: vec![],
+            remove: vec![ids[1]],
+        })
+        .dispatch();
+
+    let tasks = client
+        .get(format!("/milestone/tasks/{}", milestone))
+        .header(auth())
+        .dispatch()
+        .into_json::<MilestoneTasks>()
+        .unwrap();
+    assert_eq!(tasks.milestone.total, 1);
+    assert_eq!(tasks.milestone.points_total, 3);
+    assert_eq!(tasks.milestone.points_done, 0);
+    let group = tasks
+        .lists
+        .iter()
+        .find(|l| l.list.id == Some(list))
+        .unwrap();
+    assert_eq!(group.tasks.len(), 1);
+    assert_eq!(group.tasks[0].id, Some(ids[0]));
+    assert_eq!(group.available.len(), 1);
+
+    // Tasks outside of the milestone can't be removed from it
+    let response = client
+        .post(format!("/milestone/tasks/{}", milestone))
+        .header(auth())
+        .json(&MilestoneTasksUpdate {
+            add: vec![],
+            remove: vec![ids[0], ids[1]],
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    let tasks = client
+        .get(format!("/milestone/tasks/{}", milestone))
+        .header(auth())
+        .dispatch()
+        .into_json::<MilestoneTasks>()
+        .unwrap();
+    assert_eq!(tasks.milestone.total, 1);
+}
+
+#[rocket::async_test]
+async fn test_milestone_set_tasks_conflict() {
+    use rocket::local::asynchronous::Client;
+
+    let client = Client::tracked(rocket()).await.expect("valid `Rocket`");
+    let connection = Connection::get_one(client.rocket()).await.unwrap();
+    let board = 1033;
+    clear_bench_board(board, &connection).await;
+    let milestone = Milestone {
+        id: None,
+        name: "conflict".to_string(),
+        board_id: board,
+        board_type: "private".to_string(),
+        description: None,
+        start_date: None,
+        due_date: None,
+        status: "open".to_string(),
+    };
+    let milestone = Milestone::create(milestone, &connection).await.unwrap();
+    let list = List {
+        id: None,
+        name: "conflict".to_string(),
+        board,
+        board_type: "private".to_string(),
+    };
+    let list = List::create(list, &connection).await.unwrap();
+    let mut tasks = Vec::new();
+    for name in ["current", "stale"] {
+        let task = Task {
+            id: None,
+            name: name.to_string(),
+            list,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "".to_string(),
+            subtasks: "".to_string(),
+            points: 1,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        };
+        let id = Task::create(task, &connection).await.unwrap();
+        tasks.push(Task::get_single(id, &connection).await.unwrap());
+    }
+
+    // Somebody else updated the second task, neither of them moves
+    let mut stale = tasks[1].clone();
+    stale.version -= 1;
+    let result = Milestone::set_tasks(
+        milestone,
+        vec![tasks[0].clone(), stale],
+        vec![],
+        &connection,
+    )
+    .await;
+    assert_eq!(result, Err(diesel::result::Error::RollbackTransaction));
+    for task in &tasks {
+        let now = Task::get_single(task.id.unwrap(), &connection)
+            .await
+            .unwrap();
+        assert_eq!((now.milestone, now.version), (None, task.version));
+    }
+    assert_eq!(
+        Milestone::set_tasks(milestone, tasks.clone(), vec![], &connection).await,
+        Ok(2)
+    );
+    clear_bench_board(board, &connection).await;
+}
+
+#[test]
+fn test_milestone_burndown() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Burndown".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Burndown")
+        .unwrap()
+        .id
+        .unwrap();
+    let due = chrono::Local::now().date_naive() + chrono::Duration::days(10);
+    client
+        .post("/milestone/create")
+        .header(auth())
+        .json(&Milestone {
+            id: None,
+            name: "Sprint".to_string(),
+            board_id: 1,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: None,
+            due_date: Some(due.to_string()),
+            status: "open".to_string(),
+        })
+        .dispatch();
+    let milestone = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap()
+        .into_iter()
+        .find(|milestone| milestone.name == "Sprint")
+        .unwrap()
+        .id
+        .unwrap();
+    for (name, points) in [("small", 3), ("big", 5)] {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: None,
+                members: Some("test".to_string()),
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points,
+                tags: "".to_string(),
+                done: 0,
+                milestone: Some(milestone),
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+    }
+    let tasks = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap();
+    let small = tasks.iter().find(|task| task.name == "small").unwrap();
+    let big = tasks.iter().find(|task| task.name == "big").unwrap();
+    client
+        .post("/task/update")
+        .header(auth())
+        .json(&Task {
+            done: 1,
+            ..small.clone()
+        })
+        .dispatch();
+    client
+        .get(format!("/task/delete/{}", big.id.unwrap()))
+        .header(auth())
+        .dispatch();
+
+    let burndown = client
+        .get(format!("/milestone/burndown/{}", milestone))
+        .header(auth())
+        .dispatch()
+        .into_json::<Burndown>()
+        .unwrap();
+    assert_eq!(burndown.days.len(), 1);
+    let today = &burndown.days[0];
+    assert_eq!(today.total_tasks, 1);
+    assert_eq!(today.done_points, 3);
+    assert_eq!(today.remaining_points, 0);
+    let changes = burndown
+        .scope_changes
+        .iter()
+        .map(|change| (change.tasks, change.points))
+        .collect::<Vec<_>>();
+    assert_eq!(changes, vec![(1, 3), (1, 5), (-1, -5)]);
+    assert_eq!(burndown.ideal.len(), 2);
+    assert_eq!(burndown.ideal[1].date, due.to_string());
+
+    let response = client
+        .get("/milestone/burndown/-1")
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    client
+        .get(format!("/milestone/delete/{}", milestone))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/task/delete/{}", small.id.unwrap()))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_milestone_roadmap() {
+    test_private_board_creation();
+    test_team_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    for (name, start) in [("release 2", "2022-07-01"), ("release 1", "2022-06-01")] {
+        client
+            .post("/milestone/create")
+            .header(auth())
+            .json(&Milestone {
+                id: None,
+                name: name.to_string(),
+                board_id: 1,
+                board_type: "private".to_string(),
+                description: None,
+                start_date: Some(start.to_string()),
+                due_date: None,
+                status: "open".to_string(),
+            })
+            .dispatch();
+    }
+    let roadmap = client
+        .get("/roadmap/board/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<RoadmapItem>>()
+        .unwrap();
+    let releases = roadmap
+        .iter()
+        .filter(|item| item.milestone.name.starts_with("release"))
+        .collect::<Vec<_>>();
+    assert_eq!(releases.len(), 2);
+    // Dated milestones come first, by date
+    assert_eq!(releases[0].milestone.name, "release 1");
+    assert!(roadmap[0].milestone.start_date.is_some());
+    let (first, second) = (
+        releases[0].milestone.id.unwrap(),
+        releases[1].milestone.id.unwrap(),
+    );
+
+    let set = |id: i32, predecessors: Vec<i32>| {
+        client
+            .post(format!("/milestone/predecessors/{}", id))
+            .header(auth())
+            .json(&MilestonePredecessors { predecessors })
+            .dispatch()
+            .status()
+    };
+    assert_eq!(set(second, vec![first]), Status::Ok);
+    assert_eq!(set(first, vec![second]), Status::BadRequest);
+    assert_eq!(set(first, vec![first]), Status::BadRequest);
+    assert_eq!(set(first, vec![-1]), Status::NotFound);
+
+    let milestones = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap();
+    let get = |id: i32| milestones.iter().find(|m| m.id == Some(id)).unwrap();
+    assert_eq!(get(second).predecessors, vec![first]);
+    assert!(get(first).predecessors.is_empty());
+
+    let teams = client
+        .get("/owned")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Team>>()
+        .unwrap();
+    let response = client
+        .get(format!("/roadmap/team/{}", teams[0].id.unwrap()))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let response = client.get("/roadmap/team/-1").header(auth()).dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    for id in [first, second] {
+        client
+            .get(format!("/milestone/delete/{}", id))
+            .header(auth())
+            .dispatch();
+    }
+}
+
+#[test]
+fn test_search() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Search".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Search")
+        .unwrap()
+        .id
+        .unwrap();
+    for (name, note) in [("Login page", "Migrate to oauth"), ("Oauth docs", "")] {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: Some(note.to_string()),
+                place: None,
+                members: Some("test".to_string()),
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points: 1,
+                tags: "security".to_string(),
+                done: 0,
+                milestone: None,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+    }
+    let search = |url: &str, auth: rocket::http::Header<'static>| {
+        client
+            .get(url.to_string())
+            .header(auth)
+            .dispatch()
+            .into_json::<Vec<SearchResult>>()
+            .unwrap()
+    };
+
+    let results = search("/search?q=oaut", auth());
+    assert_eq!(results.len(), 2);
+    // A match in the name ranks above a match in the note
+    assert_eq!(results[0].name, "Oauth docs");
+    assert_eq!(results[1].snippet, "Migrate to [oauth]");
+    assert_eq!(results[1].list_id, list);
+    assert!(results[1].board_name.is_some());
+    assert_eq!(search("/search?q=secur%20login", auth()).len(), 1);
+    assert_eq!(
+        search("/search?q=oauth&board_type=private&board=1", auth()).len(),
+        2
+    );
+    assert!(search("/search?q=%22%20OR", auth()).is_empty());
+    let response = client
+        .get("/search?q=oauth&board_type=private&board=-1")
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    // Edits are indexed, other users can't see the tasks
+    let task = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .into_iter()
+        .find(|task| task.name == "Oauth docs")
+        .unwrap();
+    client
+        .post("/task/update")
+        .header(auth())
+        .json(&Task {
+            name: "Saml docs".to_string(),
+            ..task.clone()
+        })
+        .dispatch();
+    assert_eq!(search("/search?q=oauth", auth()).len(), 1);
+    assert_eq!(search("/search?q=saml", auth()).len(), 1);
+    let other = Credentials {
+        username: "searcher".to_string(),
+        password: "searcher".to_string(),
+    };
+    let _ = client.post("/register").json(&other).dispatch();
+    let other = login(&client, &other);
+    let header = rocket::http::Header::new("Authorization", other.token.clone());
+    assert!(search("/search?q=saml", header).is_empty());
+
+    for task in client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+    {
+        client
+            .get(format!("/task/delete/{}", task.id.unwrap()))
+            .header(auth())
+            .dispatch();
+    }
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+    assert!(search("/search?q=saml", auth()).is_empty());
+}
+
+#[test]
+fn test_saved_filters() {
+    test_private_board_creation();
+    test_team_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    let mut lists = Vec::new();
+    for name in ["Filter a", "Filter b"] {
+        client
+            .post("/new_list")
+            .header(auth())
+            .json(&List {
+                id: None,
+                name: name.to_string(),
+                board_type: "private".to_string(),
+                board: 1,
+            })
+            .dispatch();
+        let list = client
+            .get("/list/private/1")
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<List>>()
+            .unwrap()
+            .into_iter()
+            .find(|list| list.name == name)
+            .unwrap()
+            .id
+            .unwrap();
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: format!("{} task", name),
+                list,
+                note: None,
+                place: Some("".to_string()),
+                members: Some("test".to_string()),
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points: 2,
+                tags: "filtered".to_string(),
+                done: 0,
+                milestone: None,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+        lists.push(list);
+    }
+    let filter = TaskFilter {
+        name: "".to_string(),
+        place: "".to_string(),
+        members: "".to_string(),
+        deadline_start: "".to_string(),
+        deadline_end: "".to_string(),
+        points_min: Some(2),
+        points_max: None,
+        tags: "filtered".to_string(),
+        query: "".to_string(),
+        ..Default::default()
+    };
+    let tasks = client
+        .post("/task/filter/private/1")
+        .header(auth())
+        .json(&filter)
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap();
+    assert_eq!(
+        tasks.iter().map(|task| task.list).collect::<Vec<_>>(),
+        lists
+    );
+    let response = client
+        .post("/task/filter/private/-1")
+        .header(auth())
+        .json(&filter)
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    let save = |board_type: &str, board_id: i32, shared: bool| {
+        client
+            .post("/filter/create")
+            .header(auth())
+            .json(&SavedFilterData {
+                name: "Filtered".to_string(),
+                board_type: board_type.to_string(),
+                board_id,
+                shared,
+                filter: filter.clone(),
+            })
+            .dispatch()
+            .status()
+    };
+    assert_eq!(save("private", 1, true), Status::BadRequest);
+    assert_eq!(save("private", 1, false), Status::Ok);
+    let team_board = client
+        .get("/team_board/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<TeamBoard>>()
+        .unwrap()[0]
+        .id
+        .unwrap();
+    assert_eq!(save("team", team_board, true), Status::Ok);
+
+    let saved = client
+        .get("/filter/get/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<SavedFilterResponse>>()
+        .unwrap();
+    assert_eq!(saved.len(), 1);
+    assert_eq!(saved[0].filter, filter);
+    let shared = client
+        .get(format!("/filter/get/team/{}", team_board))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<SavedFilterResponse>>()
+        .unwrap();
+    assert!(shared[0].shared);
+
+    let other = Credentials {
+        username: "filterer".to_string(),
+        password: "filterer".to_string(),
+    };
+    let _ = client.post("/register").json(&other).dispatch();
+    let other = login(&client, &other);
+    let other = || rocket::http::Header::new("Authorization", other.token.clone());
+    let response = client
+        .get("/filter/get/private/1")
+        .header(other())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    let response = client
+        .get(format!("/filter/delete/{}", saved[0].id))
+        .header(other())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    for id in [saved[0].id, shared[0].id] {
+        let response = client
+            .get(format!("/filter/delete/{}", id))
+            .header(auth())
+            .dispatch();
+        assert_eq!(response.status(), Status::Ok);
+    }
+    for task in tasks {
+        client
+            .get(format!("/task/delete/{}", task.id.unwrap()))
+            .header(auth())
+            .dispatch();
+    }
+    for list in lists {
+        client
+            .get(format!("/list_delete/{}", list))
+            .header(auth())
+            .dispatch();
+    }
+}
+
+#[test]
+fn test_task_query() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Query".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Query")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/milestone/create")
+        .header(auth())
+        .json(&Milestone {
+            id: None,
+            name: "Query v1".to_string(),
+            board_id: 1,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: None,
+            due_date: None,
+            status: "open".to_string(),
+        })
+        .dispatch();
+    let milestone = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap()
+        .into_iter()
+        .find(|milestone| milestone.name == "Query v1")
+        .unwrap()
+        .id
+        .unwrap();
+    let tasks = [
+        ("matching", 3, "2026-10-20", 0, Some(milestone)),
+        ("small", 1, "2026-10-20", 0, Some(milestone)),
+        ("late", 5, "2026-12-01", 0, Some(milestone)),
+        ("finished", 5, "2026-10-20", 1, Some(milestone)),
+        ("unplanned", 5, "2026-10-20", 0, None),
+    ];
+    for (name, points, deadline, done, milestone) in tasks {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: Some("".to_string()),
+                members: Some("alice;bob".to_string()),
+                deadline: deadline.to_string(),
+                subtasks: "".to_string(),
+                points,
+                tags: "bug".to_string(),
+                done,
+                milestone,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+    }
+
+    let filter = |query: &str| TaskFilter {
+        name: "".to_string(),
+        place: "".to_string(),
+        members: "".to_string(),
+        deadline_start: "".to_string(),
+        deadline_end: "".to_string(),
+        points_min: None,
+        points_max: None,
+        tags: "".to_string(),
+        query: query.to_string(),
+        ..Default::default()
+    };
+    let names = |query: &str| {
+        let mut names = client
+            .post(format!("/task/get/{}", list))
+            .header(auth())
+            .json(&filter(query))
+            .dispatch()
+            .into_json::<Vec<Task>>()
+            .unwrap()
+            .into_iter()
+            .map(|task| task.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    assert_eq!(
+        names(r#"tag:bug points>=3 due<2026-11-01 @alice -done milestone:"Query v1""#),
+        vec!["matching"]
+    );
+    assert_eq!(names(r#"-milestone:"Query v1""#), vec!["unplanned"]);
+    assert_eq!(names("done"), vec!["finished"]);
+    assert_eq!(names("points<3 -@carol -place:office"), vec!["small"]);
+    assert_eq!(names("-tag:bug"), Vec::<String>::new());
+    let response = client
+        .post("/task/filter/private/1")
+        .header(auth())
+        .json(&filter("@bob due>=2026-12-01"))
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap();
+    assert_eq!(response.len(), 1);
+
+    let response = client
+        .post(format!("/task/get/{}", list))
+        .header(auth())
+        .json(&filter("tag:bug points>=lots"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    let error = response.into_json::<ParseError>().unwrap();
+    assert_eq!(error.position, 17);
+    assert_eq!(error.message, "expected a number, found `lots`");
+    let response = client
+        .post("/task/filter/private/1")
+        .header(auth())
+        .json(&filter("colour:red"))
+        .dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+
+    let scheduled = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .into_iter()
+        .filter(|task| task.milestone == Some(milestone))
+        .collect::<Vec<_>>();
+    assert!(!scheduled.is_empty());
+    client
+        .get(format!("/milestone/delete/{}", milestone))
+        .header(auth())
+        .dispatch();
+    let tasks = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap();
+    // Unscheduled tasks are new versions, stale updates of them conflict
+    for before in scheduled {
+        let after = tasks.iter().find(|task| task.id == before.id).unwrap();
+        assert_eq!((after.milestone, after.version), (None, before.version + 1));
+    }
+    for task in tasks {
+        client
+            .get(format!("/task/delete/{}", task.id.unwrap()))
+            .header(auth())
+            .dispatch();
+    }
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_task_filter_predicates() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Predicates".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Predicates")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/milestone/create")
+        .header(auth())
+        .json(&Milestone {
+            id: None,
+            name: "Predicates".to_string(),
+            board_id: 1,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: None,
+            due_date: None,
+            status: "open".to_string(),
+        })
+        .dispatch();
+    let milestone = client
+        .get("/milestone/get/1/private")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<MilestoneResponse>>()
+        .unwrap()
+        .into_iter()
+        .find(|milestone| milestone.name == "Predicates")
+        .unwrap()
+        .id
+        .unwrap();
+    let tasks = [
+        ("overdue", Some("alice"), "2000-01-01", "", "bug", 0, None),
+        (
+            "finished",
+            Some("bob"),
+            "2000-01-01",
+            "",
+            "feature",
+            1,
+            None,
+        ),
+        (
+            "planned",
+            None,
+            "",
+            "write;review",
+            "bug;ui",
+            0,
+            Some(milestone),
+        ),
+        ("future", Some(""), "2999-01-01", "", "docs", 0, None),
+    ];
+    for (name, members, deadline, subtasks, tags, done, milestone) in tasks {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: Some("".to_string()),
+                members: members.map(|members| members.to_string()),
+                deadline: deadline.to_string(),
+                subtasks: subtasks.to_string(),
+                points: 1,
+                tags: tags.to_string(),
+                done,
+                milestone,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+    }
+
+    let names = |filter: TaskFilter| {
+        let mut names = client
+            .post(format!("/task/get/{}", list))
+            .header(auth())
+            .json(&filter)
+            .dispatch()
+            .into_json::<Vec<Task>>()
+            .unwrap()
+            .into_iter()
+            .map(|task| task.name)
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+    assert_eq!(
+        names(TaskFilter::default()),
+        vec!["finished", "future", "overdue", "planned"]
+    );
+    let filter = |done, milestone, overdue, no_deadline, unassigned, open_subtasks| TaskFilter {
+        done,
+        milestone,
+        overdue,
+        no_deadline,
+        unassigned,
+        open_subtasks,
+        ..Default::default()
+    };
+    assert_eq!(
+        names(filter(Some(false), None, false, false, false, false)),
+        vec!["future", "overdue", "planned"]
+    );
+    assert_eq!(
+        names(filter(Some(true), None, false, false, false, false)),
+        vec!["finished"]
+    );
+    assert_eq!(
+        names(filter(None, Some(milestone), false, false, false, false)),
+        vec!["planned"]
+    );
+    assert_eq!(
+        names(filter(None, None, true, false, false, false)),
+        vec!["overdue"]
+    );
+    assert_eq!(
+        names(filter(None, None, false, true, false, false)),
+        vec!["planned"]
+    );
+    assert_eq!(
+        names(filter(None, None, false, false, true, false)),
+        vec!["future", "planned"]
+    );
+    assert_eq!(
+        names(filter(None, None, false, false, false, true)),
+        vec!["planned"]
+    );
+
+    let tags = |tags: &str, tags_any| TaskFilter {
+        tags: tags.to_string(),
+        tags_any,
+        ..Default::default()
+    };
+    assert_eq!(names(tags("bug;ui", false)), vec!["planned"]);
+    assert_eq!(
+        names(tags("bug;feature", true)),
+        vec!["finished", "overdue", "planned"]
+    );
+    let members = |members: &str, members_any| TaskFilter {
+        members: members.to_string(),
+        members_any,
+        ..Default::default()
+    };
+    assert_eq!(names(members("alice;bob", false)), Vec::<String>::new());
+    assert_eq!(
+        names(members("alice;bob", true)),
+        vec!["finished", "overdue"]
+    );
+
+    client
+        .get(format!("/milestone/delete/{}", milestone))
+        .header(auth())
+        .dispatch();
+    let tasks = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap();
+    for task in tasks {
+        client
+            .get(format!("/task/delete/{}", task.id.unwrap()))
+            .header(auth())
+            .dispatch();
+    }
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_pagination() {
+    test_private_board_creation();
+    test_team_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Pages".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Pages")
+        .unwrap()
+        .id
+        .unwrap();
+    for (name, points) in [("b", 3), ("c", 1), ("a", 2)] {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: None,
+                members: None,
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points,
+                tags: "".to_string(),
+                done: 0,
+                milestone: None,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+    }
+
+    let page = |url: String| {
+        let response = client.get(url).header(auth()).dispatch();
+        let total = response
+            .headers()
+            .get_one("X-Total-Count")
+            .unwrap()
+            .parse::<i64>()
+            .unwrap();
+        (response.into_json::<Vec<Task>>().unwrap(), total)
+    };
+    let names = |tasks: &[Task]| {
+        tasks
+            .iter()
+            .map(|task| task.name.clone())
+            .collect::<Vec<_>>()
+    };
+    let (tasks, total) = page(format!("/task/get/{}", list));
+    assert_eq!(names(&tasks), vec!["b", "c", "a"]);
+    assert_eq!(total, 3);
+    let (tasks, total) = page(format!("/task/get/{}?sort=-points&limit=2", list));
+    assert_eq!(names(&tasks), vec!["b", "a"]);
+    assert_eq!(total, 3);
+    let (tasks, _) = page(format!("/task/get/{}?sort=-points&limit=2&offset=2", list));
+    assert_eq!(names(&tasks), vec!["c"]);
+    let (tasks, _) = page(format!("/task/get/{}?sort=name", list));
+    assert_eq!(names(&tasks), vec!["a", "b", "c"]);
+
+    let mut updated = tasks[1].clone();
+    updated.points = 4;
+    client
+        .post("/task/update")
+        .header(auth())
+        .json(&updated)
+        .dispatch();
+    let (tasks, _) = page(format!("/task/get/{}?sort=-updated&limit=1", list));
+    assert_eq!(names(&tasks), vec!["b"]);
+
+    for url in [
+        format!("/task/get/{}?sort=colour", list),
+        format!("/task/get/{}?offset=-1", list),
+        format!("/task/get/{}?limit=0", list),
+        "/timers/get?sort=deadline".to_string(),
+        "/private_board/get?sort=points".to_string(),
+    ] {
+        let response = client.get(url).header(auth()).dispatch();
+        assert_eq!(response.status(), Status::BadRequest);
+    }
+
+    let response = client
+        .get(format!("/logs/get/{}?limit=1", tasks[0].id.unwrap()))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.headers().get_one("X-Total-Count"), Some("2"));
+    let logs = response.into_json::<Vec<Log>>().unwrap();
+    assert_eq!(logs.len(), 1);
+    assert_eq!(logs[0].points, 4);
+
+    let response = client
+        .get("/team_board/get?sort=-name&limit=1")
+        .header(auth())
+        .dispatch();
+    let total = response
+        .headers()
+        .get_one("X-Total-Count")
+        .unwrap()
+        .to_string();
+    let boards = response.into_json::<Vec<TeamBoardWithName>>().unwrap();
+    assert_eq!(boards.len(), 1);
+    let all = client
+        .get("/team_board/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<TeamBoardWithName>>()
+        .unwrap();
+    assert_eq!(total, all.len().to_string());
+    assert_eq!(
+        Some(&boards[0].name),
+        all.iter().map(|board| &board.name).max()
+    );
+    let response = client
+        .get("/private_board/get?sort=name&offset=1000")
+        .header(auth())
+        .dispatch();
+    assert_ne!(response.headers().get_one("X-Total-Count"), Some("0"));
+    assert!(response
+        .into_json::<Vec<PrivateBoard>>()
+        .unwrap()
+        .is_empty());
+
+    let (tasks, _) = page(format!("/task/get/{}", list));
+    for task in tasks {
+        client
+            .get(format!("/task/delete/{}", task.id.unwrap()))
+            .header(auth())
+            .dispatch();
+    }
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_board_snapshot() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Snapshot".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Snapshot")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Snapshot task".to_string(),
+            list,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "".to_string(),
+            subtasks: "".to_string(),
+            points: 3,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+
+    let response = client
+        .get("/boards/private/1/snapshot")
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let etag = response.headers().get_one("ETag").unwrap().to_string();
+    let snapshot = response.into_json::<Snapshot>().unwrap();
+    let board = client
+        .get("/private_board/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<PrivateBoard>>()
+        .unwrap()
+        .into_iter()
+        .find(|board| board.id == Some(1))
+        .unwrap();
+    assert_eq!(snapshot.name, board.name);
+    assert!(snapshot.lists.iter().any(|l| l.id == Some(list)));
+    let task = snapshot
+        .tasks
+        .iter()
+        .find(|task| task.task.list == list)
+        .unwrap()
+        .task
+        .clone();
+    assert_eq!(task.name, "Snapshot task");
+    assert_eq!(snapshot.counts.tasks, snapshot.tasks.len());
+    assert_eq!(snapshot.counts.lists, snapshot.lists.len());
+
+    let response = client
+        .get("/boards/private/1/snapshot")
+        .header(auth())
+        .header(rocket::http::Header::new("If-None-Match", etag.clone()))
+        .dispatch();
+    assert_eq!(response.status(), Status::NotModified);
+    assert_eq!(response.headers().get_one("ETag"), Some(etag.as_str()));
+    assert!(response.into_string().unwrap_or_default().is_empty());
+
+    let mut updated = task.clone();
+    updated.done = 1;
+    client
+        .post("/task/update")
+        .header(auth())
+        .json(&updated)
+        .dispatch();
+    let response = client
+        .get("/boards/private/1/snapshot")
+        .header(auth())
+        .header(rocket::http::Header::new("If-None-Match", etag.clone()))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_ne!(response.headers().get_one("ETag"), Some(etag.as_str()));
+    let snapshot = response.into_json::<Snapshot>().unwrap();
+    assert_eq!(snapshot.counts.done, 1);
+
+    let response = client
+        .get("/boards/private/-1/snapshot")
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    client
+        .get(format!("/task/delete/{}", task.id.unwrap()))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+/// Reads the event stream until it has `count` events
+fn read_events(
+    response: &mut rocket::local::blocking::LocalResponse,
+    count: usize,
+) -> Vec<BoardEvent> {
+    use std::io::Read;
+    let mut body = String::new();
+    let mut buffer = [0; 1024];
+    while body.matches("data:").count() < count {
+        let read = response.read(&mut buffer).unwrap();
+        assert!(read > 0, "stream ended");
+        body.push_str(std::str::from_utf8(&buffer[..read]).unwrap());
+    }
+    body.lines()
+        .filter_map(|line| line.strip_prefix("data:"))
+        .map(|data| serde_json::from_str(data.trim()).unwrap())
+        .collect()
+}
+
+#[test]
+fn test_board_events() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Events".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let mut task = Task {
+        id: None,
+        name: "Events task".to_string(),
+        list: 0,
+        note: None,
+        place: None,
+        members: None,
+        deadline: "".to_string(),
+        subtasks: "".to_string(),
+        points: 1,
+        tags: "".to_string(),
+        done: 0,
+        milestone: None,
+        version: 0,
+        parent: None,
+    };
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Events")
+        .unwrap()
+        .id
+        .unwrap();
+    task.list = list;
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&task)
+        .dispatch();
+
+    let url = format!(
+        "/boards/private/1/events?ticket={}",
+        ticket(&client, &token)
+    );
+    let mut response = client.get(format!("{}&last_event_id=0", url)).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let events = read_events(&mut response, 2);
+    assert_eq!(
+        (
+            events[0].kind.as_str(),
+            events[0].action.as_str(),
+            events[0].entity
+        ),
+        ("list", "created", list)
+    );
+    assert_eq!(
+        (events[1].kind.as_str(), events[1].action.as_str()),
+        ("task", "created")
+    );
+    let created = serde_json::from_value::<Task>(events[1].data.clone().unwrap()).unwrap();
+    assert_eq!(created.name, "Events task");
+    task.id = created.id;
+
+    // Live events, the stream is still open
+    task.done = 1;
+    client
+        .post("/task/update")
+        .header(auth())
+        .json(&task)
+        .dispatch();
+    let updated = read_events(&mut response, 1).remove(0);
+    assert_eq!(
+        (updated.kind.as_str(), updated.action.as_str()),
+        ("task", "updated")
+    );
+    assert_eq!(updated.id, events[1].id + 1);
+    assert_eq!(updated.data.unwrap()["done"], 1);
+    drop(response);
+
+    client
+        .get(format!("/task/delete/{}", task.id.unwrap()))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+
+    // Resuming sends only what was missed
+    let mut response = client
+        .get(url.clone())
+        .header(rocket::http::Header::new(
+            "Last-Event-ID",
+            updated.id.to_string(),
+        ))
+        .dispatch();
+    let events = read_events(&mut response, 2);
+    assert_eq!(
+        (events[0].kind.as_str(), events[0].action.as_str()),
+        ("task", "deleted")
+    );
+    assert!(events[0].data.is_none());
+    assert_eq!((events[1].kind.as_str(), events[1].entity), ("list", list));
+    drop(response);
+
+    // Ids the server doesn't know, it restarted
+    let mut response = client.get(format!("{}&last_event_id=1000", url)).dispatch();
+    let reset = read_events(&mut response, 1).remove(0);
+    assert_eq!(
+        (reset.kind.as_str(), reset.action.as_str()),
+        ("board", "reset")
+    );
+    drop(response);
+
+    let response = client.get("/boards/private/-1/events?ticket=x").dispatch();
+    assert_ne!(response.status(), Status::Ok);
+
+    // Neither the token in the url nor the ticket in the header
+    let response = client
+        .get(format!("/boards/private/1/events?ticket={}", token.token))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client
+        .get("/boards/private/1/snapshot")
+        .header(rocket::http::Header::new(
+            "Authorization",
+            ticket(&client, &token),
+        ))
+        .dispatch();
+    assert_ne!(response.status(), Status::Ok);
+}
+
+#[test]
+fn test_update_conflict() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Conflicts".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Conflicts")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Conflict task".to_string(),
+            list,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "".to_string(),
+            subtasks: "".to_string(),
+            points: 1,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+    let task = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0);
+    assert_eq!(task.version, 0);
+
+    // Two people edit the same version
+    let alice = Task {
+        note: Some("alice's note".to_string()),
+        ..task.clone()
+    };
+    let bob = Task {
+        members: Some("bob".to_string()),
+        ..task.clone()
+    };
+    let response = client
+        .post("/task/update")
+        .header(auth())
+        .json(&alice)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .post("/task/update")
+        .header(auth())
+        .json(&bob)
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    let current = response.into_json::<Task>().unwrap();
+    assert_eq!(current.version, 1);
+    assert_eq!(current.note, alice.note);
+    assert_eq!(current.members, None);
+
+    // Resolved on top of the current version, named by the If-Match header
+    let merged = Task {
+        members: bob.members.clone(),
+        ..current
+    };
+    let response = client
+        .post("/task/update")
+        .header(auth())
+        .header(rocket::http::Header::new("If-Match", "\"1\""))
+        .json(&Task {
+            version: 0,
+            ..merged
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let saved = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0);
+    assert_eq!(saved.version, 2);
+    assert_eq!(
+        (saved.note, saved.members),
+        (alice.note, Some("bob".to_string()))
+    );
+
+    let response = client
+        .post("/task/update")
+        .header(auth())
+        .json(&Task {
+            id: Some(-1),
+            ..task.clone()
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    // Boards are versioned the same way
+    let board = client
+        .get("/private_board/get")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<PrivateBoard>>()
+        .unwrap()
+        .into_iter()
+        .find(|board| board.id == Some(1))
+        .unwrap();
+    let rename = |version: i32| BoardUpdate {
+        name: board.name.clone(),
+        version,
+    };
+    let response = client
+        .post("/private/update/1")
+        .header(auth())
+        .json(&rename(board.version))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .post("/private/update/1")
+        .header(auth())
+        .json(&rename(board.version))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    let current = response.into_json::<PrivateBoard>().unwrap();
+    assert_eq!(current.version, board.version + 1);
+
+    client
+        .get(format!("/task/delete/{}", task.id.unwrap()))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_task_patch() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Patches".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Patches")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Patch task".to_string(),
+            list,
+            note: Some("first note".to_string()),
+            place: Some("office".to_string()),
+            members: None,
+            deadline: "2022-06-01".to_string(),
+            subtasks: "".to_string(),
+            points: 2,
+            tags: "bug".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+    let task = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0);
+    let id = task.id.unwrap();
+    let patch = |json: &str| {
+        client
+            .patch(format!("/task/{}", id))
+            .header(auth())
+            .header(ContentType::JSON)
+            .body(json)
+    };
+
+    let response = patch(r#"{"done": 1, "note": null}"#).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let patched = response.into_json::<Task>().unwrap();
+    assert_eq!((patched.done, patched.note.clone()), (1, None));
+    assert_eq!(patched.version, 1);
+    let saved = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0);
+    assert_eq!((saved.done, saved.note, saved.version), (1, None, 1));
+    assert_eq!(
+        (saved.name, saved.place, saved.points, saved.tags),
+        (task.name, task.place, task.points, task.tags)
+    );
+
+    // The log has only the fields of the patch
+    let logs = client
+        .get(format!("/logs/get/{}?limit=1", id))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Log>>()
+        .unwrap();
+    let changes: serde_json::Value =
+        serde_json::from_str(logs[0].changes.as_ref().unwrap()).unwrap();
+    assert_eq!(changes, serde_json::json!({"done": 1, "note": null}));
+    assert_eq!(logs[0].done, 1);
+
+    let response = patch(r#"{"deadline": "tomorrow"}"#).dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(
+        response.into_json::<FieldError>().unwrap().field,
+        "deadline"
+    );
+    let response = patch(r#"{"colour": "red"}"#).dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = patch(r#"{"list": -1}"#).dispatch();
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.into_json::<FieldError>().unwrap().field, "list");
+
+    // Stale versions, from the header or the body
+    let response = patch(r#"{"points": 5}"#)
+        .header(rocket::http::Header::new("If-Match", "\"0\""))
+        .dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    assert_eq!(response.into_json::<Task>().unwrap().version, 1);
+    let response = patch(r#"{"points": 5, "version": 0}"#).dispatch();
+    assert_eq!(response.status(), Status::Conflict);
+    let response = patch(r#"{"points": 5, "version": 1}"#).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<Task>().unwrap().points, 5);
+
+    let response = client
+        .patch("/task/-1")
+        .header(auth())
+        .header(ContentType::JSON)
+        .body("{}")
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    client
+        .get(format!("/task/delete/{}", id))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_task_comments() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Comments".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Comments")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Commented task".to_string(),
+            list,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "".to_string(),
+            subtasks: "".to_string(),
+            points: 1,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+    let task = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0)
+        .id
+        .unwrap();
+    let comment = |body: &str, parent: Option<i32>| {
+        client
+            .post(format!("/comment/create/{}", task))
+            .header(auth())
+            .json(&CommentData {
+                body: body.to_string(),
+                parent,
+            })
+            .dispatch()
+            .status()
+    };
+    let comments = || {
+        client
+            .get(format!("/comments/get/{}", task))
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<CommentResponse>>()
+            .unwrap()
+    };
+
+    assert_eq!(
+        comment("What do you think, @test and @nobody?", None),
+        Status::Ok
+    );
+    let first = comments().remove(0);
+    assert_eq!(first.author_name, "test");
+    assert_eq!(first.mentions, vec!["test".to_string()]);
+    assert_eq!(comment("Looks good", Some(first.id)), Status::Ok);
+    assert_eq!(comment("  ", None), Status::BadRequest);
+    assert_eq!(comment("Wrong thread", Some(-1)), Status::BadRequest);
+    let thread = comments();
+    assert_eq!(thread.len(), 1);
+    assert_eq!(thread[0].replies.len(), 1);
+    assert_eq!(thread[0].replies[0].body, "Looks good");
+
+    let response = client
+        .post(format!("/comment/update/{}", first.id))
+        .header(auth())
+        .json(&CommentData {
+            body: "Edited".to_string(),
+            parent: None,
+        })
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let edited = comments().remove(0);
+    assert_eq!(edited.body, "Edited");
+    assert!(edited.edited.is_some());
+    assert!(edited.mentions.is_empty());
+
+    // Only the author can change a comment
+    let other = Credentials {
+        username: "commenter".to_string(),
+        password: "commenter".to_string(),
+    };
+    let _ = client.post("/register").json(&other).dispatch();
+    let other = login(&client, &other);
+    let other = || rocket::http::Header::new("Authorization", other.token.clone());
+    let response = client
+        .get(format!("/comment/delete/{}", first.id))
+        .header(other())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    let response = client
+        .get(format!("/comments/get/{}", task))
+        .header(other())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    let response = client
+        .get("/boards/private/1/comments?limit=1")
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.headers().get_one("X-Total-Count"), Some("2"));
+    let activity = response.into_json::<Vec<CommentActivity>>().unwrap();
+    assert_eq!(activity[0].task_name, "Commented task");
+    assert_eq!(activity[0].comment.body, "Looks good");
+
+    // A comment with replies keeps its place in the thread
+    let response = client
+        .get(format!("/comment/delete/{}", first.id))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let thread = comments();
+    assert!(thread[0].deleted);
+    assert_eq!(thread[0].body, "");
+    let reply = thread[0].replies[0].id;
+    let response = client
+        .get(format!("/comment/delete/{}", reply))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(comments().is_empty());
+
+    client
+        .get(format!("/task/delete/{}", task))
+        .header(auth())
+        .dispatch();
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+/// Multipart form with one file field
+fn multipart(name: &str, content: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--BOUNDARY\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+         Content-Type: text/plain\r\n\r\n",
+        name
+    )
+    .into_bytes();
+    body.extend_from_slice(content);
+    body.extend_from_slice(b"\r\n--BOUNDARY--\r\n");
+    body
+}
+
+#[test]
+fn test_attachments() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Attachments".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Attachments")
+        .unwrap()
+        .id
+        .unwrap();
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Attached task".to_string(),
+            list,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "".to_string(),
+            subtasks: "".to_string(),
+            points: 1,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+    let task = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0)
+        .id
+        .unwrap();
+    let upload = |name: &str, content: &[u8]| {
+        client
+            .post(format!("/attachment/create/{}", task))
+            .header(auth())
+            .header(ContentType::parse_flexible("multipart/form-data; boundary=BOUNDARY").unwrap())
+            .body(multipart(name, content))
+            .dispatch()
+    };
+
+    // The type comes from the content, not from the client
+    let png = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR\0\0\0\x01";
+    let response = upload("screens/shot.png", png);
+    assert_eq!(response.status(), Status::Ok);
+    let image = response.into_json::<Attachment>().unwrap();
+    assert_eq!(image.name, "shot.png");
+    assert_eq!(image.content_type, "image/png");
+    assert_eq!(image.size, png.len() as i64);
+    let spec = upload("spec.txt", &[b'a'; 50])
+        .into_json::<Attachment>()
+        .unwrap();
+    assert!(spec.content_type.starts_with("text/plain"));
+
+    let listed = client
+        .get(format!("/task/{}", task))
+        .header(auth())
+        .dispatch()
+        .into_json::<TaskResponse>()
+        .unwrap();
+    assert_eq!(listed.attachments, vec![image.clone(), spec.clone()]);
+
+    // Over the file limit, then over the board's quota
+    assert_eq!(
+        upload("big.txt", &[b'a'; 65]).status(),
+        Status::PayloadTooLarge
+    );
+    assert_eq!(
+        upload("more.txt", &[b'a'; 40]).status(),
+        Status::PayloadTooLarge
+    );
+
+    let id = image.id.unwrap();
+    let response = client
+        .get(format!("/attachment/{}", id))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::PNG));
+    assert!(response
+        .headers()
+        .get_one("Content-Disposition")
+        .unwrap()
+        .starts_with("inline"));
+    assert_eq!(response.into_bytes().unwrap(), png.to_vec());
+    let response = client
+        .get(format!(
+            "/attachment/{}?ticket={}",
+            id,
+            ticket(&client, &token)
+        ))
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .get(format!("/attachment/{}?ticket={}", id, token.token))
+        .dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client.get(format!("/attachment/{}", id)).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let other = Credentials {
+        username: "uploader".to_string(),
+        password: "uploader".to_string(),
+    };
+    let _ = client.post("/register").json(&other).dispatch();
+    let other = login(&client, &other);
+    let other = || rocket::http::Header::new("Authorization", other.token.clone());
+    for url in [
+        format!("/attachment/{}", id),
+        format!("/attachment/delete/{}", id),
+    ] {
+        let response = client.get(url).header(other()).dispatch();
+        assert_eq!(response.status(), Status::NotFound);
+    }
+
+    let response = client
+        .get(format!("/attachment/delete/{}", id))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    let response = client
+        .get(format!("/attachment/{}", id))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+
+    // The task takes its files with it
+    let path = std::env::temp_dir()
+        .join("attachments")
+        .join(spec.id.unwrap().to_string());
+    assert!(path.exists());
+    client
+        .get(format!("/task/delete/{}", task))
+        .header(auth())
+        .dispatch();
+    assert!(!path.exists());
+
+    // And so does the list
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Listed task".to_string(),
+            list,
+            note: None,
+            place: None,
+            members: None,
+            deadline: "".to_string(),
+            subtasks: "".to_string(),
+            points: 1,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+    let task = client
+        .get(format!("/task/get/{}", list))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Task>>()
+        .unwrap()
+        .remove(0)
+        .id
+        .unwrap();
+    let notes = client
+        .post(format!("/attachment/create/{}", task))
+        .header(auth())
+        .header(ContentType::parse_flexible("multipart/form-data; boundary=BOUNDARY").unwrap())
+        .body(multipart("notes.txt", &[b'a'; 10]))
+        .dispatch()
+        .into_json::<Attachment>()
+        .unwrap()
+        .id
+        .unwrap();
+    let path = std::env::temp_dir()
+        .join("attachments")
+        .join(notes.to_string());
+    assert!(path.exists());
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+    assert!(!path.exists());
+    let response = client
+        .get(format!("/attachment/{}", notes))
+        .header(auth())
+        .dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    client
+        .get(format!("/task/delete/{}", task))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_task_dependencies() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    let mut lists = Vec::new();
+    for name in ["Dependencies 1", "Dependencies 2"] {
+        client
+            .post("/new_list")
+            .header(auth())
+            .json(&List {
+                id: None,
+                name: name.to_string(),
+                board_type: "private".to_string(),
+                board: 1,
+            })
+            .dispatch();
+        let list = client
+            .get("/list/private/1")
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<List>>()
+            .unwrap()
+            .into_iter()
+            .find(|list| list.name == name)
+            .unwrap()
+            .id
+            .unwrap();
+        lists.push(list);
+    }
+    // The blocker is on the other list
+    let mut ids = Vec::new();
+    for (name, list) in [("Blocker", lists[1]), ("Blocked", lists[0])] {
+        client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: Some("".to_string()),
+                members: None,
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points: 1,
+                tags: "".to_string(),
+                done: 0,
+                milestone: None,
+                version: 0,
+                parent: None,
+            })
+            .dispatch();
+        let task = client
+            .get(format!("/task/get/{}", list))
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<Task>>()
+            .unwrap()
+            .remove(0);
+        ids.push(task.id.unwrap());
+    }
+    let (blocker, blocked) = (ids[0], ids[1]);
+
+    let set = |id: i32, blockers: Vec<i32>| {
+        client
+            .post(format!("/task/blockers/{}", id))
+            .header(auth())
+            .json(&TaskBlockers { blockers })
+            .dispatch()
+            .status()
+    };
+    assert_eq!(set(blocked, vec![blocker]), Status::Ok);
+    assert_eq!(set(blocker, vec![blocked]), Status::BadRequest);
+    assert_eq!(set(blocker, vec![blocker]), Status::BadRequest);
+    assert_eq!(set(blocker, vec![-1]), Status::NotFound);
+
+    let get = |id: i32| {
+        client
+            .get(format!("/task/{}", id))
+            .header(auth())
+            .dispatch()
+            .into_json::<TaskResponse>()
+            .unwrap()
+    };
+    let task = get(blocked);
+    assert!(task.blocked);
+    assert_eq!(task.blocked_by, vec![blocker]);
+    let task = get(blocker);
+    assert!(!task.blocked);
+    assert_eq!(task.blocks, vec![blocked]);
+
+    let names = |filter: TaskFilter| {
+        client
+            .post(format!("/task/get/{}", lists[0]))
+            .header(auth())
+            .json(&filter)
+            .dispatch()
+            .into_json::<Vec<TaskResponse>>()
+            .unwrap()
+            .len()
+    };
+    let blocked_filter = |blocked| TaskFilter {
+        blocked,
+        ..Default::default()
+    };
+    assert_eq!(names(blocked_filter(Some(true))), 1);
+    assert_eq!(names(blocked_filter(Some(false))), 0);
+    let query = TaskFilter {
+        query: "-blocked".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(names(query), 0);
+
+    // Done only when forced while the blocker is open
+    let patch = |id: i32, force: bool, json: &str| {
+        client
+            .patch(format!("/task/{}?force={}", id, force))
+            .header(auth())
+            .header(ContentType::JSON)
+            .body(json)
+            .dispatch()
+    };
+    let response = patch(blocked, false, r#"{"done": 1}"#);
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.into_json::<FieldError>().unwrap().field, "done");
+    assert_eq!(patch(blocked, true, r#"{"done": 1}"#).status(), Status::Ok);
+    assert_eq!(patch(blocked, false, r#"{"done": 0}"#).status(), Status::Ok);
+    assert_eq!(patch(blocker, false, r#"{"done": 1}"#).status(), Status::Ok);
+    assert!(!get(blocked).blocked);
+    let mut task = get(blocked).task;
+    task.done = 1;
+    let response = client
+        .post("/task/update")
+        .header(auth())
+        .json(&task)
+        .dispatch();
+    assert_eq!(response.status(), Status::Ok);
+
+    client
+        .get(format!("/task/delete/{}", blocker))
+        .header(auth())
+        .dispatch();
+    assert!(get(blocked).blocked_by.is_empty());
+    client
+        .get(format!("/task/delete/{}", blocked))
+        .header(auth())
+        .dispatch();
+    for list in lists {
+        client
+            .get(format!("/list_delete/{}", list))
+            .header(auth())
+            .dispatch();
+    }
+}
+
+#[test]
+fn test_task_hierarchy() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Epics".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Epics")
+        .unwrap()
+        .id
+        .unwrap();
+    let create = |name: &str, points: i32, done: i32, parent: Option<i32>| {
+        let status = client
+            .post("/task/create")
+            .header(auth())
+            .json(&Task {
+                id: None,
+                name: name.to_string(),
+                list,
+                note: None,
+                place: Some("".to_string()),
+                members: None,
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points,
+                tags: "".to_string(),
+                done,
+                milestone: None,
+                version: 0,
+                parent,
+            })
+            .dispatch()
+            .status();
+        let id = client
+            .get(format!("/task/get/{}?sort=-created", list))
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<Task>>()
+            .unwrap()
+            .remove(0)
+            .id
+            .unwrap();
+        (status, id)
+    };
+    let (_, epic) = create("Epic", -1, 0, None);
+    let (status, first) = create("First child", 2, 0, Some(epic));
+    assert_eq!(status, Status::Ok);
+    let (_, second) = create("Second child", 3, 1, Some(epic));
+    let (status, _) = create("Orphan", 1, 0, Some(-1));
+    assert_eq!(status, Status::BadRequest);
+
+    let get = |id: i32| {
+        client
+            .get(format!("/task/{}", id))
+            .header(auth())
+            .dispatch()
+            .into_json::<TaskResponse>()
+            .unwrap()
+    };
+    let children = get(epic).children;
+    assert_eq!(
+        (
+            children.total,
+            children.done,
+            children.points_total,
+            children.points_done
+        ),
+        (2, 1, 5, 3)
+    );
+    assert_eq!(get(first).task.parent, Some(epic));
+
+    let count = |filter: TaskFilter| {
+        client
+            .post(format!("/task/get/{}", list))
+            .header(auth())
+            .json(&filter)
+            .dispatch()
+            .into_json::<Vec<TaskResponse>>()
+            .unwrap()
+            .len()
+    };
+    let epics = TaskFilter {
+        epic: Some(true),
+        ..Default::default()
+    };
+    assert_eq!(count(epics), 1);
+    let children = TaskFilter {
+        parent: Some(epic),
+        ..Default::default()
+    };
+    assert_eq!(count(children), 2);
+    let query = TaskFilter {
+        query: "-epic".to_string(),
+        ..Default::default()
+    };
+    assert_eq!(count(query), 2);
+
+    // An epic can't be under its own child
+    let patch = |id: i32, json: String| {
+        client
+            .patch(format!("/task/{}", id))
+            .header(auth())
+            .header(ContentType::JSON)
+            .body(json)
+            .dispatch()
+    };
+    let response = patch(epic, format!(r#"{{"parent": {}}}"#, first));
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.into_json::<FieldError>().unwrap().field, "parent");
+    let response = patch(epic, format!(r#"{{"parent": {}}}"#, epic));
+    assert_eq!(response.status(), Status::BadRequest);
+    let response = patch(second, r#"{"parent": null}"#.to_string());
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(get(epic).children.total, 1);
+
+    let version = get(first).task.version;
+    client
+        .get(format!("/task/delete/{}", epic))
+        .header(auth())
+        .dispatch();
+    let orphan = get(first).task;
+    assert_eq!((orphan.parent, orphan.version), (None, version + 1));
+    for id in [first, second] {
+        client
+            .get(format!("/task/delete/{}", id))
+            .header(auth())
+            .dispatch();
+    }
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+#[test]
+fn test_recurring_tasks() {
+    test_private_board_creation();
+    let rocket = rocket();
+    let client = Client::tracked(rocket).expect("valid `Rocket`");
+
+    let user = get_test_user();
+    let _ = client.post("/register").json(&user).dispatch();
+    let token = login(&client, &user);
+    assert!(token.success);
+    let auth = || rocket::http::Header::new("Authorization", token.token.clone());
+
+    client
+        .post("/new_list")
+        .header(auth())
+        .json(&List {
+            id: None,
+            name: "Recurring".to_string(),
+            board_type: "private".to_string(),
+            board: 1,
+        })
+        .dispatch();
+    let list = client
+        .get("/list/private/1")
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<List>>()
+        .unwrap()
+        .into_iter()
+        .find(|list| list.name == "Recurring")
+        .unwrap()
+        .id
+        .unwrap();
+    // A Monday long past, so the first instance is due
+    client
+        .post("/task/create")
+        .header(auth())
+        .json(&Task {
+            id: None,
+            name: "Standup notes".to_string(),
+            list,
+            note: None,
+            place: Some("".to_string()),
+            members: None,
+            deadline: "2020-01-06".to_string(),
+            subtasks: "".to_string(),
+            points: 1,
+            tags: "".to_string(),
+            done: 0,
+            milestone: None,
+            version: 0,
+            parent: None,
+        })
+        .dispatch();
+    let get_tasks = || {
+        client
+            .get(format!("/task/get/{}", list))
+            .header(auth())
+            .dispatch()
+            .into_json::<Vec<TaskResponse>>()
+            .unwrap()
+    };
+    let first = get_tasks()[0].task.id.unwrap();
+
+    let set = |id: i32, rule: &str, list: i32| {
+        client
+            .post(format!("/recurrence/set/{}", id))
+            .header(auth())
+            .json(&RecurrenceData {
+                rule: rule.to_string(),
+                list,
+            })
+            .dispatch()
+    };
+    let response = set(first, "FREQ=YEARLY", list);
+    assert_eq!(response.status(), Status::BadRequest);
+    assert_eq!(response.into_json::<FieldError>().unwrap().field, "rule");
+    let response = set(first, "FREQ=WEEKLY", -1);
+    assert_eq!(response.into_json::<FieldError>().unwrap().field, "list");
+    assert_eq!(set(first, "FREQ=WEEKLY;COUNT=2", list).status(), Status::Ok);
+    assert!(get_tasks()[0].recurrence.is_some());
+
+    let runtime = rocket::tokio::runtime::Builder::new_current_thread()
+        .build()
+        .unwrap();
+    let create_next = || {
+        runtime.block_on(async {
+            let connection = Connection::get_one(client.rocket()).await.unwrap();
+            Recurrence::create_next(&connection)
+                .await
+                .unwrap()
+                .into_iter()
+                .filter(|task| task.list == list)
+                .collect::<Vec<_>>()
+        })
+    };
+    let created = create_next();
+    assert_eq!(created.len(), 1);
+    let next = created[0].id.unwrap();
+    let deadline = NaiveDate::parse_from_str(&created[0].deadline, "%Y-%m-%d").unwrap();
+    assert!(deadline > chrono::Local::now().date_naive());
+    assert_eq!(deadline.weekday(), chrono::Weekday::Mon);
+    let tasks = get_tasks();
+    let recurrence = |id: i32| {
+        tasks
+            .iter()
+            .find(|task| task.task.id == Some(id))
+            .unwrap()
+            .recurrence
+            .clone()
+    };
+    assert_eq!(recurrence(first), None);
+    assert_eq!(recurrence(next).unwrap().count, 2);
+    let logs = client
+        .get(format!("/logs/get/{}?limit=1", next))
+        .header(auth())
+        .dispatch()
+        .into_json::<Vec<Log>>()
+        .unwrap();
+    assert_eq!(logs[0].action, "created");
+
+    // The new instance is neither done nor due yet
+    assert!(create_next().is_empty());
+    client
+        .patch(format!("/task/{}", next))
+        .header(auth())
+        .header(ContentType::JSON)
+        .body(r#"{"done": 1}"#)
+        .dispatch();
+    // COUNT=2 ends the series
+    assert!(create_next().is_empty());
+    assert_eq!(get_tasks().len(), 2);
+    assert!(get_tasks().iter().all(|task| task.recurrence.is_none()));
+
+    assert_eq!(set(next, "FREQ=DAILY", list).status(), Status::Ok);
+    client
+        .get(format!("/recurrence/delete/{}", next))
+        .header(auth())
+        .dispatch();
+    assert!(get_tasks().iter().all(|task| task.recurrence.is_none()));
+
+    for id in [first, next] {
+        client
+            .get(format!("/task/delete/{}", id))
+            .header(auth())
+            .dispatch();
+    }
+    client
+        .get(format!("/list_delete/{}", list))
+        .header(auth())
+        .dispatch();
+}
+
+/// Milestone statistics the way they were computed before, loading every task of the board
+async fn naive_stats(
+    milestone: &MilestoneResponse,
+    connection: &Connection,
+) -> (i32, i32, i32, i32) {
+    let lists = List::get(milestone.board_type.clone(), milestone.board_id, connection)
+        .await
+        .unwrap();
+    let mut tasks = Vec::new();
+    for list in lists {
+        tasks.append(&mut Task::get(list.id.unwrap(), connection).await.unwrap());
+    }
+    let id = milestone.id.unwrap();
+    let tasks = tasks
+        .into_iter()
+        .filter(|task| utils::matches(task.milestone, id))
+        .collect::<Vec<_>>();
+    let done = tasks
+        .iter()
+        .filter(|task| task.done == 1)
+        .collect::<Vec<_>>();
+    // Tasks without points have -1
+    let points = |tasks: &[&Task]| tasks.iter().map(|task| task.points.max(0)).sum();
+    (
+        done.len() as i32,
+        tasks.len() as i32,
+        points(&done),
+        points(&tasks.iter().collect::<Vec<_>>()),
+    )
+}
+
+/// Run with `cargo test bench_milestone_stats -- --ignored --nocapture`
+#[rocket::async_test]
+#[ignore]
+async fn bench_milestone_stats() {
+    use rocket::local::asynchronous::Client;
+    use std::time::Instant;
+
+    let client = Client::tracked(rocket()).await.expect("valid `Rocket`");
+    let connection = Connection::get_one(client.rocket()).await.unwrap();
+    let board = 1000;
+    // Leftovers of a run which didn't finish
+    clear_bench_board(board, &connection).await;
+    for i in 0..10 {
+        let milestone = Milestone {
+            id: None,
+            name: format!("bench{}", i),
+            board_id: board,
+            board_type: "private".to_string(),
+            description: None,
+            start_date: None,
+            due_date: None,
+            status: "open".to_string(),
+        };
+        Milestone::create(milestone, &connection).await.unwrap();
+    }
+    let milestones = Milestone::get(board, "private".to_string(), &connection)
+        .await
+        .unwrap();
+    for i in 0..20 {
+        let list = List {
+            id: None,
+            name: format!("bench{}", i),
+            board,
+            board_type: "private".to_string(),
+        };
+        List::create(list, &connection).await.unwrap();
+    }
+    let lists = List::get("private".to_string(), board, &connection)
+        .await
+        .unwrap();
+    for (i, list) in lists.iter().enumerate() {
+        for j in 0..30 {
+            let task = Task {
+                id: None,
+                name: format!("bench{}", j),
+                list: list.id.unwrap(),
+                note: None,
+                place: None,
+                members: None,
+                deadline: "".to_string(),
+                subtasks: "".to_string(),
+                points: j % 5 - 1, // -1 is a task without points
+                tags: "".to_string(),
+                done: (j % 3 == 0) as i32,
+                milestone: milestones[(i + j as usize) % milestones.len()].id,
+                version: 0,
+                parent: None,
+            };
+            Task::create(task, &connection).await.unwrap();
+        }
+    }
+
+    let runs = 20;
+    let now = Instant::now();
+    for _ in 0..runs {
+        for milestone in &milestones {
+            naive_stats(milestone, &connection).await;
+        }
+    }
+    let naive = now.elapsed() / runs;
+    let now = Instant::now();
+    let mut result = Vec::new();
+    for _ in 0..runs {
+        result = Milestone::get(board, "private".to_string(), &connection)
+            .await
+            .unwrap();
+    }
+    let grouped = now.elapsed() / runs;
+    println!(
+        "milestone stats for 10 milestones, 600 tasks: per list {:?}, grouped sql {:?}",
+        naive, grouped
+    );
+
+    for milestone in &result {
+        assert_eq!(
+            naive_stats(milestone, &connection).await,
+            (
+                milestone.done,
+                milestone.total,
+                milestone.points_done,
+                milestone.points_total
+            )
+        );
+    }
+    assert_eq!(result.iter().map(|m| m.total).sum::<i32>(), 600);
+    clear_bench_board(board, &connection).await;
+}
+
+/// Deletes the milestones, lists and tasks of a private board of the tests with their logs
+async fn clear_bench_board(board: i32, connection: &Connection) {
+    use diesel::sql_types::Integer;
+    use diesel::RunQueryDsl;
+
+    let lists = "SELECT id FROM list WHERE board_type = 'private' AND board = ?";
+    let statements = [
+        format!(
+            "DELETE FROM log WHERE task_id IN (SELECT id FROM task WHERE list IN ({}))",
+            lists
+        ),
+        format!("DELETE FROM task WHERE list IN ({})", lists),
+        "DELETE FROM list WHERE board_type = 'private' AND board = ?".to_string(),
+        "DELETE FROM milestone WHERE board_type = 'private' AND board_id = ?".to_string(),
+    ];
+    connection
+        .run(move |conn| {
+            for statement in statements {
+                diesel::sql_query(statement)
+                    .bind::<Integer, _>(board)
+                    .execute(conn)?;
+            }
+            Ok::<_, diesel::result::Error>(())
+        })
+        .await
+        .unwrap();
+}
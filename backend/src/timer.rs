@@ -1,10 +1,14 @@
 use crate::db::Connection;
-use crate::schema::timer;
+use crate::page::Page;
+use crate::schema::{timer, timer_session, timer_settings};
+use crate::task::last_insert_rowid;
+use crate::types::{PomodoroConfig, TimerBilling};
 use crate::utils::get_time;
 use diesel::prelude::*;
 use diesel::AsChangeset;
 use diesel::Insertable;
 use diesel::Queryable;
+use diesel::SqliteConnection;
 use rocket::serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug)]
@@ -16,6 +20,49 @@ pub struct Timer {
     pub status: String,
     pub time: i32,
     pub start: Option<i32>,
+    pub review: i32, // Length of an automatically stopped session, 0 if there is nothing to review
+    pub kind: String, // "simple" or "pomodoro"
+    pub work: i32,
+    pub short_break: i32,
+    pub long_break: i32,
+    pub cycles: i32,
+    pub pomodoros: i32,
+    pub category: Option<i32>,
+    pub billable: i32,
+    pub client: Option<String>,
+    pub board_id: Option<i32>,
+    pub board_type: Option<String>, // "private" or "team", decides which billing rate applies
+    pub review_session: Option<i32>, // Session of the automatic stop, which the review corrects
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct PomodoroPhase {
+    pub phase: String, // "work", "short_break" or "long_break"
+    pub remaining: i32,
+    pub completed: i32,
+}
+
+/// Sessions longer than this are stopped automatically unless the user configured another limit.
+pub const DEFAULT_IDLE_LIMIT: i32 = 10 * 60 * 60;
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug)]
+#[table_name = "timer_settings"]
+pub struct TimerSettings {
+    pub id: Option<i32>,
+    pub user_id: i32,
+    pub idle_limit: i32,
+}
+
+#[derive(Serialize, Deserialize, Queryable, Insertable, AsChangeset, Debug)]
+#[table_name = "timer_session"]
+pub struct TimerSession {
+    pub id: Option<i32>,
+    pub timer_id: i32,
+    pub user_id: i32,
+    pub category: Option<i32>,
+    pub start: i32,
+    pub duration: i32,
+    pub billable: i32,
 }
 
 impl Timer {
@@ -35,30 +82,134 @@ impl Timer {
         } else {
             "active"
         };
-        let time = if timer.status == "active" {
-            timer.time + get_time() - timer.start.unwrap()
+        Self::set_status(timer, status, connection).await
+    }
+
+    /// Moves the timer to "active", "paused" or "stopped". Setting the status the timer already
+    /// has changes nothing, and the update only applies while the timer still has the status it
+    /// was read with, so the same request sent from several tabs is harmless.
+    pub async fn set_status(
+        timer: Timer,
+        status: &'static str,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        if timer.status == status {
+            return Ok(0);
+        }
+        let now = get_time();
+        let session = if timer.status == "active" {
+            now - timer.start.unwrap()
         } else {
-            timer.time
+            0
         };
+        let pomodoros = timer.pomodoros + timer.pomodoro_phase(session).completed;
         connection
             .run(move |conn| {
-                diesel::update(timer::table.filter(timer::id.eq(timer.id)))
-                    .set((
-                        timer::status.eq(status),
-                        timer::time.eq(time),
-                        timer::start.eq(get_time()),
-                    ))
+                let cnt = diesel::update(
+                    timer::table
+                        .filter(timer::id.eq(timer.id))
+                        .filter(timer::status.eq(&timer.status)),
+                )
+                .set((
+                    timer::status.eq(status),
+                    timer::time.eq(timer.time + session),
+                    timer::start.eq(now),
+                    timer::pomodoros.eq(pomodoros),
+                ))
+                .execute(conn)?;
+                // This request changed the status, with 0 rows another one did and recorded it
+                if cnt == 1 && timer.status == "active" {
+                    TimerSession::record(&timer, session, conn)?;
+                }
+                Ok(cnt)
+            })
+            .await
+    }
+
+    pub async fn get_timers(
+        user_id: i32,
+        category: Option<i32>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<Timer>> {
+        connection
+            .run(move |conn| {
+                let mut query = timer::table.filter(timer::user_id.eq(user_id)).into_boxed();
+                if let Some(category) = category {
+                    query = query.filter(timer::category.eq(category));
+                }
+                query.load::<Timer>(conn)
+            })
+            .await
+    }
+
+    pub const SORT: [(&'static str, &'static str); 2] =
+        [("name", "timer.name"), ("created", "timer.id")];
+
+    /// Page of the user's timers and the number of all of them
+    pub async fn get_timers_page(
+        user_id: i32,
+        category: Option<i32>,
+        page: Page,
+        connection: &Connection,
+    ) -> QueryResult<(Vec<Timer>, i64)> {
+        connection
+            .run(move |conn| {
+                let filtered = || {
+                    let mut query = timer::table.filter(timer::user_id.eq(user_id)).into_boxed();
+                    if let Some(category) = category {
+                        query = query.filter(timer::category.eq(category));
+                    }
+                    query
+                };
+                let total = filtered().count().get_result(conn)?;
+                let timers = filtered()
+                    .order(page.order())
+                    .offset(page.offset)
+                    .limit(page.sql_limit())
+                    .load::<Timer>(conn)?;
+                Ok((timers, total))
+            })
+            .await
+    }
+
+    /// Sessions recorded before the timer had a category are moved to the new one as well
+    pub async fn set_category(
+        id: i32,
+        category: Option<i32>,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::update(
+                    timer_session::table
+                        .filter(timer_session::timer_id.eq(id))
+                        .filter(timer_session::category.is_null()),
+                )
+                .set(timer_session::category.eq(category))
+                .execute(conn)?;
+                diesel::update(timer::table.filter(timer::id.eq(id)))
+                    .set(timer::category.eq(category))
                     .execute(conn)
             })
             .await
     }
 
-    pub async fn get_timers(user_id: i32, connection: &Connection) -> QueryResult<Vec<Timer>> {
+    /// Sessions recorded from now on inherit the billable flag, earlier ones are marked separately
+    pub async fn set_billing(
+        id: i32,
+        billing: TimerBilling,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
         connection
             .run(move |conn| {
-                timer::table
-                    .filter(timer::user_id.eq(user_id))
-                    .load::<Timer>(conn)
+                diesel::update(timer::table.filter(timer::id.eq(id)))
+                    .set((
+                        timer::billable.eq(billing.billable as i32),
+                        timer::client.eq(billing.client),
+                        timer::board_id.eq(billing.board_id),
+                        timer::board_type.eq(billing.board_type),
+                    ))
+                    .execute(conn)
             })
             .await
     }
@@ -71,7 +222,356 @@ impl Timer {
 
     pub async fn delete(id: i32, connection: &Connection) -> QueryResult<usize> {
         connection
-            .run(move |conn| diesel::delete(timer::table.filter(timer::id.eq(id))).execute(conn))
+            .run(move |conn| {
+                diesel::delete(timer_session::table.filter(timer_session::timer_id.eq(id)))
+                    .execute(conn)?;
+                diesel::delete(timer::table.filter(timer::id.eq(id))).execute(conn)
+            })
+            .await
+    }
+
+    pub async fn stop_idle(connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(|conn| {
+                let timers = timer::table
+                    .filter(timer::status.eq("active"))
+                    .load::<Timer>(conn)?;
+                let now = get_time();
+                let mut stopped = 0;
+                for timer in timers {
+                    let limit = timer_settings::table
+                        .filter(timer_settings::user_id.eq(timer.user_id))
+                        .select(timer_settings::idle_limit)
+                        .first::<i32>(conn)
+                        .optional()?
+                        .unwrap_or(DEFAULT_IDLE_LIMIT);
+                    if now - timer.start.unwrap_or(now) < limit {
+                        continue;
+                    }
+                    // The session is cut at the limit, the user decides later how long it really was.
+                    // Skipped when the user stopped, paused or restarted the timer since it was loaded.
+                    stopped += diesel::Connection::transaction::<_, diesel::result::Error, _>(
+                        conn,
+                        || {
+                            let cnt = diesel::update(
+                                timer::table
+                                    .filter(timer::id.eq(timer.id))
+                                    .filter(timer::status.eq("active"))
+                                    .filter(timer::start.eq(timer.start)),
+                            )
+                            .set((
+                                timer::status.eq("stopped"),
+                                timer::time.eq(timer.time + limit),
+                                timer::start.eq(now),
+                                timer::review.eq(limit),
+                                timer::pomodoros
+                                    .eq(timer.pomodoros + timer.pomodoro_phase(limit).completed),
+                            ))
+                            .execute(conn)?;
+                            if cnt == 1 {
+                                let session = TimerSession::record(&timer, limit, conn)?;
+                                diesel::update(timer::table.filter(timer::id.eq(timer.id)))
+                                    .set(timer::review_session.eq(session))
+                                    .execute(conn)?;
+                            }
+                            Ok(cnt)
+                        },
+                    )?;
+                }
+                Ok(stopped)
+            })
+            .await
+    }
+
+    pub async fn set_pomodoro(
+        id: i32,
+        config: PomodoroConfig,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::update(timer::table.filter(timer::id.eq(id)))
+                    .set((
+                        timer::kind.eq("pomodoro"),
+                        timer::work.eq(config.work),
+                        timer::short_break.eq(config.short_break),
+                        timer::long_break.eq(config.long_break),
+                        timer::cycles.eq(config.cycles),
+                    ))
+                    .execute(conn)
+            })
+            .await
+    }
+
+    /// Where a pomodoro session is after `elapsed` seconds. `completed` counts work phases
+    /// finished in this session. Simple timers are treated as never finishing a work phase.
+    pub fn pomodoro_phase(&self, elapsed: i32) -> PomodoroPhase {
+        if self.kind != "pomodoro" {
+            return PomodoroPhase {
+                phase: "work".to_owned(),
+                remaining: 0,
+                completed: 0,
+            };
+        }
+        // Timers saved before the limits existed may not make a valid round
+        if self.work <= 0 || self.short_break <= 0 || self.long_break <= 0 || self.cycles <= 0 {
+            return PomodoroPhase {
+                phase: "work".to_owned(),
+                remaining: 0,
+                completed: 0,
+            };
+        }
+        let (work, short_break, long_break) = (
+            self.work as i64,
+            self.short_break as i64,
+            self.long_break as i64,
+        );
+        let cycles = self.cycles as i64;
+        // Every cycle but the last one ends with a short break
+        let cycle = work + short_break;
+        let round = (cycles - 1) * cycle + work + long_break;
+        let elapsed = elapsed.max(0) as i64;
+        let mut completed = elapsed / round * cycles;
+        let left = elapsed % round;
+        let (phase, remaining) = if left < (cycles - 1) * cycle {
+            completed += left / cycle;
+            match left % cycle {
+                in_cycle if in_cycle < work => ("work", work - in_cycle),
+                in_cycle => {
+                    completed += 1;
+                    ("short_break", cycle - in_cycle)
+                }
+            }
+        } else {
+            completed += cycles - 1;
+            match left - (cycles - 1) * cycle {
+                in_cycle if in_cycle < work => ("work", work - in_cycle),
+                in_cycle => {
+                    completed += 1;
+                    ("long_break", work + long_break - in_cycle)
+                }
+            }
+        };
+        PomodoroPhase {
+            phase: phase.to_owned(),
+            remaining: remaining as i32,
+            completed: completed as i32,
+        }
+    }
+
+    pub async fn review(timer: Timer, session: i32, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                // Sessions recorded since the automatic stop stay as they are
+                diesel::update(
+                    timer_session::table.filter(timer_session::id.eq(timer.review_session)),
+                )
+                .set(timer_session::duration.eq(session))
+                .execute(conn)?;
+                diesel::update(timer::table.filter(timer::id.eq(timer.id)))
+                    .set((
+                        timer::time.eq(timer.time - timer.review + session),
+                        timer::review.eq(0),
+                        timer::review_session.eq(None::<i32>),
+                    ))
+                    .execute(conn)
+            })
+            .await
+    }
+}
+
+impl TimerSession {
+    /// Stores a finished run of the timer, called whenever a running timer gets stopped.
+    /// Returns the id of the session.
+    pub fn record(timer: &Timer, duration: i32, connection: &SqliteConnection) -> QueryResult<i32> {
+        diesel::insert_into(timer_session::table)
+            .values(TimerSession {
+                id: None,
+                timer_id: timer.id.unwrap(),
+                user_id: timer.user_id,
+                category: timer.category,
+                start: timer.start.unwrap(),
+                duration,
+                billable: timer.billable,
+            })
+            .execute(connection)?;
+        diesel::select(last_insert_rowid).get_result::<i32>(connection)
+    }
+
+    pub async fn get(timer_id: i32, connection: &Connection) -> QueryResult<Vec<TimerSession>> {
+        connection
+            .run(move |conn| {
+                timer_session::table
+                    .filter(timer_session::timer_id.eq(timer_id))
+                    .order(timer_session::start.desc())
+                    .load::<TimerSession>(conn)
+            })
+            .await
+    }
+
+    pub async fn get_by_id(id: i32, connection: &Connection) -> QueryResult<TimerSession> {
+        connection
+            .run(move |conn| {
+                timer_session::table
+                    .filter(timer_session::id.eq(id))
+                    .first(conn)
+            })
+            .await
+    }
+
+    pub async fn get_by_user(
+        user_id: i32,
+        from: Option<i32>,
+        to: Option<i32>,
+        connection: &Connection,
+    ) -> QueryResult<Vec<TimerSession>> {
+        connection
+            .run(move |conn| {
+                let mut query = timer_session::table
+                    .filter(timer_session::user_id.eq(user_id))
+                    .into_boxed();
+                if let Some(from) = from {
+                    query = query.filter(timer_session::start.ge(from));
+                }
+                if let Some(to) = to {
+                    query = query.filter(timer_session::start.lt(to));
+                }
+                query.order(timer_session::start).load::<TimerSession>(conn)
+            })
+            .await
+    }
+
+    pub async fn set_category(
+        id: i32,
+        category: Option<i32>,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::update(timer_session::table.filter(timer_session::id.eq(id)))
+                    .set(timer_session::category.eq(category))
+                    .execute(conn)
+            })
+            .await
+    }
+    pub async fn set_billable(
+        id: i32,
+        billable: bool,
+        connection: &Connection,
+    ) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::update(timer_session::table.filter(timer_session::id.eq(id)))
+                    .set(timer_session::billable.eq(billable as i32))
+                    .execute(conn)
+            })
+            .await
+    }
+}
+
+impl TimerSettings {
+    pub async fn get(user_id: i32, connection: &Connection) -> QueryResult<TimerSettings> {
+        connection
+            .run(move |conn| {
+                let settings = timer_settings::table
+                    .filter(timer_settings::user_id.eq(user_id))
+                    .first::<TimerSettings>(conn)
+                    .optional()?;
+                Ok(settings.unwrap_or(TimerSettings {
+                    id: None,
+                    user_id,
+                    idle_limit: DEFAULT_IDLE_LIMIT,
+                }))
+            })
+            .await
+    }
+
+    pub async fn set(settings: TimerSettings, connection: &Connection) -> QueryResult<usize> {
+        connection
+            .run(move |conn| {
+                diesel::replace_into(timer_settings::table)
+                    .values((
+                        timer_settings::user_id.eq(settings.user_id),
+                        timer_settings::idle_limit.eq(settings.idle_limit),
+                    ))
+                    .execute(conn)
+            })
             .await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pomodoro() -> Timer {
+        Timer {
+            id: Some(1),
+            name: "a".to_string(),
+            user_id: 1,
+            status: "active".to_string(),
+            time: 0,
+            start: Some(0),
+            review: 0,
+            kind: "pomodoro".to_string(),
+            work: 25,
+            short_break: 5,
+            long_break: 15,
+            cycles: 2,
+            pomodoros: 0,
+            category: None,
+            billable: 0,
+            client: None,
+            board_id: None,
+            board_type: None,
+            review_session: None,
+        }
+    }
+
+    #[test]
+    fn test_pomodoro_phase() {
+        let timer = pomodoro();
+        assert_eq!(timer.pomodoro_phase(0).phase, "work");
+        assert_eq!(timer.pomodoro_phase(0).remaining, 25);
+        assert_eq!(timer.pomodoro_phase(0).completed, 0);
+
+        let phase = timer.pomodoro_phase(27);
+        assert_eq!(phase.phase, "short_break");
+        assert_eq!(phase.remaining, 3);
+        assert_eq!(phase.completed, 1);
+
+        let phase = timer.pomodoro_phase(55);
+        assert_eq!(phase.phase, "long_break");
+        assert_eq!(phase.remaining, 15);
+        assert_eq!(phase.completed, 2);
+
+        let phase = timer.pomodoro_phase(75);
+        assert_eq!(phase.phase, "work");
+        assert_eq!(phase.remaining, 20);
+        assert_eq!(phase.completed, 2);
+    }
+
+    #[test]
+    fn test_simple_timer_has_no_pomodoros() {
+        let timer = Timer {
+            kind: "simple".to_string(),
+            ..pomodoro()
+        };
+        assert_eq!(timer.pomodoro_phase(1000).completed, 0);
+    }
+
+    #[test]
+    fn test_pomodoro_phase_limits() {
+        let timer = Timer {
+            work: PomodoroConfig::MAX_PHASE,
+            short_break: PomodoroConfig::MAX_PHASE,
+            long_break: PomodoroConfig::MAX_PHASE,
+            cycles: PomodoroConfig::MAX_CYCLES,
+            ..pomodoro()
+        };
+        // 1035 rounds of 12 pomodoros, then the short break after the 8th one
+        let phase = timer.pomodoro_phase(i32::MAX);
+        assert_eq!(phase.phase, "short_break");
+        assert_eq!((phase.remaining, phase.completed), (74753, 12428));
+    }
+}
@@ -1,6 +1,9 @@
-use rocket::serde::{Deserialize, Serialize};
+use chrono::NaiveDate;
+use rocket::serde::{Deserialize, Deserializer, Serialize};
 
 use crate::board::TeamBoard;
+use crate::goal::{DAY, WEEK};
+use crate::task::Task;
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct Credentials {
@@ -20,6 +23,7 @@ pub struct TeamBoardWithName {
     pub name: String,
     pub owner: i32,
     pub team_name: String,
+    pub version: i32,
 }
 
 impl TeamBoardWithName {
@@ -31,6 +35,7 @@ impl TeamBoardWithName {
                 name: x.name,
                 owner: x.owner,
                 team_name: name.clone(),
+                version: x.version,
             })
             .collect()
     }
@@ -62,14 +67,180 @@ pub struct TeamData {
 #[derive(Serialize, Deserialize)]
 pub struct BoardUpdate {
     pub name: String,
+    #[serde(default)]
+    pub version: i32, // Version the client edited, the If-Match header takes precedence
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct TimerData {
     pub name: String,
+    pub pomodoro: Option<PomodoroConfig>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+#[derive(Deserialize, Serialize, Clone, Debug)]
+pub struct PomodoroConfig {
+    pub work: i32,
+    pub short_break: i32,
+    pub long_break: i32,
+    pub cycles: i32,
+}
+
+impl Default for PomodoroConfig {
+    fn default() -> Self {
+        PomodoroConfig {
+            work: 25 * 60,
+            short_break: 5 * 60,
+            long_break: 15 * 60,
+            cycles: 4,
+        }
+    }
+}
+
+impl PomodoroConfig {
+    /// Longest phase in seconds, and most work phases in a round
+    pub const MAX_PHASE: i32 = 24 * 60 * 60;
+    pub const MAX_CYCLES: i32 = 12;
+
+    pub fn is_valid(&self) -> bool {
+        let phase = |length: i32| (1..=Self::MAX_PHASE).contains(&length);
+        phase(self.work)
+            && phase(self.short_break)
+            && phase(self.long_break)
+            && (1..=Self::MAX_CYCLES).contains(&self.cycles)
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CategoryData {
+    pub name: String,
+    pub color: String,
+    pub team: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct CategoryAssignment {
+    pub category: Option<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct MilestoneUpdate {
+    pub name: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub due_date: Option<String>,
+    pub status: String,
+}
+
+impl MilestoneUpdate {
+    pub fn is_valid(&self) -> bool {
+        let date = |date: &Option<String>| match date {
+            Some(date) => NaiveDate::parse_from_str(date, "%Y-%m-%d").map(Some),
+            None => Ok(None),
+        };
+        let dates = match (date(&self.start_date), date(&self.due_date)) {
+            (Ok(Some(start)), Ok(Some(due))) => start <= due,
+            (Ok(_), Ok(_)) => true,
+            _ => false,
+        };
+        !self.name.is_empty() && ["open", "closed"].contains(&self.status.as_str()) && dates
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct MilestonePredecessors {
+    pub predecessors: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TaskBlockers {
+    pub blockers: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RecurrenceData {
+    pub rule: String, // See `recurrence::Rule`
+    pub list: i32,    // Where the next instances are created
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct MilestoneTasksUpdate {
+    pub add: Vec<i32>,
+    pub remove: Vec<i32>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TimerBilling {
+    pub billable: bool,
+    pub client: Option<String>,
+    pub board_id: Option<i32>,
+    pub board_type: Option<String>,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct SessionBilling {
+    pub billable: bool,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct RateData {
+    pub scope: String,
+    pub scope_id: i32, // Ignored for the "user" scope
+    pub rate: i32,     // Cents per hour
+    pub currency: String,
+    pub rounding: i32, // Minutes
+    pub rounding_mode: String,
+}
+
+impl RateData {
+    /// Longest rounding step, in minutes
+    pub const MAX_ROUNDING: i32 = 24 * 60;
+
+    pub fn is_valid(&self) -> bool {
+        ["user", "team", "private_board", "team_board"].contains(&self.scope.as_str())
+            && ["up", "down", "nearest"].contains(&self.rounding_mode.as_str())
+            && self.rate >= 0
+            && (0..=Self::MAX_ROUNDING).contains(&self.rounding)
+            && self.currency.len() == 3
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct GoalData {
+    pub period: String,
+    pub category: Option<i32>,
+    pub target: i32, // Seconds per period
+}
+
+impl GoalData {
+    pub fn is_valid(&self) -> bool {
+        match self.period.as_str() {
+            "day" => self.target > 0 && self.target <= DAY,
+            "week" => self.target > 0 && self.target <= WEEK,
+            _ => false,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TimerSettingsData {
+    pub idle_limit: i32,
+}
+
+#[derive(Deserialize, Serialize)]
+pub struct TimerReview {
+    pub session: i32,
+}
+
+impl TimerReview {
+    /// Longest session in seconds
+    pub const MAX_SESSION: i32 = 24 * 60 * 60;
+
+    pub fn is_valid(&self) -> bool {
+        (0..=Self::MAX_SESSION).contains(&self.session)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct TaskFilter {
     pub name: String,
     pub place: String,
@@ -79,12 +250,230 @@ pub struct TaskFilter {
     pub points_min: Option<i32>,
     pub points_max: Option<i32>,
     pub tags: String,
+    #[serde(default)]
+    pub query: String, // See `query` for the syntax
+    pub done: Option<bool>,
+    pub milestone: Option<i32>,
+    #[serde(default)]
+    pub overdue: bool, // Deadline passed and not done
+    #[serde(default)]
+    pub no_deadline: bool,
+    #[serde(default)]
+    pub unassigned: bool,
+    #[serde(default)]
+    pub open_subtasks: bool, // Has subtasks and isn't done
+    #[serde(default)]
+    pub members_any: bool, // Any of the `;` separated members instead of all of them
+    #[serde(default)]
+    pub tags_any: bool,
+    #[serde(default)]
+    pub blocked: Option<bool>, // Some of the blockers aren't done
+    #[serde(default)]
+    pub epic: Option<bool>, // Has child tasks
+    #[serde(default)]
+    pub parent: Option<i32>,
+}
+
+/// Tells a null field, Some(None), from an absent one, None
+fn present<'de, T: Deserialize<'de>, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<Option<Option<T>>, D::Error> {
+    Option::<T>::deserialize(deserializer).map(Some)
+}
+
+/// JSON Merge Patch of a task: absent fields are kept, null clears a field
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq)]
+#[serde(deny_unknown_fields)]
+pub struct TaskPatch {
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub name: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub list: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub note: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub place: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub members: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub deadline: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub subtasks: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub points: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub tags: Option<Option<String>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub done: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub milestone: Option<Option<i32>>,
+    #[serde(
+        default,
+        deserialize_with = "present",
+        skip_serializing_if = "Option::is_none"
+    )]
+    pub parent: Option<Option<i32>>,
+    #[serde(default, skip_serializing)]
+    pub version: Option<i32>, // Version the patch was made to, the If-Match header takes precedence
+}
+
+/// Invalid field of a request
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &str, message: &str) -> FieldError {
+        FieldError {
+            field: field.to_owned(),
+            message: message.to_owned(),
+        }
+    }
+}
+
+impl TaskPatch {
+    /// The task with the patch applied. Nulls clear the optional fields, and set the others
+    /// to what the rest of the app means by none: no deadline, subtasks, tags or points.
+    pub fn apply(&self, task: Task) -> Result<Task, FieldError> {
+        let required = |field: &str| FieldError::new(field, "can't be null");
+        let text = |value: &Option<Option<String>>, old: String| match value {
+            Some(value) => value.clone().unwrap_or_default(),
+            None => old,
+        };
+        let name = match &self.name {
+            Some(Some(name)) if name.is_empty() => {
+                return Err(FieldError::new("name", "can't be empty"))
+            }
+            Some(Some(name)) => name.clone(),
+            Some(None) => return Err(required("name")),
+            None => task.name,
+        };
+        let deadline = text(&self.deadline, task.deadline);
+        if self.deadline.is_some()
+            && !deadline.is_empty()
+            && NaiveDate::parse_from_str(&deadline, "%Y-%m-%d").is_err()
+        {
+            return Err(FieldError::new(
+                "deadline",
+                "expected a date like 2026-11-01",
+            ));
+        }
+        let points = match self.points {
+            Some(Some(points)) if points < 0 => {
+                return Err(FieldError::new("points", "can't be negative"))
+            }
+            Some(points) => points.unwrap_or(-1),
+            None => task.points,
+        };
+        let done = match self.done {
+            Some(Some(done)) if done != 0 && done != 1 => {
+                return Err(FieldError::new("done", "expected 0 or 1"))
+            }
+            Some(Some(done)) => done,
+            Some(None) => return Err(required("done")),
+            None => task.done,
+        };
+        Ok(Task {
+            id: task.id,
+            name,
+            list: match self.list {
+                Some(Some(list)) => list,
+                Some(None) => return Err(required("list")),
+                None => task.list,
+            },
+            note: self.note.clone().unwrap_or(task.note),
+            place: self.place.clone().unwrap_or(task.place),
+            members: self.members.clone().unwrap_or(task.members),
+            deadline,
+            subtasks: text(&self.subtasks, task.subtasks),
+            points,
+            tags: text(&self.tags, task.tags),
+            done,
+            milestone: self.milestone.unwrap_or(task.milestone),
+            version: task.version,
+            parent: self.parent.unwrap_or(task.parent),
+        })
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CommentData {
+    pub body: String,
+    #[serde(default)]
+    pub parent: Option<i32>, // Comment replied to, on the same task
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct SavedFilterData {
+    pub name: String,
+    pub board_type: String,
+    pub board_id: i32,
+    pub shared: bool, // Visible to the whole team, only on team boards
+    pub filter: TaskFilter,
+}
+
+impl SavedFilterData {
+    pub fn is_valid(&self) -> bool {
+        !self.name.is_empty()
+            && match self.board_type.as_str() {
+                "private" => !self.shared,
+                "team" => true,
+                _ => false,
+            }
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
     use super::*;
+    use rocket::serde::json::serde_json;
 
     #[test]
     fn test_token_response() {
@@ -93,15 +482,148 @@ mod tests {
         assert!(response.success);
     }
 
+    #[test]
+    fn test_saved_filter_validation() {
+        let mut data = SavedFilterData {
+            name: "mine".to_string(),
+            board_type: "private".to_string(),
+            board_id: 1,
+            shared: false,
+            filter: TaskFilter {
+                name: "".to_string(),
+                place: "".to_string(),
+                members: "".to_string(),
+                deadline_start: "".to_string(),
+                deadline_end: "".to_string(),
+                points_min: None,
+                points_max: None,
+                tags: "".to_string(),
+                query: "".to_string(),
+                ..Default::default()
+            },
+        };
+        assert!(data.is_valid());
+        data.shared = true;
+        assert!(!data.is_valid());
+        data.board_type = "team".to_string();
+        assert!(data.is_valid());
+        data.name = "".to_string();
+        assert!(!data.is_valid());
+    }
+
+    #[test]
+    fn test_pomodoro_config_validation() {
+        let mut config = PomodoroConfig::default();
+        assert!(config.is_valid());
+        config.cycles = PomodoroConfig::MAX_CYCLES + 1;
+        assert!(!config.is_valid());
+        config.cycles = 4;
+        config.long_break = PomodoroConfig::MAX_PHASE + 1;
+        assert!(!config.is_valid());
+        config.long_break = 0;
+        assert!(!config.is_valid());
+    }
+
+    #[test]
+    fn test_timer_review_validation() {
+        assert!(TimerReview { session: 0 }.is_valid());
+        assert!(TimerReview {
+            session: TimerReview::MAX_SESSION
+        }
+        .is_valid());
+        assert!(!TimerReview { session: -1 }.is_valid());
+        assert!(!TimerReview { session: i32::MAX }.is_valid());
+    }
+
+    #[test]
+    fn test_milestone_update_validation() {
+        let mut update = MilestoneUpdate {
+            name: "v1".to_string(),
+            description: None,
+            start_date: Some("2022-06-01".to_string()),
+            due_date: Some("2022-06-30".to_string()),
+            status: "open".to_string(),
+        };
+        assert!(update.is_valid());
+        update.due_date = Some("2022-05-30".to_string());
+        assert!(!update.is_valid());
+        update.due_date = Some("30.06.2022".to_string());
+        assert!(!update.is_valid());
+        update.due_date = None;
+        update.status = "done".to_string();
+        assert!(!update.is_valid());
+    }
+
     #[test]
     fn test_team_board_with_name() {
         let board = TeamBoard {
             id: Some(1),
             name: "a".to_string(),
             owner: 1,
+            version: 0,
         };
         let response = TeamBoardWithName::new(vec![board], "Ala ma kota".to_string());
         assert_eq!(response.len(), 1);
         assert_eq!(response.get(0).unwrap().team_name, "Ala ma kota");
     }
+
+    fn task() -> Task {
+        Task {
+            id: Some(1),
+            name: "a".to_string(),
+            list: 1,
+            note: Some("note".to_string()),
+            place: None,
+            members: None,
+            deadline: "2022-06-01".to_string(),
+            subtasks: "".to_string(),
+            points: 3,
+            tags: "bug".to_string(),
+            done: 0,
+            milestone: Some(2),
+            version: 4,
+            parent: None,
+        }
+    }
+
+    #[test]
+    fn test_task_patch_apply() {
+        let patch: TaskPatch =
+            serde_json::from_str(r#"{"done": 1, "note": null, "points": null, "tags": null}"#)
+                .unwrap();
+        let patched = patch.apply(task()).unwrap();
+        assert_eq!((patched.done, patched.points), (1, -1));
+        assert_eq!((patched.note, patched.tags.as_str()), (None, ""));
+        assert_eq!((patched.name.as_str(), patched.milestone), ("a", Some(2)));
+        assert_eq!(patched.version, 4);
+        // Only the fields sent are serialized, nulls included
+        assert_eq!(
+            serde_json::to_string(&patch).unwrap(),
+            r#"{"note":null,"points":null,"tags":null,"done":1}"#
+        );
+        let unchanged = TaskPatch::default().apply(task()).unwrap();
+        assert_eq!(
+            serde_json::to_value(unchanged).unwrap(),
+            serde_json::to_value(task()).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_task_patch_errors() {
+        let error = |json: &str| {
+            serde_json::from_str::<TaskPatch>(json)
+                .unwrap()
+                .apply(task())
+                .unwrap_err()
+                .field
+        };
+        assert_eq!(error(r#"{"name": null}"#), "name");
+        assert_eq!(error(r#"{"name": ""}"#), "name");
+        assert_eq!(error(r#"{"deadline": "tomorrow"}"#), "deadline");
+        assert_eq!(error(r#"{"points": -2}"#), "points");
+        assert_eq!(error(r#"{"done": 2}"#), "done");
+        assert_eq!(error(r#"{"list": null}"#), "list");
+        assert!(serde_json::from_str::<TaskPatch>(r#"{"colour": "red"}"#).is_err());
+        assert!(serde_json::from_str::<TaskPatch>(r#"{"points": "many"}"#).is_err());
+    }
 }
@@ -1,5 +1,6 @@
 use crate::board::PrivateBoard;
 use crate::db::Connection;
+use crate::page::Page;
 use crate::schema::private_board;
 use crate::schema::users;
 use diesel::prelude::*;
@@ -60,15 +61,25 @@ impl User {
             .await
     }
 
+    /// Page of the user's private boards and the number of all of them
     pub async fn get_private_boards(
         user_id: i32,
+        page: Page,
         connection: &Connection,
-    ) -> QueryResult<Vec<PrivateBoard>> {
+    ) -> QueryResult<(Vec<PrivateBoard>, i64)> {
         connection
             .run(move |conn| {
-                private_board::table
+                let total = private_board::table
                     .filter(private_board::owner.eq(user_id))
-                    .load::<PrivateBoard>(conn)
+                    .count()
+                    .get_result(conn)?;
+                let boards = private_board::table
+                    .filter(private_board::owner.eq(user_id))
+                    .order(page.order())
+                    .offset(page.offset)
+                    .limit(page.sql_limit())
+                    .load::<PrivateBoard>(conn)?;
+                Ok((boards, total))
             })
             .await
     }
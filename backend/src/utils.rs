@@ -1,6 +1,7 @@
 use std::time::{SystemTime, UNIX_EPOCH};
 
 use chrono::{DateTime, Local};
+use std::collections::HashSet;
 
 pub fn get_time() -> i32 {
     let time = SystemTime::now();
@@ -19,6 +20,33 @@ pub fn matches(x: Option<i32>, y: i32) -> bool {
     x.is_some() && x.unwrap() == y
 }
 
+/// Accepts colors in the `#rrggbb` form sent by color inputs
+pub fn is_color(color: &str) -> bool {
+    color.len() == 7
+        && color.starts_with('#')
+        && color.chars().skip(1).all(|c| c.is_ascii_hexdigit())
+}
+
+/// Whether `to` can be reached from `from` through `edges`, given as (node, next node) pairs
+pub fn reaches(edges: &[(i32, i32)], from: i32, to: i32) -> bool {
+    let mut stack = vec![from];
+    let mut visited = HashSet::new();
+    while let Some(node) = stack.pop() {
+        if node == to {
+            return true;
+        }
+        if visited.insert(node) {
+            stack.extend(
+                edges
+                    .iter()
+                    .filter(|edge| edge.0 == node)
+                    .map(|edge| edge.1),
+            );
+        }
+    }
+    false
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -29,4 +57,22 @@ mod tests {
         assert!(!matches(Some(10), 11));
         assert!(!matches(None, 10));
     }
+
+    #[test]
+    fn test_is_color() {
+        assert!(is_color("#12abEF"));
+        assert!(!is_color("12abEF"));
+        assert!(!is_color("#12abEG"));
+        assert!(!is_color("#fff"));
+    }
+
+    #[test]
+    fn test_reaches() {
+        let edges = [(1, 2), (2, 3), (4, 1), (3, 5)];
+        assert!(reaches(&edges, 1, 5));
+        assert!(reaches(&edges, 4, 3));
+        assert!(reaches(&edges, 2, 2));
+        assert!(!reaches(&edges, 3, 1));
+        assert!(!reaches(&edges, 6, 1));
+    }
 }
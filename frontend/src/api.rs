@@ -1,12 +1,20 @@
 use gloo_net::http::{Request, Response};
 use gloo_net::Error;
+use serde::de::DeserializeOwned;
 use serde::Serialize;
 
 use crate::types::{
-    BoardUpdate, List, Log, Login, LoginResponse, Milestone, MilestoneCreate, PrivateBoard,
-    PrivateBoardData, Task, TaskFilter, Team, TeamBoard, TeamBoardData, TeamData, Timer, TimerData,
+    BillingRate, BillingSummary, BoardUpdate, BoardVersion, Burndown, CategoryAssignment,
+    CategoryData, CategoryReport, Comment, CommentActivity, CommentData, FieldError, GoalData,
+    GoalProgress, List, Log, Login, LoginResponse, MilestoneCreate, MilestonePredecessors,
+    MilestoneTasks, MilestoneTasksUpdate, MilestoneUpdate, ParseError, PomodoroConfig,
+    PrivateBoard, PrivateBoardData, RateData, RecurrenceData, RoadmapItem, SavedFilter,
+    SavedFilterData, SearchResult, SessionBilling, Snapshot, Task, TaskBlockers, TaskFilter, Team,
+    TeamBoard, TeamBoardData, TeamData, Timer, TimerBilling, TimerCategory, TimerData, TimerReview,
+    TimerSession, TimerSettings, UpdateResult,
 };
-use crate::utils::get_backend;
+use crate::utils::{encode_uri_component, get_backend, upload_file};
+use wasm_bindgen_futures::JsFuture;
 
 fn get_login_register_req(url: &str, username: &str, password: &str) -> Result<Request, Error> {
     let url = format!("{}{}", get_backend(), url);
@@ -46,6 +54,29 @@ async fn get(url: String, token: &str) -> Result<Response, Error> {
         .await
 }
 
+/// Result of a versioned update, the backend answers 409 with the current version
+async fn updated<T: DeserializeOwned>(response: Response) -> Result<UpdateResult<T>, Error> {
+    if response.status() == 409 {
+        return Ok(UpdateResult::Conflict(response.json().await?));
+    }
+    response.json::<bool>().await?;
+    Ok(UpdateResult::Updated)
+}
+
+/// Items loaded per request by the listings which load more on scroll
+pub const PAGE_SIZE: usize = 20;
+
+/// Page of a listing and the number of all its items
+async fn get_page<T: DeserializeOwned>(url: String, token: &str) -> Result<(Vec<T>, i64), Error> {
+    let response = get(url, token).await?;
+    let total = response
+        .headers()
+        .get("X-Total-Count")
+        .and_then(|total| total.parse::<i64>().ok())
+        .unwrap_or(0);
+    Ok((response.json().await?, total))
+}
+
 pub async fn create_private_board(name: &str, token: &str) -> Result<bool, Error> {
     let url = format!("{}{}", get_backend(), "private_board/create");
     let body_obj = PrivateBoardData {
@@ -92,15 +123,17 @@ pub async fn create_list(token: &str, list: List) -> Result<bool, Error> {
     send_request(url, list, token).await?.json().await
 }
 
+/// Conflict with the current name when the board was renamed since `version`
 pub async fn update_board(
     token: &str,
     id: i32,
     name: String,
+    version: i32,
     board_type: &str,
-) -> Result<bool, Error> {
+) -> Result<UpdateResult<BoardVersion>, Error> {
     let url = format!("{}{}/update/{}", get_backend(), board_type, id);
-    let board = BoardUpdate { name };
-    send_request(url, board, token).await?.json().await
+    let board = BoardUpdate { name, version };
+    updated(send_request(url, board, token).await?).await
 }
 
 pub async fn delete_list(token: &str, id: i32) -> Result<bool, Error> {
@@ -113,35 +146,151 @@ pub async fn create_task(token: &str, task: Task) -> Result<bool, Error> {
     send_request(url, task, token).await?.json().await
 }
 
-pub async fn update_task(token: &str, task: Task) -> Result<bool, Error> {
+/// Conflict with the current task when it was changed since `task.version`
+pub async fn update_task(token: &str, task: Task) -> Result<UpdateResult<Task>, Error> {
     let url = format!("{}{}", get_backend(), "task/update");
-    send_request(url, task, token).await?.json().await
+    updated(send_request(url, task, token).await?).await
 }
 
-pub async fn get_lists(board_id: i32, board_type: String, token: &str) -> Result<Vec<List>, Error> {
-    let url = format!("{}{}/{}/{}", get_backend(), "list", board_type, board_id);
-    get(url, token).await?.json().await
+/// Changes only the fields sent, null clears a field. Invalid fields are errors, `force`
+/// marks the task done even though its blockers aren't.
+pub async fn patch_task(
+    token: &str,
+    id: i32,
+    fields: serde_json::Value,
+    force: bool,
+) -> Result<UpdateResult<Task>, Error> {
+    let url = format!("{}task/{}?force={}", get_backend(), id, force);
+    let response = Request::patch(url.as_str())
+        .header("Authorization", token)
+        .json(&fields)?
+        .send()
+        .await?;
+    match response.status() {
+        400 => {
+            let error = response.json::<FieldError>().await?;
+            Err(Error::GlooError(format!(
+                "{}: {}",
+                error.field, error.message
+            )))
+        }
+        409 => Ok(UpdateResult::Conflict(response.json().await?)),
+        _ => {
+            response.json::<Task>().await?;
+            Ok(UpdateResult::Updated)
+        }
+    }
 }
 
-pub async fn get_logs(task_id: i32, token: &str) -> Result<Vec<Log>, Error> {
-    let url = format!("{}logs/get/{}", get_backend(), task_id);
+/// Board with its lists, tasks and milestones. The backend sends an ETag and asks
+/// for revalidation, so the browser turns repeated loads into 304 responses.
+pub async fn get_board_snapshot(
+    token: &str,
+    board_type: &str,
+    board_id: i32,
+) -> Result<Snapshot, Error> {
+    let url = format!(
+        "{}boards/{}/{}/snapshot",
+        get_backend(),
+        board_type,
+        board_id
+    );
     get(url, token).await?.json().await
 }
 
+/// Short-lived ticket for the urls which can't send the token in a header
+pub async fn get_ticket(token: &str) -> Result<String, Error> {
+    let url = format!("{}ticket", get_backend());
+    let response: LoginResponse = get(url, token).await?.json().await?;
+    Ok(response.token)
+}
+
+/// Url of the board's server-sent events, resumed after `last_event_id` when it's known
+pub fn board_events_url(
+    ticket: &str,
+    board_type: &str,
+    board_id: i32,
+    last_event_id: Option<u64>,
+) -> String {
+    let resume = last_event_id
+        .map(|id| format!("&last_event_id={}", id))
+        .unwrap_or_default();
+    format!(
+        "{}boards/{}/{}/events?ticket={}{}",
+        get_backend(),
+        board_type,
+        board_id,
+        encode_uri_component(ticket),
+        resume
+    )
+}
+
+/// Newest logs first
+pub async fn get_logs(task_id: i32, token: &str, offset: usize) -> Result<(Vec<Log>, i64), Error> {
+    let url = format!(
+        "{}logs/get/{}?offset={}&limit={}",
+        get_backend(),
+        task_id,
+        offset,
+        PAGE_SIZE
+    );
+    get_page(url, token).await
+}
+
+/// `sort` is one of deadline, points, name, created and updated, prefixed with `-` for descending
 pub async fn get_tasks(
     token: &str,
     list_id: i32,
-    filter: Option<TaskFilter>,
+    offset: usize,
+    sort: &str,
+) -> Result<(Vec<Task>, i64), Error> {
+    let url = format!(
+        "{}task/get/{}?offset={}&limit={}&sort={}",
+        get_backend(),
+        list_id,
+        offset,
+        PAGE_SIZE,
+        sort
+    );
+    get_page(url, token).await
+}
+
+/// Tasks of every list of the board matching the filter
+pub async fn filter_board_tasks(
+    token: &str,
+    board_type: &str,
+    board_id: i32,
+    filter: TaskFilter,
 ) -> Result<Vec<Task>, Error> {
-    let url = format!("{}{}{}", get_backend(), "task/get/", list_id);
-    if filter.is_none() {
-        get(url, token).await?.json().await
-    } else {
-        send_request(url, filter.unwrap(), token)
-            .await?
-            .json()
-            .await
+    let url = format!("{}task/filter/{}/{}", get_backend(), board_type, board_id);
+    let response = send_request(url, filter, token).await?;
+    if response.status() == 400 {
+        let error = response.json::<ParseError>().await?;
+        return Err(Error::GlooError(format!(
+            "{} at position {}",
+            error.message, error.position
+        )));
     }
+    response.json().await
+}
+
+pub async fn get_saved_filters(
+    token: &str,
+    board_type: &str,
+    board_id: i32,
+) -> Result<Vec<SavedFilter>, Error> {
+    let url = format!("{}filter/get/{}/{}", get_backend(), board_type, board_id);
+    get(url, token).await?.json().await
+}
+
+pub async fn create_saved_filter(token: &str, filter: SavedFilterData) -> Result<bool, Error> {
+    let url = format!("{}filter/create", get_backend());
+    send_request(url, filter, token).await?.json().await
+}
+
+pub async fn delete_saved_filter(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}filter/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
 }
 
 pub async fn get_task(token: &str, id: i32) -> Result<Task, Error> {
@@ -164,13 +313,92 @@ pub async fn delete_task(token: &str, id: i32) -> Result<bool, Error> {
     get(url, token).await?.json().await
 }
 
-pub async fn get_timers(token: &str) -> Result<Vec<Timer>, Error> {
-    let url = format!("{}timers/get", get_backend());
+/// Uploads the file chosen in the input to the task, returns the status of the response
+/// or 0 when nothing was sent
+pub async fn upload_attachment(token: &str, task_id: i32, input: &str) -> u16 {
+    let url = format!("{}attachment/create/{}", get_backend(), task_id);
+    let status = JsFuture::from(upload_file(&url, token, input)).await;
+    status
+        .ok()
+        .and_then(|status| status.as_f64())
+        .unwrap_or(0.0) as u16
+}
+
+/// Url of the attachment's file, with a ticket for `<img>` and links
+pub fn attachment_url(ticket: &str, id: i32) -> String {
+    format!(
+        "{}attachment/{}?ticket={}",
+        get_backend(),
+        id,
+        encode_uri_component(ticket)
+    )
+}
+
+pub async fn delete_attachment(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}attachment/delete/{}", get_backend(), id);
     get(url, token).await?.json().await
 }
 
-pub async fn update_timer(token: &str, id: i32) -> Result<bool, Error> {
-    let url = format!("{}timer/update/{}", get_backend(), id);
+/// Comments of the task, with the replies nested
+pub async fn get_comments(token: &str, task_id: i32) -> Result<Vec<Comment>, Error> {
+    let url = format!("{}comments/get/{}", get_backend(), task_id);
+    get(url, token).await?.json().await
+}
+
+pub async fn create_comment(token: &str, task_id: i32, data: CommentData) -> Result<bool, Error> {
+    let url = format!("{}comment/create/{}", get_backend(), task_id);
+    send_request(url, data, token).await?.json().await
+}
+
+pub async fn update_comment(token: &str, id: i32, body: String) -> Result<bool, Error> {
+    let url = format!("{}comment/update/{}", get_backend(), id);
+    let data = CommentData { body, parent: None };
+    send_request(url, data, token).await?.json().await
+}
+
+pub async fn delete_comment(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}comment/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+/// Newest comments on the board's tasks
+pub async fn get_board_comments(
+    token: &str,
+    board_type: &str,
+    board_id: i32,
+    offset: usize,
+) -> Result<(Vec<CommentActivity>, i64), Error> {
+    let url = format!(
+        "{}boards/{}/{}/comments?offset={}&limit={}",
+        get_backend(),
+        board_type,
+        board_id,
+        offset,
+        PAGE_SIZE
+    );
+    get_page(url, token).await
+}
+
+pub async fn get_timers(token: &str, category: Option<i32>) -> Result<Vec<Timer>, Error> {
+    let url = match category {
+        Some(category) => format!("{}timers/get?category={}", get_backend(), category),
+        None => format!("{}timers/get", get_backend()),
+    };
+    get(url, token).await?.json().await
+}
+
+pub async fn start_timer(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}timer/start/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn pause_timer(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}timer/pause/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn stop_timer(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}timer/stop/{}", get_backend(), id);
     get(url, token).await?.json().await
 }
 
@@ -179,20 +407,155 @@ pub async fn delete_timer(token: &str, id: i32) -> Result<bool, Error> {
     get(url, token).await?.json().await
 }
 
-pub async fn create_timer(token: &str, name: &str) -> Result<bool, Error> {
+pub async fn create_timer(
+    token: &str,
+    name: &str,
+    pomodoro: Option<PomodoroConfig>,
+) -> Result<bool, Error> {
     let timer = TimerData {
         name: name.to_owned(),
+        pomodoro,
     };
     let url = format!("{}{}", get_backend(), "timer/create");
     send_request(url, timer, token).await?.json().await
 }
 
-pub async fn get_milestones(
+pub async fn get_timer_settings(token: &str) -> Result<TimerSettings, Error> {
+    let url = format!("{}timer/settings", get_backend());
+    get(url, token).await?.json().await
+}
+
+pub async fn update_timer_settings(token: &str, idle_limit: u64) -> Result<bool, Error> {
+    let url = format!("{}timer/settings", get_backend());
+    send_request(url, TimerSettings { idle_limit }, token)
+        .await?
+        .json()
+        .await
+}
+
+pub async fn review_timer(token: &str, id: i32, session: u64) -> Result<bool, Error> {
+    let url = format!("{}timer/review/{}", get_backend(), id);
+    send_request(url, TimerReview { session }, token)
+        .await?
+        .json()
+        .await
+}
+
+pub async fn get_categories(token: &str) -> Result<Vec<TimerCategory>, Error> {
+    let url = format!("{}timer/categories", get_backend());
+    get(url, token).await?.json().await
+}
+
+pub async fn create_category(token: &str, category: CategoryData) -> Result<bool, Error> {
+    let url = format!("{}timer/category/create", get_backend());
+    send_request(url, category, token).await?.json().await
+}
+
+pub async fn delete_category(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}timer/category/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn set_timer_category(
+    token: &str,
     id: i32,
-    board_type: String,
+    category: Option<i32>,
+) -> Result<bool, Error> {
+    let url = format!("{}timer/category/{}", get_backend(), id);
+    send_request(url, CategoryAssignment { category }, token)
+        .await?
+        .json()
+        .await
+}
+
+pub async fn get_timer_sessions(token: &str, id: i32) -> Result<Vec<TimerSession>, Error> {
+    let url = format!("{}timer/sessions/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn set_session_category(
     token: &str,
-) -> Result<Vec<Milestone>, Error> {
-    let url = format!("{}milestone/get/{}/{}", get_backend(), id, board_type);
+    id: i32,
+    category: Option<i32>,
+) -> Result<bool, Error> {
+    let url = format!("{}timer/session/category/{}", get_backend(), id);
+    send_request(url, CategoryAssignment { category }, token)
+        .await?
+        .json()
+        .await
+}
+
+pub async fn get_timers_report(
+    token: &str,
+    from: Option<u64>,
+) -> Result<Vec<CategoryReport>, Error> {
+    let url = match from {
+        Some(from) => format!("{}timers/report?from={}", get_backend(), from),
+        None => format!("{}timers/report", get_backend()),
+    };
+    get(url, token).await?.json().await
+}
+
+pub async fn create_goal(token: &str, goal: GoalData) -> Result<bool, Error> {
+    let url = format!("{}timer/goal/create", get_backend());
+    send_request(url, goal, token).await?.json().await
+}
+
+pub async fn delete_goal(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}timer/goal/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn get_goals_progress(token: &str) -> Result<Vec<GoalProgress>, Error> {
+    let url = format!("{}timer/goals/progress", get_backend());
+    get(url, token).await?.json().await
+}
+
+pub async fn set_timer_billing(token: &str, id: i32, billing: TimerBilling) -> Result<bool, Error> {
+    let url = format!("{}timer/billing/{}", get_backend(), id);
+    send_request(url, billing, token).await?.json().await
+}
+
+pub async fn set_session_billable(token: &str, id: i32, billable: bool) -> Result<bool, Error> {
+    let url = format!("{}timer/session/billable/{}", get_backend(), id);
+    send_request(url, SessionBilling { billable }, token)
+        .await?
+        .json()
+        .await
+}
+
+pub async fn get_rates(token: &str) -> Result<Vec<BillingRate>, Error> {
+    let url = format!("{}billing/rates", get_backend());
+    get(url, token).await?.json().await
+}
+
+pub async fn set_rate(token: &str, rate: RateData) -> Result<bool, Error> {
+    let url = format!("{}billing/rate", get_backend());
+    send_request(url, rate, token).await?.json().await
+}
+
+pub async fn delete_rate(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}billing/rate/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+/// Query string shared by the billing summary and the exports
+pub fn billing_query(from: Option<u64>, to: Option<u64>, client: &str) -> String {
+    let mut params = Vec::new();
+    if let Some(from) = from {
+        params.push(format!("from={}", from));
+    }
+    if let Some(to) = to {
+        params.push(format!("to={}", to));
+    }
+    if !client.is_empty() {
+        params.push(format!("client={}", encode_uri_component(client)));
+    }
+    params.join("&")
+}
+
+pub async fn get_billing_summary(token: &str, query: &str) -> Result<BillingSummary, Error> {
+    let url = format!("{}billing/summary?{}", get_backend(), query);
     get(url, token).await?.json().await
 }
 
@@ -200,3 +563,99 @@ pub async fn create_milestone(token: &str, milestone: MilestoneCreate) -> Result
     let url = format!("{}milestone/create", get_backend());
     send_request(url, milestone, token).await?.json().await
 }
+
+pub async fn update_milestone(
+    token: &str,
+    id: i32,
+    milestone: MilestoneUpdate,
+) -> Result<bool, Error> {
+    let url = format!("{}milestone/update/{}", get_backend(), id);
+    send_request(url, milestone, token).await?.json().await
+}
+
+pub async fn get_milestone_tasks(token: &str, id: i32) -> Result<MilestoneTasks, Error> {
+    let url = format!("{}milestone/tasks/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn set_milestone_tasks(
+    token: &str,
+    id: i32,
+    update: MilestoneTasksUpdate,
+) -> Result<bool, Error> {
+    let url = format!("{}milestone/tasks/{}", get_backend(), id);
+    send_request(url, update, token).await?.json().await
+}
+
+pub async fn get_milestone_burndown(token: &str, id: i32) -> Result<Burndown, Error> {
+    let url = format!("{}milestone/burndown/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn set_milestone_predecessors(
+    token: &str,
+    id: i32,
+    predecessors: MilestonePredecessors,
+) -> Result<bool, Error> {
+    let url = format!("{}milestone/predecessors/{}", get_backend(), id);
+    send_request(url, predecessors, token).await?.json().await
+}
+
+/// False when the blockers would depend on the task in a cycle
+pub async fn set_task_blockers(
+    token: &str,
+    id: i32,
+    blockers: TaskBlockers,
+) -> Result<bool, Error> {
+    let url = format!("{}task/blockers/{}", get_backend(), id);
+    let response = send_request(url, blockers, token).await?;
+    match response.status() {
+        400 => Ok(false),
+        _ => response.json().await,
+    }
+}
+
+/// Makes the task repeat, invalid rules and lists are errors
+pub async fn set_recurrence(token: &str, id: i32, data: RecurrenceData) -> Result<bool, Error> {
+    let url = format!("{}recurrence/set/{}", get_backend(), id);
+    let response = send_request(url, data, token).await?;
+    match response.status() {
+        400 => {
+            let error = response.json::<FieldError>().await?;
+            Err(Error::GlooError(format!(
+                "{}: {}",
+                error.field, error.message
+            )))
+        }
+        _ => response.json().await,
+    }
+}
+
+pub async fn delete_recurrence(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}recurrence/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn get_board_roadmap(
+    token: &str,
+    board_type: &str,
+    id: i32,
+) -> Result<Vec<RoadmapItem>, Error> {
+    let url = format!("{}roadmap/board/{}/{}", get_backend(), board_type, id);
+    get(url, token).await?.json().await
+}
+
+pub async fn get_team_roadmap(token: &str, id: i32) -> Result<Vec<RoadmapItem>, Error> {
+    let url = format!("{}roadmap/team/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn delete_milestone(token: &str, id: i32) -> Result<bool, Error> {
+    let url = format!("{}milestone/delete/{}", get_backend(), id);
+    get(url, token).await?.json().await
+}
+
+pub async fn search_tasks(token: &str, query: &str) -> Result<Vec<SearchResult>, Error> {
+    let url = format!("{}search?q={}", get_backend(), encode_uri_component(query));
+    get(url, token).await?.json().await
+}
@@ -0,0 +1,120 @@
+use crate::api::{get_timers, pause_timer, start_timer, stop_timer};
+use crate::types::Timer;
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use gloo_timers::callback::Interval;
+use yew::{html, Component, Context, Html, MouseEvent};
+
+/// Running and paused timers, shown in the navbar on every page
+pub struct ActiveTimers {
+    token: Option<String>,
+    timers: Vec<Timer>,
+    _clock_handle: Interval,
+    _sync_handle: Interval,
+}
+
+pub enum Msg {
+    Fetch,
+    Res(Result<Vec<Timer>, Error>),
+    Tick,
+    Start(i32),
+    Pause(i32),
+    Stop(i32),
+}
+
+fn format_elapsed(seconds: u64) -> String {
+    format!(
+        "{}:{:02}:{:02}",
+        seconds / 3600,
+        seconds % 3600 / 60,
+        seconds % 60
+    )
+}
+
+impl Component for ActiveTimers {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let _clock_handle = {
+            let link = ctx.link().clone();
+            Interval::new(1000, move || link.send_message(Msg::Tick))
+        };
+        // Other tabs change timers too, the backend is the source of truth
+        let _sync_handle = {
+            let link = ctx.link().clone();
+            Interval::new(5000, move || link.send_message(Msg::Fetch))
+        };
+        ctx.link().send_message(Msg::Fetch);
+        Self {
+            token: LocalStorage::get("Token").ok(),
+            timers: Vec::new(),
+            _clock_handle,
+            _sync_handle,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let token = match self.token.clone() {
+            Some(token) => token,
+            None => return false,
+        };
+        match msg {
+            Msg::Fetch => {
+                ctx.link().send_future(async move {
+                    let res = get_timers(token.as_str(), None).await;
+                    Msg::Res(res)
+                });
+                false
+            }
+            Msg::Res(Ok(timers)) => {
+                self.timers = timers
+                    .into_iter()
+                    .filter(|timer| timer.status == "active" || timer.status == "paused")
+                    .collect();
+                true
+            }
+            Msg::Res(Err(_)) => false,
+            Msg::Tick => self.timers.iter().any(|timer| timer.status == "active"),
+            Msg::Start(id) => {
+                ctx.link().send_future(async move {
+                    let _ = start_timer(token.as_str(), id).await;
+                    Msg::Fetch
+                });
+                false
+            }
+            Msg::Pause(id) => {
+                ctx.link().send_future(async move {
+                    let _ = pause_timer(token.as_str(), id).await;
+                    Msg::Fetch
+                });
+                false
+            }
+            Msg::Stop(id) => {
+                ctx.link().send_future(async move {
+                    let _ = stop_timer(token.as_str(), id).await;
+                    Msg::Fetch
+                });
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let timers = self.timers.iter().map(|timer| {
+            let id = timer.id;
+            html! {
+                <span class="navbar-text" style="padding-right: 10px;">
+                    <a href="/timers">{timer.name.clone()}</a>{" "}{format_elapsed(timer.get_time())}
+                    if timer.status == "active" {
+                        <button class="btn btn-sm btn-warning" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Pause(id)})}>{"Pause"}</button>
+                    } else {
+                        <button class="btn btn-sm btn-success" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Start(id)})}>{"Resume"}</button>
+                    }
+                    <button class="btn btn-sm btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Stop(id)})}>{"Stop"}</button>
+                </span>
+            }
+        });
+        html! { <>{for timers}</> }
+    }
+}
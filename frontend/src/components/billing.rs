@@ -0,0 +1,303 @@
+use super::navbar::Navbar;
+use crate::api::{
+    billing_query, delete_rate, get_billing_summary, get_private_boards, get_rates,
+    get_team_boards, get_user_teams, set_rate,
+};
+use crate::types::{BillingRate, BillingSummary, PrivateBoard, RateData, Team, TeamBoard};
+use crate::utils::{download, err, format_date, get_backend, get_timestamp, get_value};
+use crate::Route;
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use yew::{html, Component, Context, Html, MouseEvent};
+use yew_router::prelude::*;
+
+pub struct Billing {
+    token: Option<String>,
+    rates: Option<Vec<BillingRate>>,
+    teams: Vec<Team>,
+    private_boards: Vec<PrivateBoard>,
+    team_boards: Vec<TeamBoard>,
+    summary: Option<BillingSummary>,
+    query: String,
+}
+
+pub enum Msg {
+    Fetch,
+    Res(
+        Result<Vec<BillingRate>, Error>,
+        Result<Vec<Team>, Error>,
+        Result<Vec<PrivateBoard>, Error>,
+        Result<Vec<TeamBoard>, Error>,
+    ),
+    SaveRate,
+    DeleteRate(i32),
+    Show,
+    Summary(Result<BillingSummary, Error>),
+    Export(&'static str),
+}
+
+fn format_amount(cents: u64) -> String {
+    format!("{}.{:02}", cents / 100, cents % 100)
+}
+
+fn format_hours(seconds: u64) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+fn timestamp(input: &str) -> Option<u64> {
+    let value = get_timestamp(input);
+    if value < 0.0 {
+        None
+    } else {
+        Some(value as u64)
+    }
+}
+
+impl Billing {
+    fn scope_name(&self, rate: &BillingRate) -> String {
+        let name = match rate.scope.as_str() {
+            "team" => self
+                .teams
+                .iter()
+                .find(|team| team.id == rate.scope_id)
+                .map(|team| team.name.clone()),
+            "private_board" => self
+                .private_boards
+                .iter()
+                .find(|board| board.id == Some(rate.scope_id))
+                .map(|board| board.name.clone()),
+            "team_board" => self
+                .team_boards
+                .iter()
+                .find(|board| board.id == Some(rate.scope_id))
+                .map(|board| board.name.clone()),
+            _ => Some("Me".to_owned()),
+        };
+        format!(
+            "{}: {}",
+            rate.scope,
+            name.unwrap_or_else(|| rate.scope_id.to_string())
+        )
+    }
+}
+
+impl Component for Billing {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Msg::Fetch);
+        Self {
+            token: LocalStorage::get("Token").ok(),
+            rates: None,
+            teams: Vec::new(),
+            private_boards: Vec::new(),
+            team_boards: Vec::new(),
+            summary: None,
+            query: String::new(),
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let token = match self.token.clone() {
+            Some(token) => token,
+            None => return false,
+        };
+        match msg {
+            Msg::Fetch => {
+                let query = self.query.clone();
+                ctx.link().send_future(async move {
+                    let rates = get_rates(token.as_str()).await;
+                    let teams = get_user_teams(token.as_str()).await;
+                    let private_boards = get_private_boards(token.as_str()).await;
+                    let team_boards = get_team_boards(token.as_str()).await;
+                    Msg::Res(rates, teams, private_boards, team_boards)
+                });
+                let token = self.token.clone().unwrap();
+                ctx.link().send_future(async move {
+                    let res = get_billing_summary(token.as_str(), query.as_str()).await;
+                    Msg::Summary(res)
+                });
+                false
+            }
+            Msg::Res(Ok(rates), Ok(teams), Ok(private_boards), Ok(team_boards)) => {
+                self.rates = Some(rates);
+                self.teams = teams;
+                self.private_boards = private_boards;
+                self.team_boards = team_boards;
+                true
+            }
+            Msg::SaveRate => {
+                // Options of the target select are "<scope>:<id>"
+                let target = get_value("rateTarget");
+                let (scope, scope_id) = target.split_once(':').unwrap_or(("user", "0"));
+                let rate = get_value("rate").parse::<f64>().unwrap_or(-1.0);
+                let currency = get_value("currency");
+                if rate < 0.0 || currency.len() != 3 {
+                    err("Please enter the rate and a 3 letter currency code");
+                    return false;
+                }
+                let rate = RateData {
+                    scope: scope.to_owned(),
+                    scope_id: scope_id.parse().unwrap_or(0),
+                    rate: (rate * 100.0).round() as u64,
+                    currency,
+                    rounding: get_value("rounding").parse().unwrap_or(0),
+                    rounding_mode: get_value("roundingMode"),
+                };
+                ctx.link().send_future(async move {
+                    let _ = set_rate(token.as_str(), rate).await;
+                    Msg::Fetch
+                });
+                false
+            }
+            Msg::DeleteRate(id) => {
+                ctx.link().send_future(async move {
+                    let _ = delete_rate(token.as_str(), id).await;
+                    Msg::Fetch
+                });
+                false
+            }
+            Msg::Show => {
+                // The end date is inclusive
+                let to = timestamp("billingTo").map(|to| to + 24 * 3600);
+                self.query = billing_query(timestamp("billingFrom"), to, &get_value("client"));
+                ctx.link().send_message(Msg::Fetch);
+                false
+            }
+            Msg::Summary(Ok(summary)) => {
+                self.summary = Some(summary);
+                true
+            }
+            Msg::Export(format) => {
+                let url = format!("{}billing/export/{}?{}", get_backend(), format, self.query);
+                download(
+                    url.as_str(),
+                    token.as_str(),
+                    format!("invoice.{}", format).as_str(),
+                );
+                false
+            }
+            _ => {
+                err("Error occured");
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.token.is_none() {
+            return html! { <Redirect<Route> to={Route::Login}/> };
+        }
+        let rates = self.rates.iter().flatten().map(|rate| {
+            let id = rate.id;
+            html! {
+                <tr>
+                    <td>{self.scope_name(rate)}</td>
+                    <td>{format_amount(rate.rate)}{" "}{rate.currency.clone()}{"/h"}</td>
+                    <td>{rate.rounding}{" min, "}{rate.rounding_mode.clone()}</td>
+                    <td><button class="btn btn-link" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::DeleteRate(id)})}>{"Delete"}</button></td>
+                </tr>
+            }
+        });
+        let targets = self
+            .teams
+            .iter()
+            .map(|team| (format!("team:{}", team.id), format!("Team {}", team.name)))
+            .chain(self.private_boards.iter().map(|board| {
+                (
+                    format!("private_board:{}", board.id.unwrap()),
+                    format!("Board {}", board.name),
+                )
+            }))
+            .chain(self.team_boards.iter().map(|board| {
+                (
+                    format!("team_board:{}", board.id.unwrap()),
+                    format!("Board {} ({})", board.name, board.team_name),
+                )
+            }))
+            .map(|(value, name)| html! { <option value={value}>{name}</option> });
+        let summary = self.summary.iter().map(|summary| {
+            let clients = summary.clients.iter().map(|client| html! {
+                <tr>
+                    <td>{client.client.clone()}</td>
+                    <td>{format_hours(client.duration)}</td>
+                    <td>{format_hours(client.billed)}</td>
+                    <td>{format_amount(client.amount)}{" "}{client.currency.clone()}</td>
+                </tr>
+            });
+            let lines = summary.lines.iter().map(|line| html! {
+                <tr>
+                    <td>{format_date(line.start as f64)}</td>
+                    <td>{line.timer.clone()}</td>
+                    <td>{line.client.clone()}</td>
+                    <td>{format_hours(line.billed)}</td>
+                    <td>{format_amount(line.amount)}{" "}{line.currency.clone()}</td>
+                </tr>
+            });
+            html! {
+                <div>
+                    <h5>{"Clients"}</h5>
+                    <table class="table">
+                        <tr><th>{"Client"}</th><th>{"Hours"}</th><th>{"Billed hours"}</th><th>{"Amount"}</th></tr>
+                        {for clients}
+                    </table>
+                    <h5>{"Sessions"}</h5>
+                    <table class="table">
+                        <tr><th>{"Date"}</th><th>{"Timer"}</th><th>{"Client"}</th><th>{"Billed hours"}</th><th>{"Amount"}</th></tr>
+                        {for lines}
+                    </table>
+                </div>
+            }
+        });
+        html! {
+            <div>
+                <Navbar />
+                <h5>{"Hourly rates"}</h5>
+                <table class="table">
+                    {for rates}
+                </table>
+                <form>
+                    <div class="form-group">
+                        <label for="rateTarget">{"Applies to:"}</label>
+                        <select id="rateTarget">
+                            <option value="user:0">{"Me"}</option>
+                            {for targets}
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="rate">{"Rate per hour"}</label>
+                        <input type="number" class="form-control" id="rate" min="0" step="0.01"/>
+                    </div>
+                    <div class="form-group">
+                        <label for="currency">{"Currency"}</label>
+                        <input type="text" class="form-control" id="currency" value="PLN" maxlength="3"/>
+                    </div>
+                    <div class="form-group">
+                        <label for="rounding">{"Round every session to (minutes, 0 for exact time)"}</label>
+                        <input type="number" class="form-control" id="rounding" min="0" max="1440" value="0"/>
+                        <select id="roundingMode">
+                            <option value="up">{"Up"}</option>
+                            <option value="nearest">{"Nearest"}</option>
+                            <option value="down">{"Down"}</option>
+                        </select>
+                    </div>
+                    <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::SaveRate})}>{"Save rate"}</button>
+                </form>
+                <h5>{"Billable time"}</h5>
+                <div class="form-group">
+                    <label for="billingFrom">{"From"}</label>
+                    <input type="date" class="form-control" id="billingFrom"/>
+                    <label for="billingTo">{"To"}</label>
+                    <input type="date" class="form-control" id="billingTo"/>
+                    <label for="client">{"Client (empty for all)"}</label>
+                    <input type="text" class="form-control" id="client"/>
+                    <button class="btn btn-secondary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Show})}>{"Show"}</button>
+                    <button class="btn btn-secondary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Export("csv")})}>{"Export CSV"}</button>
+                    <button class="btn btn-secondary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Export("pdf")})}>{"Export PDF"}</button>
+                </div>
+                {for summary}
+            </div>
+        }
+    }
+}
@@ -1,18 +1,25 @@
+use super::comments::{BoardActivity, TaskComments};
 use super::milestone::MilestoneList;
 use gloo_net::Error;
 use gloo_storage::{LocalStorage, Storage};
-use yew::{function_component, html, Component, Context, Html, MouseEvent, Properties};
+use gloo_timers::callback::{Interval, Timeout};
+use yew::{function_component, html, Component, Context, Event, Html, MouseEvent, Properties};
 
 use super::navbar::Navbar;
 use crate::{
     api::{
-        create_list, create_task, delete_list, delete_task, get_lists, get_logs, get_milestones,
-        get_task, get_tasks, update_task,
+        attachment_url, board_events_url, create_list, create_saved_filter, create_task,
+        delete_attachment, delete_list, delete_recurrence, delete_saved_filter, delete_task,
+        filter_board_tasks, get_board_snapshot, get_logs, get_saved_filters, get_task, get_tasks,
+        get_ticket, patch_task, set_recurrence, set_task_blockers, update_task, upload_attachment,
+    },
+    types::{
+        BoardEvent, IdProp, List, Log, Milestone, RecurrenceData, Rollup, SavedFilter,
+        SavedFilterData, Snapshot, Task, TaskBlockers, TaskFilter, UpdateResult,
     },
-    types::{IdProp, List, Log, Milestone, Task, TaskFilter},
     utils::{
-        err, get_parameter, get_value, hide_modal, is_checked, map_result, map_token, open_modal,
-        reload, set_checked, set_value,
+        err, get_parameter, get_selected, get_value, hide_modal, is_checked, map_result, map_token,
+        open_modal, replace_url, scrolled_to_bottom, set_checked, set_value, EventSubscription,
     },
 };
 
@@ -55,43 +62,74 @@ fn sub_tasks(SubTasksProps { subtasks }: &SubTasksProps) -> Html {
 struct Logs {
     token: Option<String>,
     logs: Option<Vec<Log>>,
+    total: i64,
+    loading: bool,
 }
 
 pub enum MsgLogs {
-    Update(Result<Vec<Log>, Error>),
+    Update(Result<(Vec<Log>, i64), Error>),
+    LoadMore,
 }
 
 impl Component for Logs {
     type Message = MsgLogs;
     type Properties = IdProp;
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
+        let token = map_token(LocalStorage::get("Token"));
+        if let Some(token) = token.clone() {
+            let id = ctx.props().id;
+            ctx.link().send_future(async move {
+                let logs = get_logs(id, &token, 0).await;
+                Self::Message::Update(logs)
+            });
+        }
         Self {
             logs: None,
-            token: map_token(LocalStorage::get("Token")),
+            token,
+            total: 0,
+            loading: false,
         }
     }
 
-    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
-        if let Self::Message::Update(Ok(logs)) = msg {
-            self.logs = Some(logs);
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::Update(Ok((mut logs, total))) => {
+                self.logs.get_or_insert_with(Vec::new).append(&mut logs);
+                self.total = total;
+                self.loading = false;
+                true
+            }
+            Self::Message::Update(Err(_)) => {
+                self.loading = false;
+                false
+            }
+            Self::Message::LoadMore => {
+                let loaded = self.logs.as_ref().map_or(0, |logs| logs.len());
+                if self.loading || loaded as i64 >= self.total {
+                    return false;
+                }
+                self.loading = true;
+                let token = self.token.clone().unwrap();
+                let id = ctx.props().id;
+                ctx.link().send_future(async move {
+                    let logs = get_logs(id, &token, loaded).await;
+                    Self::Message::Update(logs)
+                });
+                false
+            }
         }
-        true
     }
 
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        if self.logs.is_none() {
-            let token = self.token.clone().unwrap();
-            let id = ctx.props().id;
-            ctx.link().send_future(async move {
-                let logs = get_logs(id, &token).await;
-                Self::Message::Update(logs)
-            });
-            return html! {};
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            open_modal(&format!("logs{}", ctx.props().id));
         }
+    }
 
-        let logs = self.logs.clone();
-        let logs = logs.unwrap().into_iter().map(|log| html! {
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let logs = self.logs.clone().unwrap_or_default();
+        let logs = logs.into_iter().map(|log| html! {
             <div class="card" style="width: 18rem;">
                 <div class="card-body">
                     <h5 class="card-title">{"Name: "}{log.name}{" Action: "}{log.action}{" When:"}{log.timestamp}</h5>
@@ -102,16 +140,21 @@ impl Component for Logs {
                     <h6 class="card-subtitle mb-2 text-muted">{"Points:"}{if log.points >= 0 {log.points.to_string()} else {"".to_string()}}</h6>
                     <h6 class="card-subtitle mb-2 text-muted">{"Tags:"}{log.tags}</h6>
                     <SubTasks subtasks={log.subtasks.clone()}/>
+                    if let Some(changed) = changed_fields(&log) {
+                        <h6 class="card-subtitle mb-2 text-muted">{"Changed:"}{changed}</h6>
+                    }
                 </div>
             </div>
         });
 
         let id_prop = ctx.props().id;
         let id = format!("logs{}", id_prop);
+        let content = format!("logsContent{}", id_prop);
+        let scrolled = content.clone();
         html! {
             <div id={id.clone()} class="modal">
 
-                <div class="modal-content">
+                <div class="modal-content" id={content} style="max-height: 80vh; overflow-y: auto;" onscroll={ctx.link().batch_callback(move |_: Event| scrolled_to_bottom(&scrolled).then_some(MsgLogs::LoadMore))}>
                     <span class="close btn btn-danger" onclick={move |_: MouseEvent| {hide_modal(id.as_str());}}>{"Hide"}</span>
                     {for logs}
                 </div>
@@ -120,19 +163,50 @@ impl Component for Logs {
     }
 }
 
+/// Names of the fields a partial update changed, None for the other logs
+fn changed_fields(log: &Log) -> Option<String> {
+    let changes = serde_json::from_str::<serde_json::Value>(log.changes.as_ref()?).ok()?;
+    let fields = changes.as_object()?.keys().cloned().collect::<Vec<_>>();
+    Some(fields.join(", "))
+}
+
+/// Order of the board's own tasks, other orders are loaded page by page
+const DEFAULT_SORT: &str = "created";
+/// Delay before subscribing again when the server closed the board's events
+const RECONNECT_MS: u32 = 5000;
+/// Tickets expire after 15 minutes, the attachments' urls need a valid one
+const TICKET_REFRESH_MS: u32 = 10 * 60 * 1000;
+
 struct ListDetails {
-    tasks: Option<Vec<Task>>,
+    tasks: Option<Vec<Task>>, // Sorted by `sort`, when it isn't the default
     token: Option<String>,
     id: Option<i32>,
+    total: i64,
+    loading: bool,
+    sort: String,
+    logs: Option<i32>,     // Task whose logs are shown
+    comments: Option<i32>, // Task whose comments are shown
+    expanded: Vec<i32>,    // Tasks whose children are shown
 }
 
 pub enum MsgList {
-    Update(Result<Vec<Task>, Error>),
+    Update(Result<(Vec<Task>, i64), Error>),
+    LoadMore,
+    Sort,
+    ShowLogs(i32),
+    ShowComments(i32),
+    SetDone(i32, i32, bool), // Forced while the task is blocked
+    SetBlockers(i32),
+    Repeat(i32),
+    StopRepeating(i32),
+    Expand(i32),
+    Upload(i32),
+    Uploaded(u16),
+    DeleteAttachment(i32),
     Delete(Option<i32>),
     Return,
     UpdateTask(Option<i32>),
     DeleteList,
-    Pass,
 }
 
 impl Component for ListDetails {
@@ -144,20 +218,184 @@ impl Component for ListDetails {
             tasks: None,
             token: map_token(LocalStorage::get("Token")),
             id: None,
+            total: 0,
+            loading: false,
+            sort: DEFAULT_SORT.to_string(),
+            logs: None,
+            comments: None,
+            expanded: Vec::new(),
         }
     }
 
     fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
         match msg {
-            Self::Message::Update(Ok(tasks)) => {
-                self.tasks = Some(tasks);
+            Self::Message::Update(Ok((mut tasks, total))) => {
+                self.tasks.get_or_insert_with(Vec::new).append(&mut tasks);
+                self.total = total;
+                self.loading = false;
                 true
             }
+            Self::Message::Update(Err(_)) => {
+                self.loading = false;
+                false
+            }
+            Self::Message::LoadMore => {
+                let loaded = self.tasks.as_ref().map_or(0, |tasks| tasks.len());
+                // The board's tasks all come at once
+                if self.sort == DEFAULT_SORT || self.loading || loaded as i64 >= self.total {
+                    return false;
+                }
+                self.loading = true;
+                let token = self.token.clone().unwrap();
+                let (id, sort) = (ctx.props().id, self.sort.clone());
+                ctx.link().send_future(async move {
+                    let tasks = get_tasks(&token, id, loaded, &sort).await;
+                    Self::Message::Update(tasks)
+                });
+                false
+            }
+            Self::Message::Sort => {
+                self.sort = get_value(&format!("sort{}", ctx.props().id));
+                self.tasks = None;
+                true
+            }
+            Self::Message::ShowLogs(id) => {
+                if self.logs == Some(id) {
+                    open_modal(&format!("logs{}", id));
+                    return false;
+                }
+                self.logs = Some(id);
+                true
+            }
+            Self::Message::ShowComments(id) => {
+                if self.comments == Some(id) {
+                    open_modal(&format!("comments{}", id));
+                    return false;
+                }
+                self.comments = Some(id);
+                true
+            }
+            Self::Message::SetDone(id, done, force) => {
+                let token = self.token.clone().unwrap();
+                // Only the done flag is sent, the board's events show the change
+                ctx.link().send_future(async move {
+                    let fields = serde_json::json!({ "done": done });
+                    match patch_task(&token, id, fields, force).await {
+                        Ok(UpdateResult::Updated) => {}
+                        Ok(UpdateResult::Conflict(_)) => {
+                            err("The task was changed meanwhile, please try again");
+                        }
+                        Err(Error::GlooError(message)) => {
+                            err(&format!("Invalid update: {}", message));
+                        }
+                        Err(_) => {
+                            err("Could not update the task");
+                        }
+                    }
+                    Self::Message::Return
+                });
+                false
+            }
+            Self::Message::SetBlockers(id) => {
+                let token = self.token.clone().unwrap();
+                let blockers = TaskBlockers {
+                    blockers: get_selected(&format!("blockers{}", id))
+                        .split(',')
+                        .filter_map(|blocker| blocker.parse().ok())
+                        .collect(),
+                };
+                ctx.link().send_future(async move {
+                    match set_task_blockers(&token, id, blockers).await {
+                        Ok(true) => {}
+                        Ok(false) => {
+                            err("Tasks can't block each other in a cycle");
+                        }
+                        Err(_) => {
+                            err("Could not save the blockers");
+                        }
+                    }
+                    Self::Message::Return
+                });
+                false
+            }
+            Self::Message::Repeat(id) => {
+                let token = self.token.clone().unwrap();
+                let list = get_value(&format!("recurrenceList{}", id)).parse::<i32>();
+                let data = RecurrenceData {
+                    rule: get_value(&format!("recurrenceRule{}", id)),
+                    list: list.unwrap_or(ctx.props().id),
+                };
+                ctx.link().send_future(async move {
+                    match set_recurrence(&token, id, data).await {
+                        Ok(_) => {}
+                        Err(Error::GlooError(message)) => {
+                            err(&format!("Invalid recurrence: {}", message));
+                        }
+                        Err(_) => {
+                            err("Could not save the recurrence");
+                        }
+                    }
+                    Self::Message::Return
+                });
+                false
+            }
+            Self::Message::StopRepeating(id) => {
+                let token = self.token.clone().unwrap();
+                ctx.link().send_future(async move {
+                    if delete_recurrence(&token, id).await.is_err() {
+                        err("Could not stop the recurrence");
+                    }
+                    Self::Message::Return
+                });
+                false
+            }
+            Self::Message::Expand(id) => {
+                match self.expanded.iter().position(|&expanded| expanded == id) {
+                    Some(i) => {
+                        self.expanded.remove(i);
+                    }
+                    None => self.expanded.push(id),
+                }
+                true
+            }
+            Self::Message::Upload(id) => {
+                let token = self.token.clone().unwrap();
+                let input = format!("attachmentFile{}", id);
+                // The board's events add the attachment to the card
+                ctx.link().send_future(async move {
+                    let status = upload_attachment(&token, id, &input).await;
+                    set_value(&input, "");
+                    Self::Message::Uploaded(status)
+                });
+                false
+            }
+            Self::Message::Uploaded(status) => {
+                match status {
+                    200 => {}
+                    0 => {
+                        err("Please choose a file");
+                    }
+                    413 => {
+                        err("The file is too large or the board's space for files is used up");
+                    }
+                    _ => {
+                        err("Could not upload the file");
+                    }
+                }
+                false
+            }
+            Self::Message::DeleteAttachment(id) => {
+                let token = self.token.clone().unwrap();
+                ctx.link().send_future(async move {
+                    let _ = delete_attachment(&token, id).await;
+                    Self::Message::Return
+                });
+                false
+            }
             Self::Message::Delete(id) => {
                 let token = self.token.clone().unwrap();
                 ctx.link().send_future(async move {
                     let _ = delete_task(&token, id.unwrap()).await;
-                    let _ = reload();
                     Self::Message::Return
                 });
                 false
@@ -167,7 +405,6 @@ impl Component for ListDetails {
                 let token = self.token.clone().unwrap();
                 ctx.link().send_future(async move {
                     let _ = delete_list(&token, id).await;
-                    let _ = reload();
                     Self::Message::Return
                 });
                 false
@@ -183,6 +420,8 @@ impl Component for ListDetails {
                     let task = task.unwrap();
 
                     set_value("idUpdate", id.unwrap().to_string().as_str());
+                    // The edited version, to find out what changed if somebody saves first
+                    set_value("baseUpdate", &serde_json::to_string(&task).unwrap());
                     set_value("nameUpdateTask", task.name.as_str());
                     if task.points >= 0 {
                         set_value("pointsUpdate", task.points.to_string().as_str());
@@ -221,52 +460,195 @@ impl Component for ListDetails {
                             task.milestone.unwrap().to_string().as_str(),
                         );
                     }
+                    let parent = task.parent.map(|parent| parent.to_string());
+                    set_value("parentUpdate", parent.as_deref().unwrap_or("None"));
                     Self::Message::Return
                 });
                 true
             }
-            Self::Message::Pass => false,
             _ => true,
         }
     }
 
-    fn view(&self, ctx: &Context<Self>) -> Html {
-        if self.tasks.is_none() {
-            let token = self.token.clone().unwrap();
-            let id = ctx.props().id;
-            let filter = ctx.props().filter.clone();
-            ctx.link().send_future(async move {
-                let tasks = get_tasks(&token, id, filter).await;
-                Self::Message::Update(tasks)
-            });
-            return html! {};
+    fn changed(&mut self, _ctx: &Context<Self>) -> bool {
+        // The board's tasks changed, other orders are loaded again
+        if self.sort != DEFAULT_SORT {
+            self.tasks = None;
         }
-        let tasks = self.tasks.clone();
-        let tasks = tasks.unwrap().into_iter().map(|task| html! {
-            <div class="card" style="width: 18rem;">
-                <div class="card-body">
-                    <h5 class="card-title">{task.name}</h5>
-                    <h6 class="card-subtitle mb-2 text-muted">{"Note:"}{task.note.unwrap()}</h6>
-                    <h6 class="card-subtitle mb-2 text-muted">{"Place:"}{task.place.unwrap()}</h6>
-                    <h6 class="card-subtitle mb-2 text-muted">{"Assigned:"}{task.members.unwrap()}</h6>
-                    <h6 class="card-subtitle mb-2 text-muted">{"Deadline:"}{task.deadline}</h6>
-                    <h6 class="card-subtitle mb-2 text-muted">{"Points:"}{if task.points >= 0 {task.points.to_string()} else {"".to_string()}}</h6>
-                    <h6 class="card-subtitle mb-2 text-muted">{"Tags:"}{task.tags}</h6>
-                    <SubTasks subtasks={task.subtasks.clone()}/>
-                    <Logs id={task.id.unwrap()}/>
-                    <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Delete(task.id)})}>{"Delete"}</button>
-                    <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| {open_modal("taskUpdate"); Self::Message::UpdateTask(task.id)})}>{"Update"}</button>
-                    <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| {open_modal(format!("logs{}", task.id.unwrap()).as_str()); Self::Message::Pass})}>{"Show logs"}</button>
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        // The board's tasks come with its snapshot, other orders are loaded by the list
+        let tasks = match (self.sort == DEFAULT_SORT, &self.tasks) {
+            (true, _) => ctx.props().tasks.clone(),
+            (false, Some(tasks)) => tasks.clone(),
+            (false, None) => {
+                let token = self.token.clone().unwrap();
+                let (id, sort) = (ctx.props().id, self.sort.clone());
+                ctx.link().send_future(async move {
+                    let tasks = get_tasks(&token, id, 0, &sort).await;
+                    Self::Message::Update(tasks)
+                });
+                return html! {};
+            }
+        };
+        let ticket = &ctx.props().ticket;
+        let board_tasks = &ctx.props().board_tasks;
+        let names = |ids: &[i32]| {
+            ids.iter()
+                .map(|id| {
+                    board_tasks
+                        .iter()
+                        .find(|task| task.id == Some(*id))
+                        .map_or_else(|| format!("#{}", id), |task| task.name.clone())
+                })
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let tasks = tasks.into_iter().map(|task| {
+            // Images show as thumbnails, the other files as links
+            let attachments = task.attachments.iter().filter_map(|attachment| {
+                let id = attachment.id?;
+                let url = attachment_url(ticket, id);
+                let file = if attachment.is_image() {
+                    html! { <img src={url.clone()} alt={attachment.name.clone()} style="max-width: 80px; max-height: 80px;"/> }
+                } else {
+                    html! { {attachment.name.clone()} }
+                };
+                Some(html! {
+                    <span style="margin-right: 4px;">
+                        <a href={url} target="_blank">{file}</a>
+                        <button class="btn btn-sm btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::DeleteAttachment(id))}>{"x"}</button>
+                    </span>
+                })
+            }).collect::<Html>();
+            let file = format!("attachmentFile{}", task.id.unwrap_or_default());
+            let blocker_options = board_tasks.iter().filter(|other| other.id != task.id).map(|other| {
+                let other_id = other.id.unwrap_or_default();
+                html! {
+                    <option value={other_id.to_string()} selected={task.blocked_by.contains(&other_id)}>{other.name.clone()}</option>
+                }
+            }).collect::<Html>();
+            // New instances go to the task's list unless the series chose another one
+            let recurrence_list = task.recurrence.as_ref().map_or(task.list, |recurrence| recurrence.list);
+            let list_options = ctx.props().lists.iter().flatten().map(|list| {
+                html! {
+                    <option value={list.id.unwrap_or_default().to_string()} selected={list.id == Some(recurrence_list)}>{list.name.clone()}</option>
+                }
+            }).collect::<Html>();
+            let rule = task.recurrence.as_ref().map(|recurrence| recurrence.rule.clone());
+            let (blocked_by, blocks) = (names(&task.blocked_by), names(&task.blocks));
+            let parent = names(&task.parent.into_iter().collect::<Vec<_>>());
+            let expanded = task.id.map_or(false, |id| self.expanded.contains(&id));
+            // Children can be on the other lists of the board
+            let children = board_tasks.iter().filter(|child| expanded && child.parent == task.id && child.parent.is_some()).map(|child| {
+                html! {
+                    <li>
+                        if child.done == 1 {
+                            <s>{child.name.clone()}</s>
+                        } else {
+                            {child.name.clone()}
+                        }
+                    </li>
+                }
+            }).collect::<Html>();
+            html! {
+                <div class="card" style="width: 18rem;">
+                    <div class="card-body">
+                        <h5 class="card-title">
+                            {task.name}
+                            if task.blocked {
+                                {" "}<span class="badge bg-warning">{"Blocked"}</span>
+                            }
+                        </h5>
+                        <h6 class="card-subtitle mb-2 text-muted">{"Note:"}{task.note.unwrap()}</h6>
+                        <h6 class="card-subtitle mb-2 text-muted">{"Place:"}{task.place.unwrap()}</h6>
+                        <h6 class="card-subtitle mb-2 text-muted">{"Assigned:"}{task.members.unwrap()}</h6>
+                        <h6 class="card-subtitle mb-2 text-muted">{"Deadline:"}{task.deadline}</h6>
+                        <h6 class="card-subtitle mb-2 text-muted">{"Points:"}{if task.points >= 0 {task.points.to_string()} else {"".to_string()}}</h6>
+                        <h6 class="card-subtitle mb-2 text-muted">{"Tags:"}{task.tags}</h6>
+                        if !parent.is_empty() {
+                            <h6 class="card-subtitle mb-2 text-muted">{"Parent:"}{parent}</h6>
+                        }
+                        if task.children.total > 0 {
+                            <h6 class="card-subtitle mb-2 text-muted">
+                                {format!("{}/{} children done", task.children.done, task.children.total)}
+                                {format!(" ({}/{} points) ", task.children.points_done, task.children.points_total)}
+                                <a href="#" onclick={ctx.link().callback(move |e: MouseEvent| {e.prevent_default(); Self::Message::Expand(task.id.unwrap())})}>{if expanded {"Hide"} else {"Expand"}}</a>
+                            </h6>
+                            <ul>{children}</ul>
+                        }
+                        if let Some(rule) = rule.clone() {
+                            <h6 class="card-subtitle mb-2 text-muted">{"Repeats:"}{rule}</h6>
+                        }
+                        if !blocked_by.is_empty() {
+                            <h6 class="card-subtitle mb-2 text-muted">{"Blocked by:"}{blocked_by}</h6>
+                        }
+                        if !blocks.is_empty() {
+                            <h6 class="card-subtitle mb-2 text-muted">{"Blocks:"}{blocks}</h6>
+                        }
+                        <SubTasks subtasks={task.subtasks.clone()}/>
+                        <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Delete(task.id)})}>{"Delete"}</button>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| {open_modal("taskUpdate"); Self::Message::UpdateTask(task.id)})}>{"Update"}</button>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::ShowLogs(task.id.unwrap()))}>{"Show logs"}</button>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::ShowComments(task.id.unwrap()))}>{"Comments"}</button>
+                        if task.blocked && task.done == 0 {
+                            <button class="btn btn-warning" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::SetDone(task.id.unwrap(), 1, true))}>{"Mark done anyway"}</button>
+                        } else {
+                            <button class="btn btn-success" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::SetDone(task.id.unwrap(), 1 - task.done, false))}>{if task.done == 1 {"Reopen"} else {"Mark done"}}</button>
+                        }
+                        <select multiple=true class="form-control" id={format!("blockers{}", task.id.unwrap_or_default())}>{blocker_options}</select>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::SetBlockers(task.id.unwrap()))}>{"Set blockers"}</button>
+                        <input type="text" class="form-control" id={format!("recurrenceRule{}", task.id.unwrap_or_default())} placeholder="FREQ=WEEKLY;BYDAY=MO" value={rule.clone().unwrap_or_default()}/>
+                        <select class="form-control" id={format!("recurrenceList{}", task.id.unwrap_or_default())}>{list_options}</select>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::Repeat(task.id.unwrap()))}>{"Repeat"}</button>
+                        if rule.is_some() {
+                            <button class="btn btn-secondary" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::StopRepeating(task.id.unwrap()))}>{"Stop repeating"}</button>
+                        }
+                        <div>{attachments}</div>
+                        <input type="file" id={file}/>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| Self::Message::Upload(task.id.unwrap()))}>{"Attach"}</button>
+                    </div>
                 </div>
-            </div>
+            }
         });
 
+        let id = ctx.props().id;
+        let sort_options = [
+            ("created", "Oldest"),
+            ("-created", "Newest"),
+            ("deadline", "Deadline"),
+            ("-points", "Most points"),
+            ("points", "Fewest points"),
+            ("name", "Name"),
+            ("-updated", "Recently updated"),
+        ]
+        .into_iter()
+        .map(|(value, label)| {
+            html! {
+                <option value={value} selected={self.sort == value}>{label}</option>
+            }
+        });
+        let list = format!("list{}", id);
+        let scrolled = list.clone();
         html! {
             <>
-                <div class="col-xs-6" style="padding-left: 80px;">
+                <div class="col-xs-6" id={list} style="padding-left: 80px; max-height: 90vh; overflow-y: auto;" onscroll={ctx.link().batch_callback(move |_: Event| scrolled_to_bottom(&scrolled).then_some(MsgList::LoadMore))}>
                     <h2>{&ctx.props().name}</h2>
                     <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::DeleteList})}>{"Delete list"}</button>
+                    if !ctx.props().filtered {
+                        <select id={format!("sort{}", id)} onchange={ctx.link().callback(|_: Event| MsgList::Sort)}>
+                            {for sort_options}
+                        </select>
+                    }
                     {for tasks}
+                    if let Some(id) = self.logs {
+                        <Logs key={id} id={id}/>
+                    }
+                    if let Some(id) = self.comments {
+                        <TaskComments key={id} task_id={id} revision={ctx.props().comments}/>
+                    }
                 </div>
                 <div class="col-xs-6 vl"></div>
             </>
@@ -279,7 +661,11 @@ struct ListProp {
     pub name: String,
     pub id: i32,
     lists: Option<Vec<List>>,
-    pub filter: Option<TaskFilter>,
+    pub tasks: Vec<Task>, // From the board's snapshot or matching its filter
+    pub board_tasks: Vec<Task>, // All of the board's tasks, which can block the list's tasks
+    pub filtered: bool,
+    pub comments: u32,  // Revision of the board's comments
+    pub ticket: String, // For the attachments' urls
 }
 
 pub struct Board {
@@ -287,37 +673,177 @@ pub struct Board {
     board_id: i32,
     lists: Option<Vec<List>>,
     token: Option<String>,
+    ticket: Option<String>, // Authorizes the urls, the events and the attachments
     error: bool,
     filter: Option<TaskFilter>,
+    filtered: Option<Vec<Task>>,
+    saved: Option<Vec<SavedFilter>>,
     milestones: Option<Vec<Milestone>>,
+    tasks: Vec<Task>,
+    events: Option<EventSubscription>,
+    last_event_id: Option<u64>,
+    pending: Vec<BoardEvent>, // Received while the snapshot loads
+    conflict: Option<Conflict>,
+    comments: u32, // Revision of the board's comments, bumped by their events
+    _refresh_handle: Interval,
+}
+
+/// Task update which somebody else's update got ahead of
+struct Conflict {
+    mine: Task,
+    theirs: Task,
+    changed: Vec<&'static str>, // Fields the user changed, the others default to theirs
 }
 
 pub enum Msg {
     Submit,
     Res(Result<bool, Error>),
-    Update(Result<Vec<List>, Error>),
+    Update(Result<Snapshot, Error>),
     UpdateTaskSubmit,
     AddTask,
     Filter,
     Reset,
-    UpdateMilestones(Result<Vec<Milestone>, Error>),
+    Filtered(Result<Vec<Task>, Error>),
+    Saved(Result<Vec<SavedFilter>, Error>),
+    ApplySaved,
+    SaveFilter,
+    DeleteSaved,
+    Event(String),
+    Disconnected,
+    FetchTicket,
+    Ticket(Result<String, Error>),
+    TaskUpdated(Result<UpdateResult<Task>, Error>, Task, Option<Task>), // With the task sent and the version it was based on
+    SaveMerged,
+    DiscardMine,
+}
+
+/// Replaces the item with the id by its new version, adds it when it's new or removes it when
+/// there's no new version
+fn patch<T>(items: &mut Vec<T>, id: i32, item: Option<T>, item_id: fn(&T) -> Option<i32>) {
+    let position = items.iter().position(|old| item_id(old) == Some(id));
+    match (position, item) {
+        (Some(position), Some(item)) => items[position] = item,
+        (Some(position), None) => {
+            items.remove(position);
+        }
+        (None, Some(item)) => items.push(item),
+        (None, None) => {}
+    }
+}
+
+impl Board {
+    /// Shows the tasks matching the filter and keeps it in the url, so the link reproduces the view
+    fn set_filter(&mut self, filter: Option<TaskFilter>) {
+        let query = filter.as_ref().map(|f| f.to_query()).unwrap_or_default();
+        replace_url(&format!(
+            "board?board_type={}&id={}{}",
+            self.board_type, self.board_id, query
+        ));
+        self.filter = filter;
+        self.filtered = None;
+        self.lists = None;
+    }
+
+    fn selected_filter(&self) -> Option<&SavedFilter> {
+        let id = get_value("savedFilter").parse::<i32>().ok()?;
+        self.saved.iter().flatten().find(|saved| saved.id == id)
+    }
+
+    /// Live changes of the board, resumed after the last event received
+    fn subscribe(&mut self, ctx: &Context<Self>) {
+        let ticket = match &self.ticket {
+            Some(ticket) => ticket,
+            None => return,
+        };
+        let url = board_events_url(ticket, &self.board_type, self.board_id, self.last_event_id);
+        let on_event = ctx.link().callback(Msg::Event);
+        let on_closed = ctx.link().callback(|_: ()| Msg::Disconnected);
+        self.events = Some(EventSubscription::new(
+            &url,
+            move |data| on_event.emit(data),
+            move || on_closed.emit(()),
+        ));
+    }
+
+    /// Patches the board with the change instead of loading it again
+    fn apply(&mut self, event: BoardEvent) {
+        let id = event.entity;
+        let deleted = event.action == "deleted";
+        match event.kind.as_str() {
+            "task" => {
+                let task = event
+                    .data
+                    .and_then(|data| serde_json::from_value(data).ok());
+                patch(&mut self.tasks, id, task, |task: &Task| task.id);
+                // The filter runs on the backend
+                if self.filter.is_some() {
+                    self.filtered = None;
+                }
+            }
+            "list" => {
+                let list = event
+                    .data
+                    .and_then(|data| serde_json::from_value(data).ok());
+                patch(self.lists.get_or_insert_with(Vec::new), id, list, |list| {
+                    list.id
+                });
+                if deleted {
+                    self.tasks.retain(|task| task.list != id);
+                }
+            }
+            "milestone" => {
+                let milestone = event
+                    .data
+                    .and_then(|data| serde_json::from_value(data).ok());
+                let milestones = self.milestones.get_or_insert_with(Vec::new);
+                patch(milestones, id, milestone, |milestone| milestone.id);
+                if deleted {
+                    for task in self.tasks.iter_mut().filter(|t| t.milestone == Some(id)) {
+                        task.milestone = None;
+                    }
+                }
+            }
+            // The open comments load again
+            "comment" => self.comments = self.comments.wrapping_add(1),
+            // Too many changes were missed
+            _ => self.lists = None,
+        }
+    }
 }
 
 impl Component for Board {
     type Message = Msg;
     type Properties = ();
 
-    fn create(_ctx: &Context<Self>) -> Self {
+    fn create(ctx: &Context<Self>) -> Self {
         let board_type = get_parameter("board_type");
         let board_id = get_parameter("id").parse::<i32>().unwrap();
+        let _refresh_handle = {
+            let link = ctx.link().clone();
+            Interval::new(TICKET_REFRESH_MS, move || {
+                link.send_message(Msg::FetchTicket)
+            })
+        };
+        // Subscribed with the ticket before the snapshot loads, so no change is missed in between
+        ctx.link().send_message(Msg::FetchTicket);
         Self {
             board_type,
             board_id,
             lists: None,
             token: map_token(LocalStorage::get("Token")),
+            ticket: None,
             error: false,
-            filter: None,
+            filter: TaskFilter::from_query(),
+            filtered: None,
+            saved: None,
             milestones: None,
+            tasks: Vec::new(),
+            events: None,
+            last_event_id: None,
+            pending: Vec::new(),
+            conflict: None,
+            comments: 0,
+            _refresh_handle,
         }
     }
 
@@ -343,14 +869,61 @@ impl Component for Board {
                 });
                 false
             }
-            Self::Message::Update(Ok(lists)) => {
-                self.lists = Some(lists);
+            Self::Message::Update(Ok(snapshot)) => {
+                self.lists = Some(snapshot.lists);
+                self.milestones = Some(snapshot.milestones);
+                self.tasks = snapshot.tasks;
+                for event in std::mem::take(&mut self.pending) {
+                    self.apply(event);
+                }
+                true
+            }
+            // The board's events bring the change
+            Self::Message::Res(Ok(_)) => false,
+            Self::Message::Event(data) => {
+                let event = match serde_json::from_str::<BoardEvent>(&data) {
+                    Ok(event) => event,
+                    Err(_) => return false,
+                };
+                self.last_event_id = Some(event.id);
+                if self.lists.is_none() {
+                    self.pending.push(event);
+                    return false;
+                }
+                self.apply(event);
                 true
             }
-            Self::Message::Res(Ok(_)) => {
-                self.lists = None;
+            Self::Message::Disconnected => {
+                self.events = None;
+                let link = ctx.link().clone();
+                Timeout::new(RECONNECT_MS, move || link.send_message(Msg::FetchTicket)).forget();
+                false
+            }
+            Self::Message::FetchTicket => {
+                let token = match self.token.clone() {
+                    Some(token) => token,
+                    None => return false,
+                };
+                ctx.link().send_future(async move {
+                    let res = get_ticket(&token).await;
+                    Self::Message::Ticket(res)
+                });
+                false
+            }
+            Self::Message::Ticket(Ok(ticket)) => {
+                self.ticket = Some(ticket);
+                if self.events.is_none() {
+                    self.subscribe(ctx);
+                }
                 true
             }
+            // A stream which is still open keeps going, a closed one tries again
+            Self::Message::Ticket(Err(_)) => {
+                if self.events.is_none() {
+                    ctx.link().send_message(Msg::Disconnected);
+                }
+                false
+            }
             Self::Message::AddTask => {
                 let name = get_value("nameTask");
                 let note = get_value("note");
@@ -370,6 +943,7 @@ impl Component for Board {
                 let tags = get_value("tags");
                 let done = is_checked("done");
                 let milestone = map_result(get_value("milestone").parse::<i32>());
+                let parent = map_result(get_value("parent").parse::<i32>());
                 ctx.link().send_future(async move {
                     let res = create_task(
                         &token,
@@ -386,17 +960,23 @@ impl Component for Board {
                             tags,
                             done,
                             milestone,
+                            version: 0,
+                            attachments: Vec::new(),
+                            blocked_by: Vec::new(),
+                            blocks: Vec::new(),
+                            blocked: false,
+                            parent,
+                            children: Rollup::default(),
+                            recurrence: None,
                         },
                     )
                     .await;
-                    let _ = reload();
                     Self::Message::Res(res)
                 });
                 false
             }
             Self::Message::Reset => {
-                self.filter = None;
-                self.lists = None;
+                self.set_filter(None);
                 true
             }
             Self::Message::UpdateTaskSubmit => {
@@ -419,32 +999,73 @@ impl Component for Board {
                 let tags = get_value("tagsUpdate");
                 let done = is_checked("doneUpdate");
                 let milestone = map_result(get_value("milestoneUpdate").parse::<i32>());
+                let parent = map_result(get_value("parentUpdate").parse::<i32>());
+                let base = serde_json::from_str::<Task>(&get_value("baseUpdate")).ok();
+                let task = Task {
+                    subtasks,
+                    id: Some(id),
+                    name,
+                    note: Some(note),
+                    place: Some(place),
+                    members: Some(members),
+                    list,
+                    deadline,
+                    points,
+                    tags,
+                    done,
+                    milestone,
+                    version: base.as_ref().map_or(0, |base| base.version),
+                    attachments: Vec::new(),
+                    blocked_by: Vec::new(),
+                    blocks: Vec::new(),
+                    blocked: false,
+                    parent,
+                    children: Rollup::default(),
+                    recurrence: None,
+                };
                 ctx.link().send_future(async move {
-                    let res = update_task(
-                        &token,
-                        Task {
-                            subtasks,
-                            id: Some(id),
-                            name,
-                            note: Some(note),
-                            place: Some(place),
-                            members: Some(members),
-                            list,
-                            deadline,
-                            points,
-                            tags,
-                            done,
-                            milestone,
-                        },
-                    )
-                    .await;
-                    let _ = reload();
-                    Self::Message::Res(res)
+                    let res = update_task(&token, task.clone()).await;
+                    Self::Message::TaskUpdated(res, task, base)
                 });
                 false
             }
-            Self::Message::UpdateMilestones(Ok(milestones)) => {
-                self.milestones = Some(milestones);
+            Self::Message::TaskUpdated(Ok(UpdateResult::Updated), _, _) => {
+                self.conflict = None;
+                hide_modal("conflictModal");
+                true
+            }
+            Self::Message::TaskUpdated(Ok(UpdateResult::Conflict(theirs)), mine, base) => {
+                let changed = match base {
+                    Some(base) => base.differences(&mine),
+                    None => mine.differences(&theirs),
+                };
+                self.conflict = Some(Conflict {
+                    changed: changed.into_iter().map(|(field, _, _)| field).collect(),
+                    mine,
+                    theirs,
+                });
+                hide_modal("taskUpdate");
+                open_modal("conflictModal");
+                true
+            }
+            Self::Message::SaveMerged => {
+                let conflict = match self.conflict.take() {
+                    Some(conflict) => conflict,
+                    None => return false,
+                };
+                let merged = conflict.mine.merge(&conflict.theirs, |field| {
+                    is_checked(&format!("theirs_{}", field)) == 1
+                });
+                let (token, base) = (self.token.clone().unwrap(), conflict.theirs);
+                ctx.link().send_future(async move {
+                    let res = update_task(&token, merged.clone()).await;
+                    Self::Message::TaskUpdated(res, merged, Some(base))
+                });
+                false
+            }
+            Self::Message::DiscardMine => {
+                self.conflict = None;
+                hide_modal("conflictModal");
                 true
             }
             Self::Message::Filter => {
@@ -456,6 +1077,7 @@ impl Component for Board {
                 let points_min = map_result(get_value("pointsMin").parse::<i32>());
                 let points_max = map_result(get_value("pointsMax").parse::<i32>());
                 let tags = get_value("tagsFilter");
+                let query = get_value("queryFilter");
                 let filter = TaskFilter {
                     name,
                     place,
@@ -465,11 +1087,89 @@ impl Component for Board {
                     points_min,
                     points_max,
                     tags,
+                    query,
+                    done: get_value("doneFilter").parse::<bool>().ok(),
+                    milestone: get_value("milestoneFilter").parse::<i32>().ok(),
+                    overdue: is_checked("overdueFilter") == 1,
+                    no_deadline: is_checked("noDeadlineFilter") == 1,
+                    unassigned: is_checked("unassignedFilter") == 1,
+                    open_subtasks: is_checked("openSubtasksFilter") == 1,
+                    members_any: is_checked("membersAnyFilter") == 1,
+                    tags_any: is_checked("tagsAnyFilter") == 1,
+                    blocked: get_value("blockedFilter").parse::<bool>().ok(),
+                    epic: get_value("epicFilter").parse::<bool>().ok(),
+                    parent: self.filter.as_ref().and_then(|filter| filter.parent),
                 };
-                self.filter = Some(filter);
-                self.lists = None;
+                self.set_filter(Some(filter));
+                hide_modal("filterModal");
+                true
+            }
+            Self::Message::Filtered(Ok(tasks)) => {
+                self.filtered = Some(tasks);
+                true
+            }
+            Self::Message::Filtered(Err(Error::GlooError(message))) => {
+                err(&format!("Invalid query: {}", message));
+                self.set_filter(None);
+                true
+            }
+            Self::Message::Saved(Ok(saved)) => {
+                self.saved = Some(saved);
+                true
+            }
+            Self::Message::ApplySaved => {
+                let filter = self.selected_filter().map(|saved| saved.filter.clone());
+                if filter.is_none() {
+                    err("Please select filter");
+                    return false;
+                }
+                self.set_filter(filter);
                 true
             }
+            Self::Message::SaveFilter => {
+                let name = get_value("savedFilterName");
+                let filter = match self.filter.clone() {
+                    Some(filter) if !name.is_empty() => filter,
+                    _ => {
+                        err("Please filter the tasks and enter a name");
+                        return false;
+                    }
+                };
+                let data = SavedFilterData {
+                    name,
+                    board_type: self.board_type.clone(),
+                    board_id: self.board_id,
+                    shared: self.board_type == "team" && is_checked("savedFilterShared") == 1,
+                    filter,
+                };
+                let token = self.token.clone().unwrap();
+                let (board_type, board_id) = (self.board_type.clone(), self.board_id);
+                ctx.link().send_future(async move {
+                    let _ = create_saved_filter(&token, data).await;
+                    let saved = get_saved_filters(&token, &board_type, board_id).await;
+                    Self::Message::Saved(saved)
+                });
+                false
+            }
+            Self::Message::DeleteSaved => {
+                let id = match self.selected_filter() {
+                    Some(saved) => saved.id,
+                    None => {
+                        err("Please select filter");
+                        return false;
+                    }
+                };
+                let token = self.token.clone().unwrap();
+                let (board_type, board_id) = (self.board_type.clone(), self.board_id);
+                ctx.link().send_future(async move {
+                    if delete_saved_filter(&token, id).await.is_err() {
+                        err("Only the author can delete the filter");
+                    }
+                    let saved = get_saved_filters(&token, &board_type, board_id).await;
+                    Self::Message::Saved(saved)
+                });
+                false
+            }
             _ => {
                 self.error = true;
                 true
@@ -478,7 +1178,7 @@ impl Component for Board {
     }
 
     fn view(&self, ctx: &Context<Self>) -> Html {
-        if self.token.is_none() {
+        if self.token.is_none() || self.ticket.is_none() {
             return html! {};
         }
         if self.lists.is_none() {
@@ -486,18 +1186,28 @@ impl Component for Board {
             let board_id = self.board_id;
             let board_type = self.board_type.clone();
             ctx.link().send_future(async move {
-                let lists = get_lists(board_id, board_type, &token).await;
-                Self::Message::Update(lists)
+                let snapshot = get_board_snapshot(&token, &board_type, board_id).await;
+                Self::Message::Update(snapshot)
             });
             return html! {};
         }
-        if self.milestones.is_none() {
+        if self.saved.is_none() {
             let token = self.token.clone().unwrap();
             let board_type = self.board_type.clone();
             let id = self.board_id;
             ctx.link().send_future(async move {
-                let res = get_milestones(id, board_type, &token).await;
-                Self::Message::UpdateMilestones(res)
+                let res = get_saved_filters(&token, &board_type, id).await;
+                Self::Message::Saved(res)
+            });
+            return html! {};
+        }
+        if let (Some(filter), None) = (self.filter.clone(), &self.filtered) {
+            let token = self.token.clone().unwrap();
+            let board_type = self.board_type.clone();
+            let id = self.board_id;
+            ctx.link().send_future(async move {
+                let res = filter_board_tasks(&token, &board_type, id, filter).await;
+                Self::Message::Filtered(res)
             });
             return html! {};
         }
@@ -505,8 +1215,21 @@ impl Component for Board {
         let lists_clone = lists.clone();
         let filter = self.filter.clone();
         let lists = lists.unwrap().into_iter().map(|list| {
+            let tasks = self
+                .filtered
+                .as_ref()
+                .unwrap_or(&self.tasks)
+                .iter()
+                .filter(|task| Some(task.list) == list.id)
+                .cloned()
+                .collect::<Vec<_>>();
             html! {
-                <ListDetails name={list.name} id ={list.id.unwrap()} lists={lists_clone.clone()} filter={filter.clone()}/>
+                <ListDetails name={list.name} id ={list.id.unwrap()} lists={lists_clone.clone()} tasks={tasks} board_tasks={self.tasks.clone()} filtered={self.filtered.is_some()} comments={self.comments} ticket={self.ticket.clone().unwrap_or_default()}/>
+            }
+        });
+        let saved_options = self.saved.iter().flatten().map(|saved| {
+            html! {
+                <option value={saved.id.to_string()}>{saved.name.clone()}{if saved.shared { " (team)" } else { "" }}</option>
             }
         });
         let lists_options = self.lists.clone();
@@ -521,6 +1244,44 @@ impl Component for Board {
             }
         });
         let milestone_options_clone = milestone_options.clone();
+        let milestone_options_filter = milestone_options.clone();
+        let parent_options = self.tasks.iter().map(|task| {
+            html! {
+                <option value={task.id.unwrap_or_default().to_string()}>{task.name.clone()}</option>
+            }
+        });
+        let parent_options_update = parent_options.clone();
+        let conflict = self.conflict.as_ref().map(|conflict| {
+            let rows = conflict
+                .mine
+                .differences(&conflict.theirs)
+                .into_iter()
+                .map(|(field, mine, theirs)| {
+                    let changed = conflict.changed.contains(&field);
+                    let group = format!("conflict_{}", field);
+                    html! {
+                        <tr>
+                            <td>{field}</td>
+                            <td>
+                                <input type="radio" name={group.clone()} id={format!("mine_{}", field)} checked={changed}/>
+                                {" "}{mine}
+                            </td>
+                            <td>
+                                <input type="radio" name={group} id={format!("theirs_{}", field)} checked={!changed}/>
+                                {" "}{theirs}
+                            </td>
+                        </tr>
+                    }
+                });
+            html! {
+                <table class="table">
+                    <thead>
+                        <tr><th>{"Field"}</th><th>{"Yours"}</th><th>{"Saved meanwhile"}</th></tr>
+                    </thead>
+                    <tbody>{for rows}</tbody>
+                </table>
+            }
+        });
         html! {
             <>
             <Navbar />
@@ -543,6 +1304,26 @@ impl Component for Board {
                     <button class="btn btn-primary" id="myBtn" onclick={|_: MouseEvent| {open_modal("myModal");}} >{"Add task"}</button>
                     <button class="btn btn-primary" id="myBtnFilter" onclick={move |_: MouseEvent| {if filter.clone().is_some() {filter.clone().unwrap().set_filters();} open_modal("filterModal");}} >{"Filter tasks"}</button>
                     <button class="btn btn-danger" id="myBtnReset" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Reset})}>{"Reset filters"}</button>
+                    <button class="btn btn-primary" id="myBtnActivity" onclick={|_: MouseEvent| {open_modal("activityModal");}}>{"Activity"}</button>
+                    <BoardActivity board_type={self.board_type.clone()} board_id={self.board_id} revision={self.comments}/>
+                    <div class="form-group">
+                        <label for="savedFilter">{"Saved filters:"}</label>
+                        <select id="savedFilter">
+                            {for saved_options}
+                        </select>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::ApplySaved})}>{"Apply"}</button>
+                        <button class="btn btn-danger" onclick={ctx.link().callback(|_: MouseEvent| {Msg::DeleteSaved})}>{"Delete"}</button>
+                    </div>
+                    if self.filter.is_some() {
+                        <div class="form-group">
+                            <input type="text" class="form-control" id="savedFilterName" placeholder="Name of the current filter"/>
+                            if self.board_type == "team" {
+                                <input type="checkbox" id="savedFilterShared"/>
+                                <label for="savedFilterShared">{"Share with the team"}</label>
+                            }
+                            <button class="btn btn-primary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::SaveFilter})}>{"Save filter"}</button>
+                        </div>
+                    }
                     <div id="myModal" class="modal">
 
                     <div class="modal-content">
@@ -587,6 +1368,13 @@ impl Component for Board {
                                     {for milestone_options}
                                 </select>
                         </div>
+                        <div class="form-group">
+                            <label for="parent">{"Parent task:"}</label>
+                                <select id="parent">
+                                    <option value="None">{"None"}</option>
+                                    {for parent_options}
+                                </select>
+                        </div>
                         <div class="form-group">
                             <input type="checkbox" id="done" name="Done" value="yes"/>
                             <label for="done">{"Done:"}</label>
@@ -625,6 +1413,7 @@ impl Component for Board {
                     </div>
                     <div class="form-group">
                         <input type="hidden" class="form-control" id="idUpdate" aria-describedby="usernameHelp" placeholder="Enter place"/>
+                        <input type="hidden" id="baseUpdate"/>
                     </div>
                     <div class="form-group">
                         <label for="members">{"Assigned people"}</label>
@@ -657,6 +1446,13 @@ impl Component for Board {
                                     {for milestone_options_clone}
                                 </select>
                     </div>
+                    <div class="form-group">
+                            <label for="parentUpdate">{"Parent task:"}</label>
+                                <select id="parentUpdate">
+                                <option value="None">{"None"}</option>
+                                    {for parent_options_update}
+                                </select>
+                    </div>
                     <div class="form-group">
                             <input type="checkbox" id="doneUpdate" name="Done" value="yes"/>
                             <label for="doneUpdate">{"Done:"}</label>
@@ -670,11 +1466,26 @@ impl Component for Board {
                 </form>
                 </div>
                 </div>
+                <div id="conflictModal" class="modal">
+                <div class="modal-content">
+                    <span class="close btn btn-danger" onclick={ctx.link().callback(|_: MouseEvent| Msg::DiscardMine)}>{"Hide"}</span>
+                    <h4>{"Somebody saved this task while you were editing it"}</h4>
+                    <p class="text-muted">{"Choose the value to keep for every field you both changed."}</p>
+                    {conflict}
+                    <button class="btn btn-primary" onclick={ctx.link().callback(|_: MouseEvent| Msg::SaveMerged)}>{"Save"}</button>
+                    <button class="btn btn-danger" onclick={ctx.link().callback(|_: MouseEvent| Msg::DiscardMine)}>{"Discard my changes"}</button>
+                </div>
+                </div>
                 <div id="filterModal" class="modal">
 
                 <div class="modal-content">
                     <span class="close btn btn-danger" onclick={|_: MouseEvent| {hide_modal("filterModal");}}>{"Hide"}</span>
                     <form>
+                    <div class="form-group">
+                        <label for="queryFilter">{"Query"}</label>
+                        <input type="text" class="form-control" id="queryFilter" placeholder="tag:bug points>=3 due<2026-11-01 @alice -done milestone:\"v1\""/>
+                        <small id="queryHelp" class="form-text text-muted">{"Fields: name, tag, place, note, milestone, points, due, @person, done, blocked, epic; prefix a term with - to negate it"}</small>
+                    </div>
                     <div class="form-group">
                         <label for="nameTaskFilter">{"name contains"}</label>
                         <input type="text" class="form-control" id="nameTaskFilter" aria-describedby="usernameHelp" placeholder="Enter task name"/>
@@ -687,6 +1498,14 @@ impl Component for Board {
                         <label for="membersFilter">{"Assigned people contains"}</label>
                         <input type="text" class="form-control" id="membersFilter" aria-describedby="usernameHelp" placeholder="Enter assigned people"/>
                         <small id="membersHelpFilter" class="form-text text-muted">{"Assigned people should be seperated by ;"}</small>
+                        <div>
+                            <input type="checkbox" id="membersAnyFilter"/>
+                            <label for="membersAnyFilter">{"Any of them"}</label>
+                        </div>
+                    </div>
+                    <div class="form-group">
+                        <input type="checkbox" id="unassignedFilter"/>
+                        <label for="unassignedFilter">{"Unassigned"}</label>
                     </div>
                     <div class="form-group">
                         <label for="deadlineStart">{"Deadline start:"}</label>
@@ -698,6 +1517,12 @@ impl Component for Board {
                         <input type="date" class="form-control" id="deadlineEnd"/>
                         <small id="endHelp" class="form-text text-muted">{"Leave empty for no filter"}</small>
                     </div>
+                    <div class="form-group">
+                        <input type="checkbox" id="overdueFilter"/>
+                        <label for="overdueFilter">{"Overdue"}</label>
+                        <input type="checkbox" id="noDeadlineFilter"/>
+                        <label for="noDeadlineFilter">{"No deadline"}</label>
+                    </div>
                     <div class="form-group">
                         <label for="pointsMin">{"Points min:"}</label>
                         <input type="number" class="form-control" id="pointsMin"/>
@@ -712,6 +1537,45 @@ impl Component for Board {
                         <label for="tags">{"Tags contains:"}</label>
                         <input type="text" class="form-control"  id="tagsFilter"/>
                         <small id="TagsHelp" class="form-text text-muted">{"Tags should be seperated by ;"}</small>
+                        <div>
+                            <input type="checkbox" id="tagsAnyFilter"/>
+                            <label for="tagsAnyFilter">{"Any of them"}</label>
+                        </div>
+                    </div>
+                    <div class="form-group">
+                        <label for="doneFilter">{"Done:"}</label>
+                        <select id="doneFilter">
+                            <option value="">{"Any"}</option>
+                            <option value="true">{"Done"}</option>
+                            <option value="false">{"Not done"}</option>
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="epicFilter">{"Epics:"}</label>
+                        <select id="epicFilter">
+                            <option value="">{"Any"}</option>
+                            <option value="true">{"Only epics"}</option>
+                            <option value="false">{"No epics"}</option>
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="blockedFilter">{"Blocked:"}</label>
+                        <select id="blockedFilter">
+                            <option value="">{"Any"}</option>
+                            <option value="true">{"Blocked"}</option>
+                            <option value="false">{"Not blocked"}</option>
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="milestoneFilter">{"Milestone:"}</label>
+                        <select id="milestoneFilter">
+                            <option value="">{"Any"}</option>
+                            {for milestone_options_filter}
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <input type="checkbox" id="openSubtasksFilter"/>
+                        <label for="openSubtasksFilter">{"Has open subtasks"}</label>
                     </div>
                     <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::Filter})}>{"Submit"}</button>
                 </form>
@@ -0,0 +1,185 @@
+use crate::types::{Burndown, BurndownDay};
+use crate::utils::day_number;
+use yew::{html, Component, Context, Html, MouseEvent};
+
+const WIDTH: f64 = 640.0;
+const HEIGHT: f64 = 320.0;
+const LEFT: f64 = 40.0;
+const BOTTOM: f64 = 30.0;
+const TOP: f64 = 10.0;
+
+pub struct BurndownChart {
+    burnup: bool,
+    points: bool,
+}
+
+pub enum Msg {
+    Burnup(bool),
+    Points(bool),
+}
+
+/// Maps days since the start and values to svg coordinates
+struct Scale {
+    start: f64,
+    span: f64,
+    max: f64,
+}
+
+impl Scale {
+    fn x(&self, date: &str) -> f64 {
+        LEFT + (day_number(date) - self.start) / self.span * (WIDTH - LEFT - TOP)
+    }
+
+    fn y(&self, value: f64) -> f64 {
+        TOP + (1.0 - value / self.max) * (HEIGHT - TOP - BOTTOM)
+    }
+
+    fn polyline(&self, values: Vec<(&str, f64)>, color: &str) -> Html {
+        let points = values
+            .into_iter()
+            .map(|(date, value)| format!("{:.1},{:.1}", self.x(date), self.y(value)))
+            .collect::<Vec<_>>()
+            .join(" ");
+        html! {
+            <polyline points={points} fill="none" stroke={color.to_owned()} stroke-width="2"/>
+        }
+    }
+}
+
+impl Component for BurndownChart {
+    type Message = Msg;
+    type Properties = Burndown;
+
+    fn create(_ctx: &Context<Self>) -> Self {
+        Self {
+            burnup: false,
+            points: true,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Burnup(burnup) => self.burnup = burnup,
+            Msg::Points(points) => self.points = points,
+        }
+        true
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let burndown = ctx.props();
+        let (first, last) = match (burndown.days.first(), burndown.days.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => return html! { <p class="text-muted">{"The milestone has not started yet"}</p> },
+        };
+        let points = self.points;
+        let total = |day: &BurndownDay| {
+            if points {
+                day.total_points
+            } else {
+                day.total_tasks
+            }
+        };
+        let done = |day: &BurndownDay| {
+            if points {
+                day.done_points
+            } else {
+                day.done_tasks
+            }
+        };
+
+        let end = burndown
+            .due_date
+            .clone()
+            .filter(|due| day_number(due) > day_number(&last.date))
+            .unwrap_or_else(|| last.date.clone());
+        let start = day_number(&burndown.start_date);
+        let max = burndown.days.iter().map(total).max().unwrap_or(0).max(1);
+        let scale = Scale {
+            start,
+            span: (day_number(&end) - start).max(1.0),
+            max: max as f64,
+        };
+
+        let series = |value: &dyn Fn(&BurndownDay) -> i32| {
+            burndown
+                .days
+                .iter()
+                .map(|day| (day.date.as_str(), value(day) as f64))
+                .collect::<Vec<_>>()
+        };
+        // The ideal line goes from the scope at the start to zero (or the whole scope) at the due date
+        let ideal = burndown.ideal.last().map(|due| {
+            let scope = total(first) as f64;
+            let (from, to) = if self.burnup {
+                (0.0, total(last) as f64)
+            } else {
+                (scope, 0.0)
+            };
+            html! {
+                <line x1={format!("{:.1}", scale.x(&burndown.start_date))} y1={format!("{:.1}", scale.y(from))}
+                    x2={format!("{:.1}", scale.x(&due.date))} y2={format!("{:.1}", scale.y(to))}
+                    stroke="gray" stroke-dasharray="6 4"/>
+            }
+        });
+        let lines = if self.burnup {
+            html! {
+                <>
+                    {scale.polyline(series(&total), "orange")}
+                    {scale.polyline(series(&done), "green")}
+                </>
+            }
+        } else {
+            scale.polyline(
+                series(&|day: &BurndownDay| total(day) - done(day)),
+                "steelblue",
+            )
+        };
+        let markers = burndown.scope_changes.iter().map(|change| {
+            let x = format!("{:.1}", scale.x(&change.date));
+            let label = format!(
+                "{}: {} ({:+} tasks, {:+} points)",
+                change.date, change.name, change.tasks, change.points
+            );
+            html! {
+                <line x1={x.clone()} y1={TOP.to_string()} x2={x} y2={(HEIGHT - BOTTOM).to_string()}
+                    stroke="red" stroke-width="3" stroke-opacity="0.3">
+                    <title>{label}</title>
+                </line>
+            }
+        });
+        let unit = if points { "points" } else { "tasks" };
+
+        html! {
+            <div>
+                <div class="btn-group mb-2">
+                    <button class={if self.burnup { "btn btn-outline-primary" } else { "btn btn-primary" }}
+                        onclick={ctx.link().callback(|_: MouseEvent| Msg::Burnup(false))}>{"Burndown"}</button>
+                    <button class={if self.burnup { "btn btn-primary" } else { "btn btn-outline-primary" }}
+                        onclick={ctx.link().callback(|_: MouseEvent| Msg::Burnup(true))}>{"Burnup"}</button>
+                </div>
+                {" "}
+                <div class="btn-group mb-2">
+                    <button class={if points { "btn btn-secondary" } else { "btn btn-outline-secondary" }}
+                        onclick={ctx.link().callback(|_: MouseEvent| Msg::Points(true))}>{"Points"}</button>
+                    <button class={if points { "btn btn-outline-secondary" } else { "btn btn-secondary" }}
+                        onclick={ctx.link().callback(|_: MouseEvent| Msg::Points(false))}>{"Tasks"}</button>
+                </div>
+                <svg width={WIDTH.to_string()} height={HEIGHT.to_string()} style="display: block;">
+                    <line x1={LEFT.to_string()} y1={TOP.to_string()} x2={LEFT.to_string()} y2={(HEIGHT - BOTTOM).to_string()} stroke="black"/>
+                    <line x1={LEFT.to_string()} y1={(HEIGHT - BOTTOM).to_string()} x2={(WIDTH - TOP).to_string()} y2={(HEIGHT - BOTTOM).to_string()} stroke="black"/>
+                    <text x="5" y={(TOP + 10.0).to_string()} font-size="12">{max}</text>
+                    <text x="5" y={(HEIGHT - BOTTOM).to_string()} font-size="12">{"0"}</text>
+                    <text x={LEFT.to_string()} y={(HEIGHT - 10.0).to_string()} font-size="12">{burndown.start_date.clone()}</text>
+                    <text x={(WIDTH - TOP).to_string()} y={(HEIGHT - 10.0).to_string()} font-size="12" text-anchor="end">{end.clone()}</text>
+                    {for markers}
+                    {for ideal}
+                    {lines}
+                </svg>
+                <small class="text-muted">
+                    {format!("Remaining {}: {}. ", unit, total(last) - done(last))}
+                    {"Dashed: ideal line to the due date, red: scope changes."}
+                </small>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,338 @@
+use crate::api::{
+    create_comment, delete_comment, get_board_comments, get_comments, update_comment,
+};
+use crate::types::{Comment, CommentActivity, CommentData};
+use crate::utils::{
+    err, get_value, hide_modal, map_token, open_modal, scrolled_to_bottom, set_value,
+};
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use yew::{html, Component, Context, Event, Html, MouseEvent, Properties};
+
+/// Text of the comment with the mentioned users in bold
+fn view_body(comment: &Comment) -> Html {
+    if comment.deleted {
+        return html! { <i>{"Deleted"}</i> };
+    }
+    let words = comment.body.split(' ').map(|word| {
+        let name = word
+            .strip_prefix('@')
+            .map(|name| name.trim_end_matches(|c: char| !c.is_alphanumeric()));
+        match name {
+            Some(name) if comment.mentions.iter().any(|m| m == name) => {
+                html! { <><strong>{word}</strong>{" "}</> }
+            }
+            _ => html! { <>{word}{" "}</> },
+        }
+    });
+    html! { <>{for words}</> }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct CommentsProp {
+    pub task_id: i32,
+    pub revision: u32, // Changes when the board's comments change
+}
+
+/// Threaded comments of a task, in a modal
+pub struct TaskComments {
+    token: Option<String>,
+    username: Option<String>,
+    comments: Option<Vec<Comment>>,
+    replying: Option<i32>,
+    editing: Option<i32>,
+}
+
+pub enum MsgComments {
+    Update(Result<Vec<Comment>, Error>),
+    Post(Option<i32>), // Reply to the comment, or a new thread
+    Reply(Option<i32>),
+    Edit(Option<i32>),
+    Save(i32),
+    Delete(i32),
+    Res(Result<bool, Error>),
+}
+
+impl TaskComments {
+    fn load(&self, ctx: &Context<Self>) {
+        if let Some(token) = self.token.clone() {
+            let id = ctx.props().task_id;
+            ctx.link().send_future(async move {
+                let comments = get_comments(&token, id).await;
+                MsgComments::Update(comments)
+            });
+        }
+    }
+
+    fn view_comment(&self, ctx: &Context<Self>, comment: &Comment) -> Html {
+        let id = comment.id;
+        let own = !comment.deleted && self.username.as_ref() == Some(&comment.author_name);
+        let replies = comment
+            .replies
+            .iter()
+            .map(|reply| self.view_comment(ctx, reply));
+        html! {
+            <div class="card" style="margin-top: 8px;">
+                <div class="card-body">
+                    <h6 class="card-subtitle mb-2 text-muted">
+                        {&comment.author_name}{" "}{&comment.created}
+                        if comment.edited.is_some() {
+                            {" (edited)"}
+                        }
+                    </h6>
+                    if self.editing == Some(id) {
+                        <textarea class="form-control" id={format!("editComment{}", id)} value={comment.body.clone()}/>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| MsgComments::Save(id))}>{"Save"}</button>
+                        <button class="btn btn-danger" onclick={ctx.link().callback(|_: MouseEvent| MsgComments::Edit(None))}>{"Cancel"}</button>
+                    } else {
+                        <p class="card-text">{view_body(comment)}</p>
+                    }
+                    if !comment.deleted {
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| MsgComments::Reply(Some(id)))}>{"Reply"}</button>
+                    }
+                    if own {
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| MsgComments::Edit(Some(id)))}>{"Edit"}</button>
+                        <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| MsgComments::Delete(id))}>{"Delete"}</button>
+                    }
+                    if self.replying == Some(id) {
+                        <textarea class="form-control" id={format!("replyComment{}", id)} placeholder="Reply, @name mentions a user"/>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| MsgComments::Post(Some(id)))}>{"Send"}</button>
+                    }
+                    <div style="padding-left: 20px;">
+                        {for replies}
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
+
+impl Component for TaskComments {
+    type Message = MsgComments;
+    type Properties = CommentsProp;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let comments = Self {
+            token: map_token(LocalStorage::get("Token")),
+            username: map_token(LocalStorage::get("Username")),
+            comments: None,
+            replying: None,
+            editing: None,
+        };
+        comments.load(ctx);
+        comments
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let token = match self.token.clone() {
+            Some(token) => token,
+            None => return false,
+        };
+        match msg {
+            Self::Message::Update(Ok(comments)) => {
+                self.comments = Some(comments);
+                true
+            }
+            Self::Message::Update(Err(_)) => false,
+            Self::Message::Post(parent) => {
+                let input = match parent {
+                    Some(id) => format!("replyComment{}", id),
+                    None => format!("newComment{}", ctx.props().task_id),
+                };
+                let body = get_value(&input);
+                if body.trim().is_empty() {
+                    err("Please write the comment");
+                    return false;
+                }
+                set_value(&input, "");
+                let id = ctx.props().task_id;
+                ctx.link().send_future(async move {
+                    let res = create_comment(&token, id, CommentData { body, parent }).await;
+                    Self::Message::Res(res)
+                });
+                self.replying = None;
+                true
+            }
+            Self::Message::Reply(id) => {
+                self.replying = id;
+                true
+            }
+            Self::Message::Edit(id) => {
+                self.editing = id;
+                true
+            }
+            Self::Message::Save(id) => {
+                let body = get_value(&format!("editComment{}", id));
+                if body.trim().is_empty() {
+                    err("Please write the comment");
+                    return false;
+                }
+                ctx.link().send_future(async move {
+                    let res = update_comment(&token, id, body).await;
+                    Self::Message::Res(res)
+                });
+                self.editing = None;
+                true
+            }
+            Self::Message::Delete(id) => {
+                ctx.link().send_future(async move {
+                    let res = delete_comment(&token, id).await;
+                    Self::Message::Res(res)
+                });
+                false
+            }
+            Self::Message::Res(Ok(_)) => {
+                self.load(ctx);
+                false
+            }
+            Self::Message::Res(Err(_)) => {
+                err("Could not save the comment");
+                false
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        self.load(ctx);
+        false
+    }
+
+    fn rendered(&mut self, ctx: &Context<Self>, first_render: bool) {
+        if first_render {
+            open_modal(&format!("comments{}", ctx.props().task_id));
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let task_id = ctx.props().task_id;
+        let id = format!("comments{}", task_id);
+        let comments = self
+            .comments
+            .iter()
+            .flatten()
+            .map(|comment| self.view_comment(ctx, comment));
+        html! {
+            <div id={id.clone()} class="modal">
+                <div class="modal-content" style="max-height: 80vh; overflow-y: auto;">
+                    <span class="close btn btn-danger" onclick={move |_: MouseEvent| {hide_modal(id.as_str());}}>{"Hide"}</span>
+                    <h3>{"Comments"}</h3>
+                    {for comments}
+                    <div class="form-group" style="margin-top: 8px;">
+                        <textarea class="form-control" id={format!("newComment{}", task_id)} placeholder="Comment, @name mentions a user"/>
+                        <button class="btn btn-primary" onclick={ctx.link().callback(|_: MouseEvent| MsgComments::Post(None))}>{"Comment"}</button>
+                    </div>
+                </div>
+            </div>
+        }
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ActivityProp {
+    pub board_type: String,
+    pub board_id: i32,
+    pub revision: u32, // Changes when the board's comments change
+}
+
+/// Newest comments on the board's tasks, more are loaded on scroll
+pub struct BoardActivity {
+    token: Option<String>,
+    activity: Option<Vec<CommentActivity>>,
+    total: i64,
+    loading: bool,
+}
+
+pub enum MsgActivity {
+    Update(Result<(Vec<CommentActivity>, i64), Error>, bool), // Whether it's the first page
+    LoadMore,
+}
+
+impl BoardActivity {
+    fn load(&mut self, ctx: &Context<Self>, offset: usize) {
+        let token = match self.token.clone() {
+            Some(token) => token,
+            None => return,
+        };
+        self.loading = true;
+        let (board_type, board_id) = (ctx.props().board_type.clone(), ctx.props().board_id);
+        ctx.link().send_future(async move {
+            let activity = get_board_comments(&token, &board_type, board_id, offset).await;
+            MsgActivity::Update(activity, offset == 0)
+        });
+    }
+}
+
+impl Component for BoardActivity {
+    type Message = MsgActivity;
+    type Properties = ActivityProp;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let mut activity = Self {
+            token: map_token(LocalStorage::get("Token")),
+            activity: None,
+            total: 0,
+            loading: false,
+        };
+        activity.load(ctx, 0);
+        activity
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Self::Message::Update(Ok((mut activity, total)), first) => {
+                if first {
+                    self.activity = None;
+                }
+                self.activity
+                    .get_or_insert_with(Vec::new)
+                    .append(&mut activity);
+                self.total = total;
+                self.loading = false;
+                true
+            }
+            Self::Message::Update(Err(_), _) => {
+                self.loading = false;
+                false
+            }
+            Self::Message::LoadMore => {
+                let loaded = self.activity.as_ref().map_or(0, |a| a.len());
+                if self.loading || loaded as i64 >= self.total {
+                    return false;
+                }
+                self.load(ctx, loaded);
+                false
+            }
+        }
+    }
+
+    fn changed(&mut self, ctx: &Context<Self>) -> bool {
+        // New comments come first
+        self.load(ctx, 0);
+        false
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let activity = self.activity.iter().flatten().map(|item| {
+            html! {
+                <div class="card" style="width: 18rem;">
+                    <div class="card-body">
+                        <h6 class="card-subtitle mb-2 text-muted">
+                            {&item.comment.author_name}{" on "}{&item.task_name}{" "}{&item.comment.created}
+                        </h6>
+                        <p class="card-text">{view_body(&item.comment)}</p>
+                    </div>
+                </div>
+            }
+        });
+        let scrolled = "activityContent";
+        html! {
+            <div id="activityModal" class="modal">
+                <div class="modal-content" id={scrolled} style="max-height: 80vh; overflow-y: auto;" onscroll={ctx.link().batch_callback(move |_: Event| scrolled_to_bottom(scrolled).then_some(MsgActivity::LoadMore))}>
+                    <span class="close btn btn-danger" onclick={|_: MouseEvent| {hide_modal("activityModal");}}>{"Hide"}</span>
+                    <h3>{"Activity"}</h3>
+                    {for activity}
+                </div>
+            </div>
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use crate::api::{create_goal, delete_goal, get_categories, get_goals_progress};
+use crate::types::{GoalData, GoalProgress, TimerCategory};
+use crate::utils::{err, get_value, map_result};
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use gloo_timers::callback::Interval;
+use yew::{html, Component, Context, Html, MouseEvent, Properties};
+
+#[derive(Properties, PartialEq)]
+pub struct GoalsProp {
+    #[prop_or_default]
+    pub compact: bool, // Navbar version, without the list of goals and the form
+}
+
+pub struct Goals {
+    token: Option<String>,
+    progress: Option<Vec<GoalProgress>>,
+    categories: Vec<TimerCategory>,
+    _refresh_handle: Interval,
+}
+
+pub enum Msg {
+    Fetch,
+    Res(
+        Result<Vec<GoalProgress>, Error>,
+        Result<Vec<TimerCategory>, Error>,
+    ),
+    Submit,
+    Delete(i32),
+}
+
+fn format_hours(seconds: u64) -> String {
+    format!("{}h {}min", seconds / 3600, seconds % 3600 / 60)
+}
+
+impl Component for Goals {
+    type Message = Msg;
+    type Properties = GoalsProp;
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let _refresh_handle = {
+            let link = ctx.link().clone();
+            Interval::new(60_000, move || link.send_message(Msg::Fetch))
+        };
+        ctx.link().send_message(Msg::Fetch);
+        Self {
+            token: LocalStorage::get("Token").ok(),
+            progress: None,
+            categories: Vec::new(),
+            _refresh_handle,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let token = match self.token.clone() {
+            Some(token) => token,
+            None => return false,
+        };
+        match msg {
+            Msg::Fetch => {
+                ctx.link().send_future(async move {
+                    let progress = get_goals_progress(token.as_str()).await;
+                    let categories = get_categories(token.as_str()).await;
+                    Msg::Res(progress, categories)
+                });
+                false
+            }
+            Msg::Res(Ok(progress), Ok(categories)) => {
+                self.progress = Some(progress);
+                self.categories = categories;
+                true
+            }
+            Msg::Submit => {
+                let hours = get_value("goalHours").parse::<f64>().unwrap_or(0.0);
+                if hours <= 0.0 {
+                    err("Please enter number of hours");
+                    return false;
+                }
+                let goal = GoalData {
+                    period: get_value("goalPeriod"),
+                    category: map_result(get_value("goalCategory").parse::<i32>()),
+                    target: (hours * 3600.0) as u64,
+                };
+                ctx.link().send_future(async move {
+                    let _ = create_goal(token.as_str(), goal).await;
+                    Msg::Fetch
+                });
+                false
+            }
+            Msg::Delete(id) => {
+                ctx.link().send_future(async move {
+                    let _ = delete_goal(token.as_str(), id).await;
+                    Msg::Fetch
+                });
+                false
+            }
+            _ => false,
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        let progress = match (&self.token, &self.progress) {
+            (Some(_), Some(progress)) => progress,
+            _ => return html! {},
+        };
+        let category_name = |category: Option<i32>| match category {
+            Some(id) => self
+                .categories
+                .iter()
+                .find(|c| c.id == id)
+                .map_or("?".to_owned(), |c| c.name.clone()),
+            None => "All timers".to_owned(),
+        };
+        if ctx.props().compact {
+            let goals = progress.iter().map(|goal| {
+                let percent = (goal.tracked * 100 / goal.goal.target.max(1)).min(100);
+                html! {
+                    <span class="navbar-text" style="padding-right: 10px;" title={category_name(goal.goal.category)}>
+                        {goal.goal.period.clone()}{": "}{percent}{"%"}
+                        if goal.streak > 0 {
+                            {" ("}{goal.streak}{" in a row)"}
+                        }
+                    </span>
+                }
+            });
+            return html! { <>{for goals}</> };
+        }
+        let goals = progress.iter().map(|goal| {
+            let id = goal.goal.id;
+            let percent = (goal.tracked * 100 / goal.goal.target.max(1)).min(100);
+            html! {
+                <div>
+                    <p>
+                        {if goal.goal.period == "day" { "Today" } else { "This week" }}{" - "}{category_name(goal.goal.category)}{": "}
+                        {format_hours(goal.tracked)}{" of "}{format_hours(goal.goal.target)}
+                        {", streak: "}{goal.streak}
+                        <button class="btn btn-link" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Delete(id)})}>{"Delete"}</button>
+                    </p>
+                    <div class="progress">
+                        <div class="progress-bar" role="progressbar" style={format!("width: {}%;", percent)}>{percent}{"%"}</div>
+                    </div>
+                </div>
+            }
+        });
+        let category_options = self.categories.iter().map(|category| {
+            html! {
+                <option value={category.id.to_string()}>{category.name.clone()}</option>
+            }
+        });
+        html! {
+            <div>
+                <h5>{"Goals"}</h5>
+                {for goals}
+                <form>
+                    <div class="form-group">
+                        <label for="goalPeriod">{"Period:"}</label>
+                        <select id="goalPeriod">
+                            <option value="day">{"Day"}</option>
+                            <option value="week">{"Week"}</option>
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="goalCategory">{"Category:"}</label>
+                        <select id="goalCategory">
+                            <option value="None">{"All timers"}</option>
+                            {for category_options}
+                        </select>
+                    </div>
+                    <div class="form-group">
+                        <label for="goalHours">{"Target (hours)"}</label>
+                        <input type="number" class="form-control" id="goalHours" min="0.5" step="0.5"/>
+                    </div>
+                    <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::Submit})}>{"Add goal"}</button>
+                </form>
+            </div>
+        }
+    }
+}
@@ -29,6 +29,10 @@ impl Component for LoginForm {
                     let name = get_value("loginName");
                     let pass = get_value("loginPass");
                     let res = login(name.as_str(), pass.as_str()).await;
+                    // Comments of the user can be edited by them
+                    if res.is_ok() {
+                        let _ = LocalStorage::set("Username", name);
+                    }
                     Self::Message::Res(res)
                 });
                 false
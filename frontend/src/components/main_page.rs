@@ -2,8 +2,8 @@ use super::navbar::Navbar;
 use crate::Route;
 use crate::{
     api::{delete_private, delete_team_board, get_private_boards, get_team_boards, update_board},
-    types::{PrivateBoard, TeamBoard},
-    utils::{get_value, hide_modal, map_token, open_modal, reload, set_value},
+    types::{PrivateBoard, TeamBoard, UpdateResult},
+    utils::{err, get_value, hide_modal, map_token, open_modal, reload, set_value},
 };
 use gloo_net::Error;
 use gloo_storage::{LocalStorage, Storage};
@@ -46,6 +46,7 @@ impl Component for TeamDetails {
                 let id = ctx.props().id.unwrap();
                 set_value("nameBoard", name);
                 set_value("idBoard", id.to_string().as_str());
+                set_value("versionBoard", &ctx.props().version.to_string());
                 set_value("typeBoard", "team");
                 false
             }
@@ -65,6 +66,7 @@ impl Component for TeamDetails {
                     <a href={"board?board_type=team&&id=".to_owned() + ctx.props().id.unwrap().to_string().as_str()} class="btn btn-primary" role="button" aria-pressed="true">{"Open"}</a>
                     <button class="btn btn-danger" onclick={ctx.link().callback(|_: MouseEvent| {Self::Message::Delete})}>{"Delete"}</button>
                     <button class="btn btn-primary" onclick={ctx.link().callback(|_: MouseEvent| {open_modal("myModal"); Self::Message::Update})}>{"Update"}</button>
+                    <a href={format!("roadmap?team={}", ctx.props().owner)} class="btn btn-link" role="button">{"Team roadmap"}</a>
             </div>
             </div>
         }
@@ -101,6 +103,7 @@ impl Component for PrivateDetails {
                 let id = ctx.props().id.unwrap();
                 set_value("nameBoard", name);
                 set_value("idBoard", id.to_string().as_str());
+                set_value("versionBoard", &ctx.props().version.to_string());
                 set_value("typeBoard", "private");
                 false
             }
@@ -175,10 +178,17 @@ impl Component for Main {
             Self::Message::UpdateBoard => {
                 let id = get_value("idBoard").parse::<i32>().unwrap();
                 let name = get_value("nameBoard");
+                let version = get_value("versionBoard").parse::<i32>().unwrap_or_default();
                 let token = self.token.clone().unwrap();
                 let board_type = get_value("typeBoard");
                 ctx.link().send_future(async move {
-                    let _ = update_board(&token, id, name, &board_type).await;
+                    let res = update_board(&token, id, name, version, &board_type).await;
+                    if let Ok(UpdateResult::Conflict(current)) = res {
+                        err(&format!(
+                            "Somebody renamed the board to \"{}\" meanwhile, please try again",
+                            current.name
+                        ));
+                    }
                     Self::Message::Return
                 });
             }
@@ -213,13 +223,13 @@ impl Component for Main {
         let privates = self.private_boards.clone();
         let privates = privates.unwrap().into_iter().map(|private| {
             html! {
-                <PrivateDetails name={private.name.clone()} id={private.id} owner={private.owner} />
+                <PrivateDetails name={private.name.clone()} id={private.id} owner={private.owner} version={private.version} />
             }
         });
 
         let teams = self.team_boards.clone();
         let teams = teams.unwrap().into_iter().map(|team| html! {
-                        <TeamDetails team_name={team.team_name} id={team.id} name={team.name.clone()} owner={team.owner}/>
+                        <TeamDetails team_name={team.team_name} id={team.id} name={team.name.clone()} owner={team.owner} version={team.version}/>
                     });
         html! {
             <>
@@ -243,6 +253,7 @@ impl Component for Main {
                 <div class="form-group">
                     <input type="hidden" class="form-control" id="typeBoard" aria-describedby="usernameHelp" placeholder="Enter assigned people"/>
                 </div>
+                <input type="hidden" id="versionBoard"/>
                 <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::UpdateBoard})}>{"Submit"}</button>
             </form>
             </div>
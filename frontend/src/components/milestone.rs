@@ -1,6 +1,8 @@
-use crate::api::create_milestone;
-use crate::types::{BoardProp, MilestoneCreate};
-use crate::utils::{get_value, reload};
+use crate::api::{
+    create_milestone, delete_milestone, set_milestone_predecessors, update_milestone,
+};
+use crate::types::{BoardProp, MilestoneCreate, MilestonePredecessors, MilestoneUpdate};
+use crate::utils::{err, get_value, is_checked, reload};
 use crate::Route;
 use gloo_storage::{LocalStorage, Storage};
 use yew::{html, Component, Context, Html, MouseEvent};
@@ -8,13 +10,27 @@ use yew_router::prelude::*;
 
 pub struct MilestoneList {
     token: Option<String>,
+    editing: Option<i32>,
 }
 
 pub enum Msg {
     Submit,
+    Edit(Option<i32>),
+    Save(i32),
+    Delete(i32),
     Ok,
 }
 
+/// Value of an optional input, None when it's empty
+fn get_optional(input: &str) -> Option<String> {
+    let value = get_value(input);
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
 impl Component for MilestoneList {
     type Message = Msg;
     type Properties = BoardProp;
@@ -22,8 +38,14 @@ impl Component for MilestoneList {
     fn create(_ctx: &Context<Self>) -> Self {
         let token = LocalStorage::get("Token");
         match token {
-            Ok(key) => Self { token: Some(key) },
-            Err(_) => Self { token: None },
+            Ok(key) => Self {
+                token: Some(key),
+                editing: None,
+            },
+            Err(_) => Self {
+                token: None,
+                editing: None,
+            },
         }
     }
 
@@ -36,6 +58,9 @@ impl Component for MilestoneList {
                     name: get_value("nameMilestone"),
                     board_id: ctx.props().id,
                     board_type: ctx.props().board_type.clone(),
+                    description: get_optional("descriptionMilestone"),
+                    start_date: get_optional("startMilestone"),
+                    due_date: get_optional("dueMilestone"),
                 };
                 ctx.link().send_future(async move {
                     let _ = create_milestone(&token, milestone).await;
@@ -44,6 +69,60 @@ impl Component for MilestoneList {
                 });
                 false
             }
+            Self::Message::Edit(id) => {
+                self.editing = id;
+                true
+            }
+            Self::Message::Save(id) => {
+                let milestone = MilestoneUpdate {
+                    name: get_value("nameMilestoneUpdate"),
+                    description: get_optional("descriptionMilestoneUpdate"),
+                    start_date: get_optional("startMilestoneUpdate"),
+                    due_date: get_optional("dueMilestoneUpdate"),
+                    status: get_value("statusMilestoneUpdate"),
+                };
+                if milestone.name.is_empty() {
+                    err("Please enter name");
+                    return false;
+                }
+                let predecessors = MilestonePredecessors {
+                    predecessors: ctx
+                        .props()
+                        .milestones
+                        .iter()
+                        .flatten()
+                        .filter_map(|milestone| milestone.id)
+                        .filter(|other| is_checked(&format!("predecessor{}", other)) == 1)
+                        .collect(),
+                };
+                ctx.link().send_future(async move {
+                    match update_milestone(&token, id, milestone).await {
+                        Ok(true) => {
+                            match set_milestone_predecessors(&token, id, predecessors).await {
+                                Ok(true) => {
+                                    let _ = reload();
+                                }
+                                _ => {
+                                    err("Milestones can't depend on each other in a cycle");
+                                }
+                            }
+                        }
+                        _ => {
+                            err("Due date can't be before the start date");
+                        }
+                    }
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Delete(id) => {
+                ctx.link().send_future(async move {
+                    let _ = delete_milestone(&token, id).await;
+                    let _ = reload();
+                    Self::Message::Ok
+                });
+                false
+            }
             Self::Message::Ok => true,
         }
     }
@@ -52,26 +131,96 @@ impl Component for MilestoneList {
         match self.token {
             None => html! { <Redirect<Route> to={Route::Login}/> },
             _ => {
-                let milestones = ctx.props().milestones.clone();
-                let milestones = milestones.unwrap().into_iter().map(|milestone| html! {
+                let all = ctx.props().milestones.clone().unwrap();
+                let name = |id: &i32| {
+                    all.iter()
+                        .find(|milestone| milestone.id == Some(*id))
+                        .map(|milestone| milestone.name.clone())
+                        .unwrap_or_default()
+                };
+                let milestones = all.clone().into_iter().map(|milestone| {
+                    let id = milestone.id.unwrap();
+                    if self.editing == Some(id) {
+                        let others = all.iter().filter(|other| other.id != Some(id)).map(|other| {
+                            let other_id = other.id.unwrap();
+                            html! {
+                                <div class="form-check">
+                                    <input class="form-check-input" type="checkbox" id={format!("predecessor{}", other_id)}
+                                        checked={milestone.predecessors.contains(&other_id)}/>
+                                    <label class="form-check-label" for={format!("predecessor{}", other_id)}>{other.name.clone()}</label>
+                                </div>
+                            }
+                        });
+                        return html! {
+                            <div class="card" style="width: 18rem;">
+                                <div class="card-body">
+                                    <input type="text" class="form-control" id="nameMilestoneUpdate" value={milestone.name}/>
+                                    <textarea class="form-control" id="descriptionMilestoneUpdate" value={milestone.description.unwrap_or_default()}/>
+                                    <label for="startMilestoneUpdate">{"Start:"}</label>
+                                    <input type="date" class="form-control" id="startMilestoneUpdate" value={milestone.start_date.unwrap_or_default()}/>
+                                    <label for="dueMilestoneUpdate">{"Due:"}</label>
+                                    <input type="date" class="form-control" id="dueMilestoneUpdate" value={milestone.due_date.unwrap_or_default()}/>
+                                    <select id="statusMilestoneUpdate">
+                                        <option value="open" selected={milestone.status == "open"}>{"Open"}</option>
+                                        <option value="closed" selected={milestone.status == "closed"}>{"Closed"}</option>
+                                    </select>
+                                    <h6>{"After:"}</h6>
+                                    {for others}
+                                    <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Save(id)})}>{"Save"}</button>
+                                    <button class="btn btn-secondary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Edit(None)})}>{"Cancel"}</button>
+                                </div>
+                            </div>
+                        };
+                    }
+                    html! {
                         <div class="card" style="width: 18rem;">
                             <div class="card-body">
-                                <h5 class="card-title">{milestone.name}</h5>
+                                <h5 class="card-title">{milestone.name}{" "}<span class="badge bg-secondary">{milestone.status}</span></h5>
+                                if let Some(description) = milestone.description {
+                                    <p class="card-text">{description}</p>
+                                }
+                                if let Some(start_date) = milestone.start_date {
+                                    <h6 class="card-subtitle mb-2 text-muted">{"Start:"}{start_date}</h6>
+                                }
+                                if let Some(due_date) = milestone.due_date {
+                                    <h6 class="card-subtitle mb-2 text-muted">{"Due:"}{due_date}</h6>
+                                }
+                                if !milestone.predecessors.is_empty() {
+                                    <h6 class="card-subtitle mb-2 text-muted">{"After:"}{milestone.predecessors.iter().map(name).collect::<Vec<_>>().join(", ")}</h6>
+                                }
                                 <h6 class="card-subtitle mb-2 text-muted">{"Done:"}{milestone.done}</h6>
                                 <h6 class="card-subtitle mb-2 text-muted">{"Total:"}{milestone.total}</h6>
+                                <h6 class="card-subtitle mb-2 text-muted">{"Points:"}{milestone.points_done}{" / "}{milestone.points_total}</h6>
+                                <a href={format!("milestone?id={}", id)} class="btn btn-info" role="button">{"Tasks"}</a>
+                                <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Edit(Some(id))})}>{"Edit"}</button>
+                                <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Msg::Delete(id)})}>{"Delete"}</button>
                             </div>
                         </div>
-                    });
+                    }
+                });
                 html! {
                     <div>
                         <div class="col-xs-6" style="padding-left: 80px;">
                             <h1>{"Milestones"}</h1>
+                            <a href={format!("roadmap?board_type={}&&id={}", ctx.props().board_type, ctx.props().id)}>{"Roadmap"}</a>
                             {for milestones}
                             <form>
                                 <div class="form-group">
                                     <label for="nameMilestone">{"Name"}</label>
                                     <input type="text" class="form-control" id="nameMilestone" aria-describedby="emailHelp" placeholder="Enter name"/>
                                 </div>
+                                <div class="form-group">
+                                    <label for="descriptionMilestone">{"Description"}</label>
+                                    <textarea class="form-control" id="descriptionMilestone"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="startMilestone">{"Start date"}</label>
+                                    <input type="date" class="form-control" id="startMilestone"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="dueMilestone">{"Due date"}</label>
+                                    <input type="date" class="form-control" id="dueMilestone"/>
+                                </div>
                                 <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::Submit})}>{"Submit"}</button>
                             </form>
                         </div>
@@ -0,0 +1,166 @@
+use super::burndown::BurndownChart;
+use super::navbar::Navbar;
+use crate::api::{get_milestone_burndown, get_milestone_tasks, set_milestone_tasks};
+use crate::types::{Burndown, MilestoneTasks, MilestoneTasksUpdate, Task};
+use crate::utils::{err, get_parameter, is_checked, map_token};
+use crate::Route;
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use yew::{html, Component, Context, Html, MouseEvent};
+use yew_router::prelude::*;
+
+pub struct MilestonePage {
+    id: i32,
+    token: Option<String>,
+    tasks: Option<MilestoneTasks>,
+    burndown: Option<Burndown>,
+}
+
+pub enum Msg {
+    Fetch,
+    Res(Result<MilestoneTasks, Error>),
+    Chart(Result<Burndown, Error>),
+    Apply,
+}
+
+fn task_row(task: &Task, checkbox: String) -> Html {
+    html! {
+        <tr>
+            <td><input type="checkbox" id={checkbox}/></td>
+            <td>{task.name.clone()}</td>
+            <td>{if task.done == 1 { "Done" } else { "Open" }}</td>
+            <td>{task.members.clone().unwrap_or_default()}</td>
+            <td>{task.points}</td>
+        </tr>
+    }
+}
+
+impl Component for MilestonePage {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        ctx.link().send_message(Msg::Fetch);
+        Self {
+            id: get_parameter("id").parse::<i32>().unwrap_or(0),
+            token: map_token(LocalStorage::get("Token")),
+            tasks: None,
+            burndown: None,
+        }
+    }
+
+    fn update(&mut self, ctx: &Context<Self>, msg: Self::Message) -> bool {
+        let token = match self.token.clone() {
+            Some(token) => token,
+            None => return false,
+        };
+        let id = self.id;
+        match msg {
+            Msg::Fetch => {
+                let chart_token = token.clone();
+                ctx.link().send_future(async move {
+                    let res = get_milestone_tasks(token.as_str(), id).await;
+                    Msg::Res(res)
+                });
+                ctx.link().send_future(async move {
+                    let res = get_milestone_burndown(chart_token.as_str(), id).await;
+                    Msg::Chart(res)
+                });
+                false
+            }
+            Msg::Res(Ok(tasks)) => {
+                self.tasks = Some(tasks);
+                true
+            }
+            Msg::Chart(res) => {
+                self.burndown = res.ok();
+                true
+            }
+            Msg::Res(Err(_)) => {
+                err("Milestone not found");
+                false
+            }
+            Msg::Apply => {
+                let checked = |prefix: &str, tasks: Vec<&Task>| {
+                    tasks
+                        .into_iter()
+                        .filter_map(|task| task.id)
+                        .filter(|id| is_checked(format!("{}{}", prefix, id).as_str()) == 1)
+                        .collect::<Vec<_>>()
+                };
+                let lists = self.tasks.iter().flat_map(|tasks| tasks.lists.iter());
+                let update = MilestoneTasksUpdate {
+                    add: checked(
+                        "add",
+                        lists.clone().flat_map(|l| l.available.iter()).collect(),
+                    ),
+                    remove: checked("remove", lists.flat_map(|l| l.tasks.iter()).collect()),
+                };
+                ctx.link().send_future(async move {
+                    let _ = set_milestone_tasks(token.as_str(), id, update).await;
+                    Msg::Fetch
+                });
+                false
+            }
+        }
+    }
+
+    fn view(&self, ctx: &Context<Self>) -> Html {
+        if self.token.is_none() {
+            return html! { <Redirect<Route> to={Route::Login}/> };
+        }
+        let tasks = match &self.tasks {
+            Some(tasks) => tasks,
+            None => return html! { <Navbar /> },
+        };
+        let milestone = &tasks.milestone;
+        let lists = tasks.lists.iter().map(|list| {
+            let in_milestone = list
+                .tasks
+                .iter()
+                .map(|task| task_row(task, format!("remove{}", task.id.unwrap())));
+            let available = list
+                .available
+                .iter()
+                .map(|task| task_row(task, format!("add{}", task.id.unwrap())));
+            html! {
+                <div>
+                    <h4>{list.list.name.clone()}</h4>
+                    <table class="table">
+                        <tr><th>{"Remove"}</th><th>{"Task"}</th><th>{"Status"}</th><th>{"Assignees"}</th><th>{"Points"}</th></tr>
+                        {for in_milestone}
+                    </table>
+                    if !list.available.is_empty() {
+                        <details>
+                            <summary>{"Other tasks on this list"}</summary>
+                            <table class="table">
+                                <tr><th>{"Add"}</th><th>{"Task"}</th><th>{"Status"}</th><th>{"Assignees"}</th><th>{"Points"}</th></tr>
+                                {for available}
+                            </table>
+                        </details>
+                    }
+                </div>
+            }
+        });
+        html! {
+            <div>
+                <Navbar />
+                <div class="col-xs-6" style="padding-left: 80px;">
+                    <h1>{milestone.name.clone()}{" "}<span class="badge bg-secondary">{milestone.status.clone()}</span></h1>
+                    if let Some(description) = milestone.description.clone() {
+                        <p>{description}</p>
+                    }
+                    <h6 class="text-muted">{"Done:"}{milestone.done}{" / "}{milestone.total}</h6>
+                    <h6 class="text-muted">{"Points:"}{milestone.points_done}{" / "}{milestone.points_total}</h6>
+                    <a href={format!("board?board_type={}&&id={}", milestone.board_type, milestone.board_id)}>{"Back to board"}</a>
+                    if let Some(burndown) = self.burndown.clone() {
+                        <h4>{"Progress"}</h4>
+                        <BurndownChart ..burndown />
+                    }
+                    {for lists}
+                    <button class="btn btn-primary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Apply})}>{"Apply changes"}</button>
+                </div>
+            </div>
+        }
+    }
+}
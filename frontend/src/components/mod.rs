@@ -1,10 +1,18 @@
+pub mod active_timer;
+pub mod billing;
 pub mod board;
+pub mod burndown;
+pub mod comments;
+pub mod goal;
 pub mod login;
 pub mod main_page;
 pub mod milestone;
+pub mod milestone_page;
 pub mod navbar;
 pub mod private_board_create;
 pub mod register;
+pub mod roadmap;
+pub mod search;
 pub mod team_board_create;
 pub mod team_create;
 pub mod timer;
@@ -1,3 +1,5 @@
+use super::active_timer::ActiveTimers;
+use super::goal::Goals;
 use yew::{function_component, html};
 
 #[function_component(Navbar)]
@@ -21,8 +23,16 @@ pub fn app() -> Html {
             <li class="nav-item">
             <a class="nav-link" href="/timers">{"Timers"}</a>
           </li>
+            <li class="nav-item">
+              <a class="nav-link" href="/billing">{"Billing"}</a>
+            </li>
           </ul>
         </div>
+        <form class="d-flex" action="/search" method="get">
+          <input class="form-control" type="search" name="q" placeholder="Search tasks" aria-label="Search"/>
+        </form>
+        <ActiveTimers />
+        <Goals compact={true} />
       </nav>
     }
 }
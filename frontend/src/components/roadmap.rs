@@ -0,0 +1,197 @@
+use super::navbar::Navbar;
+use crate::api::{get_board_roadmap, get_team_roadmap};
+use crate::types::{Milestone, RoadmapItem};
+use crate::utils::{day_number, err, format_date, get_parameter, map_token};
+use crate::Route;
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use yew::{html, Component, Context, Html};
+use yew_router::prelude::*;
+
+pub struct Roadmap {
+    token: Option<String>,
+    roadmap: Option<Vec<RoadmapItem>>,
+}
+
+pub enum Msg {
+    Res(Result<Vec<RoadmapItem>, Error>),
+}
+
+/// Days covered by the milestone, a milestone with a single date is one day long
+fn span(milestone: &Milestone) -> Option<(f64, f64)> {
+    let start = milestone
+        .start_date
+        .as_deref()
+        .or(milestone.due_date.as_deref())?;
+    let end = milestone.due_date.as_deref().unwrap_or(start);
+    Some((day_number(start), day_number(end) + 1.0))
+}
+
+fn date_label(day: f64) -> String {
+    format_date(day * 24.0 * 60.0 * 60.0)
+}
+
+/// Names of the predecessors which are due after the milestone starts
+fn late_predecessors(milestone: &Milestone, roadmap: &[RoadmapItem]) -> Vec<String> {
+    let start = match milestone.start_date.as_deref() {
+        Some(start) => day_number(start),
+        None => return Vec::new(),
+    };
+    roadmap
+        .iter()
+        .map(|item| &item.milestone)
+        .filter(|other| {
+            other
+                .id
+                .map_or(false, |id| milestone.predecessors.contains(&id))
+        })
+        .filter(|other| {
+            other
+                .due_date
+                .as_deref()
+                .map_or(false, |due| day_number(due) >= start)
+        })
+        .map(|other| other.name.clone())
+        .collect()
+}
+
+impl Component for Roadmap {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let token = map_token(LocalStorage::get("Token"));
+        if let Some(token) = token.clone() {
+            let team = get_parameter("team");
+            let board_type = get_parameter("board_type");
+            let id = get_parameter("id").parse::<i32>().unwrap_or(0);
+            ctx.link().send_future(async move {
+                let res = match team.parse::<i32>() {
+                    Ok(team) => get_team_roadmap(&token, team).await,
+                    Err(_) => get_board_roadmap(&token, &board_type, id).await,
+                };
+                Msg::Res(res)
+            });
+        }
+        Self {
+            token,
+            roadmap: None,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Res(Ok(roadmap)) => {
+                self.roadmap = Some(roadmap);
+                true
+            }
+            Msg::Res(Err(_)) => {
+                err("Roadmap not found");
+                false
+            }
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        if self.token.is_none() {
+            return html! { <Redirect<Route> to={Route::Login}/> };
+        }
+        let roadmap = match &self.roadmap {
+            Some(roadmap) => roadmap,
+            None => return html! { <Navbar /> },
+        };
+        let spans = roadmap
+            .iter()
+            .filter_map(|item| span(&item.milestone))
+            .collect::<Vec<_>>();
+        let first = spans.iter().map(|s| s.0).fold(f64::INFINITY, f64::min);
+        let last = spans.iter().map(|s| s.1).fold(f64::NEG_INFINITY, f64::max);
+        let length = (last - first).max(1.0);
+        let names = |milestone: &Milestone| {
+            roadmap
+                .iter()
+                .filter(|item| {
+                    item.milestone
+                        .id
+                        .map_or(false, |id| milestone.predecessors.contains(&id))
+                })
+                .map(|item| item.milestone.name.clone())
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+
+        let rows = roadmap.iter().map(|item| {
+            let milestone = &item.milestone;
+            let progress = if milestone.total > 0 {
+                100 * milestone.done / milestone.total
+            } else {
+                0
+            };
+            let late = late_predecessors(milestone, roadmap);
+            let bar = match span(milestone) {
+                Some((start, end)) => {
+                    let style = format!(
+                        "position: absolute; left: {:.2}%; width: {:.2}%; height: 24px; {}",
+                        (start - first) / length * 100.0,
+                        (end - start) / length * 100.0,
+                        if late.is_empty() { "" } else { "border: 2px solid red;" }
+                    );
+                    html! {
+                        <div class="progress" style={style}>
+                            <div class="progress-bar" role="progressbar" style={format!("width: {}%;", progress)}>
+                                {format!("{}%", progress)}
+                            </div>
+                        </div>
+                    }
+                }
+                None => html! { <span class="text-muted">{"No dates"}</span> },
+            };
+            html! {
+                <tr>
+                    <td style="width: 25%;">
+                        <a href={format!("milestone?id={}", milestone.id.unwrap_or(0))}>{milestone.name.clone()}</a>
+                        {" "}<span class="badge bg-secondary">{milestone.status.clone()}</span>
+                        <div class="text-muted small">{item.board_name.clone()}</div>
+                        if !milestone.predecessors.is_empty() {
+                            <div class="text-muted small">{"After: "}{names(milestone)}</div>
+                        }
+                        if !late.is_empty() {
+                            <div class="text-danger small">{"Starts before "}{late.join(", ")}{" is due"}</div>
+                        }
+                    </td>
+                    <td style="position: relative;">{bar}</td>
+                    <td style="width: 10%;">{milestone.done}{" / "}{milestone.total}</td>
+                </tr>
+            }
+        });
+        let axis = if spans.is_empty() {
+            html! {}
+        } else {
+            html! {
+                <tr>
+                    <th></th>
+                    <th>
+                        <span>{date_label(first)}</span>
+                        <span style="float: right;">{date_label(last - 1.0)}</span>
+                    </th>
+                    <th>{"Done"}</th>
+                </tr>
+            }
+        };
+        html! {
+            <div>
+                <Navbar />
+                <div class="col-xs-6" style="padding-left: 80px; padding-right: 80px;">
+                    <h1>{"Roadmap"}</h1>
+                    if roadmap.is_empty() {
+                        <p class="text-muted">{"No milestones yet"}</p>
+                    }
+                    <table class="table">
+                        {axis}
+                        {for rows}
+                    </table>
+                </div>
+            </div>
+        }
+    }
+}
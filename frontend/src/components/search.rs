@@ -0,0 +1,105 @@
+use super::navbar::Navbar;
+use crate::api::search_tasks;
+use crate::types::SearchResult;
+use crate::utils::{err, get_parameter, map_token};
+use crate::Route;
+use gloo_net::Error;
+use gloo_storage::{LocalStorage, Storage};
+use yew::{html, Component, Context, Html};
+use yew_router::prelude::*;
+
+pub struct Search {
+    token: Option<String>,
+    query: String,
+    results: Option<Vec<SearchResult>>,
+}
+
+pub enum Msg {
+    Res(Result<Vec<SearchResult>, Error>),
+}
+
+/// Snippet with the matched words, which the backend puts in [brackets], highlighted
+fn snippet(text: &str) -> Html {
+    let parts = text
+        .split('[')
+        .enumerate()
+        .map(|(i, part)| match part.split_once(']') {
+            Some((matched, rest)) if i > 0 => html! { <><mark>{matched}</mark>{rest}</> },
+            _ => html! { {part} },
+        });
+    html! { <>{for parts}</> }
+}
+
+impl Component for Search {
+    type Message = Msg;
+    type Properties = ();
+
+    fn create(ctx: &Context<Self>) -> Self {
+        let token = map_token(LocalStorage::get("Token"));
+        let query = get_parameter("q");
+        if let Some(token) = token.clone() {
+            let query = query.clone();
+            ctx.link().send_future(async move {
+                let res = search_tasks(&token, &query).await;
+                Msg::Res(res)
+            });
+        }
+        Self {
+            token,
+            query,
+            results: None,
+        }
+    }
+
+    fn update(&mut self, _ctx: &Context<Self>, msg: Self::Message) -> bool {
+        match msg {
+            Msg::Res(Ok(results)) => {
+                self.results = Some(results);
+                true
+            }
+            Msg::Res(Err(_)) => {
+                err("Search failed");
+                false
+            }
+        }
+    }
+
+    fn view(&self, _ctx: &Context<Self>) -> Html {
+        if self.token.is_none() {
+            return html! { <Redirect<Route> to={Route::Login}/> };
+        }
+        let results = self.results.iter().flatten().map(|result| {
+            html! {
+                <div class="card mb-2">
+                    <div class="card-body">
+                        <h5 class="card-title">
+                            {result.name.clone()}
+                            if result.done == 1 {
+                                {" "}<span class="badge bg-success">{"Done"}</span>
+                            }
+                        </h5>
+                        <h6 class="card-subtitle mb-2 text-muted">
+                            <a href={format!("board?board_type={}&&id={}", result.board_type, result.board_id)}>
+                                {result.board_name.clone().unwrap_or_default()}
+                            </a>
+                            {" / "}{result.list_name.clone()}
+                        </h6>
+                        <p class="card-text">{snippet(&result.snippet)}</p>
+                    </div>
+                </div>
+            }
+        });
+        html! {
+            <div>
+                <Navbar />
+                <div class="col-xs-6" style="padding-left: 80px; padding-right: 80px;">
+                    <h1>{"Search: "}{self.query.clone()}</h1>
+                    if self.results.as_ref().map_or(false, |results| results.is_empty()) {
+                        <p class="text-muted">{"No tasks found"}</p>
+                    }
+                    {for results}
+                </div>
+            </div>
+        }
+    }
+}
@@ -1,18 +1,40 @@
+use super::goal::Goals;
 use super::navbar::Navbar;
-use crate::api::{create_timer, delete_timer, get_timers, update_timer};
-use crate::types::Timer;
-use crate::utils::{err, get_value};
+use crate::api::{
+    create_category, create_timer, delete_category, delete_timer, get_categories,
+    get_private_boards, get_team_boards, get_timer_sessions, get_timer_settings, get_timers,
+    get_timers_report, get_user_teams, pause_timer, review_timer, set_session_billable,
+    set_session_category, set_timer_billing, set_timer_category, start_timer, stop_timer,
+    update_timer_settings,
+};
+use crate::types::{
+    CategoryData, CategoryReport, PomodoroConfig, PrivateBoard, Team, TeamBoard, Timer,
+    TimerBilling, TimerCategory, TimerSession, TimerSettings,
+};
+use crate::utils::{err, get_value, is_checked, map_result, notify};
 use crate::Route;
 use gloo_net::Error;
 use gloo_storage::{LocalStorage, Storage};
 use gloo_timers::callback::Interval;
+use std::collections::HashMap;
+use wasm_timer::{SystemTime, UNIX_EPOCH};
 use yew::{html, Component, Context, Html, MouseEvent};
 use yew_router::prelude::*;
 
 pub struct TimerList {
     token: Option<String>,
     timers: Option<Vec<Timer>>,
+    settings: Option<TimerSettings>,
+    phases: HashMap<i32, String>,
+    categories: Option<Vec<TimerCategory>>,
+    teams: Vec<Team>,
+    boards: Vec<(String, String)>, // Value of the board select and the board name
+    filter: Option<i32>,
+    report: Option<Vec<CategoryReport>>,
+    report_days: Option<u64>,
+    sessions: Option<(i32, Vec<TimerSession>)>,
     _clock_handle: Interval,
+    _sync_handle: Interval,
 }
 
 pub enum Msg {
@@ -20,8 +42,30 @@ pub enum Msg {
     Update,
     Submit,
     Res(Result<Vec<Timer>, Error>),
-    UpdateTimer(i32),
+    Start(i32),
+    Pause(i32),
+    Stop(i32),
     Delete(i32),
+    Settings(Result<TimerSettings, Error>),
+    SaveSettings,
+    Review(i32, u64),
+    Correct(i32),
+    Categories(Result<Vec<TimerCategory>, Error>, Result<Vec<Team>, Error>),
+    Boards(
+        Result<Vec<PrivateBoard>, Error>,
+        Result<Vec<TeamBoard>, Error>,
+    ),
+    SaveBilling(i32),
+    SessionBillable(i32, bool),
+    CreateCategory,
+    DeleteCategory(i32),
+    Filter,
+    AssignTimer(i32),
+    Sessions(i32),
+    SessionsRes(i32, Result<Vec<TimerSession>, Error>),
+    AssignSession(i32),
+    ShowReport,
+    Report(Result<Vec<CategoryReport>, Error>),
     Ok,
 }
 
@@ -35,17 +79,24 @@ impl Component for TimerList {
             let link = ctx.link().clone();
             Interval::new(1000, move || link.send_message(Self::Message::Update))
         };
-        match token {
-            Ok(key) => Self {
-                token: Some(key),
-                timers: None,
-                _clock_handle,
-            },
-            Err(_) => Self {
-                token: None,
-                timers: None,
-                _clock_handle,
-            },
+        let _sync_handle = {
+            let link = ctx.link().clone();
+            Interval::new(5000, move || link.send_message(Self::Message::Fetch))
+        };
+        Self {
+            token: token.ok(),
+            timers: None,
+            settings: None,
+            phases: HashMap::new(),
+            categories: None,
+            teams: Vec::new(),
+            boards: Vec::new(),
+            filter: None,
+            report: None,
+            report_days: None,
+            sessions: None,
+            _clock_handle,
+            _sync_handle,
         }
     }
 
@@ -53,8 +104,9 @@ impl Component for TimerList {
         let token = self.token.clone().unwrap();
         match msg {
             Self::Message::Fetch => {
+                let filter = self.filter;
                 ctx.link().send_future(async move {
-                    let res = get_timers(token.as_str()).await;
+                    let res = get_timers(token.as_str(), filter).await;
                     Self::Message::Res(res)
                 });
                 false
@@ -63,17 +115,164 @@ impl Component for TimerList {
                 self.timers = Some(res);
                 true
             }
-            Self::Message::Update => true,
-            Self::Message::UpdateTimer(x) => {
+            Self::Message::Update => {
+                for timer in self.timers.iter().flatten() {
+                    if let Some((phase, _, _)) = timer.pomodoro_phase() {
+                        let previous = self.phases.insert(timer.id, phase.clone());
+                        if previous.is_some() && previous.unwrap() != phase {
+                            notify(format!("{}: {}", timer.name, phase).as_str());
+                        }
+                    }
+                }
+                true
+            }
+            Self::Message::Start(x) => {
                 ctx.link().send_future(async move {
-                    let _ = update_timer(token.as_str(), x).await;
+                    let _ = start_timer(token.as_str(), x).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Pause(x) => {
+                ctx.link().send_future(async move {
+                    let _ = pause_timer(token.as_str(), x).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Stop(x) => {
+                ctx.link().send_future(async move {
+                    let _ = stop_timer(token.as_str(), x).await;
                     Self::Message::Ok
                 });
-
                 false
             }
             Self::Message::Ok => {
                 self.timers = None;
+                self.settings = None;
+                self.categories = None;
+                self.report = None;
+                if let Some((id, _)) = self.sessions {
+                    ctx.link().send_message(Self::Message::Sessions(id));
+                }
+                true
+            }
+            Self::Message::Categories(Ok(categories), Ok(teams)) => {
+                self.categories = Some(categories);
+                self.teams = teams;
+                true
+            }
+            Self::Message::Boards(Ok(private_boards), Ok(team_boards)) => {
+                self.boards = private_boards
+                    .into_iter()
+                    .map(|board| (format!("private:{}", board.id.unwrap()), board.name))
+                    .chain(team_boards.into_iter().map(|board| {
+                        (
+                            format!("team:{}", board.id.unwrap()),
+                            format!("{} ({})", board.name, board.team_name),
+                        )
+                    }))
+                    .collect();
+                true
+            }
+            Self::Message::SaveBilling(id) => {
+                let client = get_value(format!("client{}", id).as_str());
+                let board = get_value(format!("board{}", id).as_str());
+                let (board_type, board_id) = match board.split_once(':') {
+                    Some((board_type, board_id)) => {
+                        (Some(board_type.to_owned()), board_id.parse::<i32>().ok())
+                    }
+                    None => (None, None),
+                };
+                let billing = TimerBilling {
+                    billable: is_checked(format!("billable{}", id).as_str()) == 1,
+                    client: if client.is_empty() {
+                        None
+                    } else {
+                        Some(client)
+                    },
+                    board_id,
+                    board_type,
+                };
+                ctx.link().send_future(async move {
+                    let _ = set_timer_billing(token.as_str(), id, billing).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::SessionBillable(id, billable) => {
+                ctx.link().send_future(async move {
+                    let _ = set_session_billable(token.as_str(), id, billable).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::CreateCategory => {
+                let name = get_value("categoryName");
+                let color = get_value("categoryColor");
+                if name.is_empty() {
+                    err("Please enter name");
+                    return false;
+                }
+                let team = map_result(get_value("categoryTeam").parse::<i32>());
+                ctx.link().send_future(async move {
+                    let _ =
+                        create_category(token.as_str(), CategoryData { name, color, team }).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::DeleteCategory(id) => {
+                if self.filter == Some(id) {
+                    self.filter = None;
+                }
+                ctx.link().send_future(async move {
+                    let _ = delete_category(token.as_str(), id).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Filter => {
+                self.filter = map_result(get_value("categoryFilter").parse::<i32>());
+                self.timers = None;
+                true
+            }
+            Self::Message::AssignTimer(id) => {
+                let category =
+                    map_result(get_value(format!("category{}", id).as_str()).parse::<i32>());
+                ctx.link().send_future(async move {
+                    let _ = set_timer_category(token.as_str(), id, category).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Sessions(id) => {
+                ctx.link().send_future(async move {
+                    let res = get_timer_sessions(token.as_str(), id).await;
+                    Self::Message::SessionsRes(id, res)
+                });
+                false
+            }
+            Self::Message::SessionsRes(id, Ok(sessions)) => {
+                self.sessions = Some((id, sessions));
+                true
+            }
+            Self::Message::AssignSession(id) => {
+                let category =
+                    map_result(get_value(format!("session{}", id).as_str()).parse::<i32>());
+                ctx.link().send_future(async move {
+                    let _ = set_session_category(token.as_str(), id, category).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::ShowReport => {
+                self.report_days = get_value("reportDays").parse::<u64>().ok();
+                self.report = None;
+                true
+            }
+            Self::Message::Report(Ok(report)) => {
+                self.report = Some(report);
                 true
             }
             Self::Message::Delete(x) => {
@@ -83,10 +282,67 @@ impl Component for TimerList {
                 });
                 false
             }
+            Self::Message::Settings(Ok(settings)) => {
+                self.settings = Some(settings);
+                true
+            }
+            Self::Message::SaveSettings => {
+                let hours = get_value("idleLimit").parse::<u64>().unwrap_or(0);
+                if hours == 0 {
+                    err("Please enter number of hours");
+                    return false;
+                }
+                let idle_limit = hours * 3600;
+                ctx.link().send_future(async move {
+                    let _ = update_timer_settings(token.as_str(), idle_limit).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Review(id, session) => {
+                ctx.link().send_future(async move {
+                    let _ = review_timer(token.as_str(), id, session).await;
+                    Self::Message::Ok
+                });
+                false
+            }
+            Self::Message::Correct(id) => {
+                let minutes = get_value(format!("review{}", id).as_str()).parse::<u64>();
+                // Up to a day, like the backend accepts
+                if !matches!(minutes, Ok(minutes) if minutes <= 24 * 60) {
+                    err("Please enter number of minutes, up to a day");
+                    return false;
+                }
+                ctx.link()
+                    .send_message(Self::Message::Review(id, minutes.unwrap() * 60));
+                false
+            }
             Self::Message::Submit => {
                 let name = get_value("name");
+                let pomodoro = if is_checked("pomodoro") == 1 {
+                    let minutes = ["work", "shortBreak", "longBreak"]
+                        .map(|input| get_value(input).parse::<u64>().unwrap_or(0) * 60);
+                    let cycles = get_value("cycles").parse::<u64>().unwrap_or(0);
+                    // Phases up to a day and up to 12 cycles, like the backend accepts
+                    if minutes.iter().any(|&m| m == 0 || m > 24 * 60 * 60)
+                        || !(1..=12).contains(&cycles)
+                    {
+                        err("Please fill in the pomodoro settings, phases up to 24 hours and up to 12 cycles");
+                        return false;
+                    }
+                    // Asks for the permission, phase changes are announced with notifications
+                    notify("Pomodoro started");
+                    Some(PomodoroConfig {
+                        work: minutes[0],
+                        short_break: minutes[1],
+                        long_break: minutes[2],
+                        cycles,
+                    })
+                } else {
+                    None
+                };
                 ctx.link().send_future(async move {
-                    let _ = create_timer(token.as_str(), name.as_str()).await;
+                    let _ = create_timer(token.as_str(), name.as_str(), pomodoro).await;
                     Self::Message::Ok
                 });
                 false
@@ -105,37 +361,246 @@ impl Component for TimerList {
                 if self.timers.is_none() {
                     ctx.link().send_message(Self::Message::Fetch);
                     html! {}
+                } else if self.settings.is_none() {
+                    let token = self.token.clone().unwrap();
+                    ctx.link().send_future(async move {
+                        let res = get_timer_settings(token.as_str()).await;
+                        Self::Message::Settings(res)
+                    });
+                    html! {}
+                } else if self.categories.is_none() {
+                    let token = self.token.clone().unwrap();
+                    ctx.link().send_future(async move {
+                        let categories = get_categories(token.as_str()).await;
+                        let teams = get_user_teams(token.as_str()).await;
+                        Self::Message::Categories(categories, teams)
+                    });
+                    let token = self.token.clone().unwrap();
+                    ctx.link().send_future(async move {
+                        let private_boards = get_private_boards(token.as_str()).await;
+                        let team_boards = get_team_boards(token.as_str()).await;
+                        Self::Message::Boards(private_boards, team_boards)
+                    });
+                    html! {}
+                } else if self.report.is_none() {
+                    let token = self.token.clone().unwrap();
+                    let from = self.report_days.map(|days| {
+                        let now = SystemTime::now()
+                            .duration_since(UNIX_EPOCH)
+                            .expect("Time went backwards");
+                        now.as_secs().saturating_sub(days * 24 * 3600)
+                    });
+                    ctx.link().send_future(async move {
+                        let res = get_timers_report(token.as_str(), from).await;
+                        Self::Message::Report(res)
+                    });
+                    html! {}
                 } else {
+                    let categories = self.categories.clone().unwrap();
+                    let categories_ref = &categories;
+                    let category_options = move |selected: Option<i32>| {
+                        categories_ref.iter().map(move |category| html! {
+                            <option value={category.id.to_string()} selected={selected == Some(category.id)}>{category.name.clone()}</option>
+                        })
+                    };
+                    let boards = &self.boards;
+                    let board_options = move |selected: Option<String>| {
+                        boards.iter().map(move |(value, name)| html! {
+                            <option value={value.clone()} selected={selected.as_ref() == Some(value)}>{name.clone()}</option>
+                        })
+                    };
+                    let category_color = |category: Option<i32>| {
+                        categories
+                            .iter()
+                            .find(|c| Some(c.id) == category)
+                            .map_or("#6c757d".to_owned(), |c| c.color.clone())
+                    };
+                    let idle_limit = self.settings.clone().unwrap().idle_limit / 3600;
                     let timers = self.timers.clone();
                     let timers = timers.unwrap().into_iter().map(|timer| html! {
-                        <div class="card" style="width: 18rem;">
+                        <div class="card" style={format!("width: 18rem; border-left: 6px solid {};", category_color(timer.category))}>
                             <div class="card-body">
                                 <h5 class="card-title">{timer.name.clone()}</h5>
                                 <h6 class="card-subtitle mb-2 text-muted">{"Time:"}{timer.get_time()}</h6>
+                                if timer.kind == "pomodoro" {
+                                    <h6 class="card-subtitle mb-2 text-muted">{"Completed pomodoros:"}{timer.pomodoros + timer.pomodoro_phase().map_or(0, |(_, _, completed)| completed)}</h6>
+                                }
+                                if let Some((phase, remaining, _)) = timer.pomodoro_phase() {
+                                    <h6 class="card-subtitle mb-2 text-muted">{"Phase:"}{phase}{" ("}{remaining / 60}{":"}{format!("{:02}", remaining % 60)}{" left)"}</h6>
+                                }
+                                if timer.review > 0 {
+                                    <div class="alert alert-warning">
+                                        <p>{"Stopped automatically after "}{timer.review / 3600}{"h "}{timer.review % 3600 / 60}{"min. Did you really work this long?"}</p>
+                                        <input type="number" class="form-control" id={format!("review{}", timer.id)} min="0" max="1440" placeholder="Minutes actually worked"/>
+                                        <button class="btn btn-success" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Review(timer.id, timer.review)})}>{"Yes, keep it"}</button>
+                                        <button class="btn btn-primary" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Correct(timer.id)})}>{"Correct"}</button>
+                                    </div>
+                                }
+                                <div class="form-group">
+                                    <select id={format!("category{}", timer.id)}>
+                                        <option value="None">{"Uncategorized"}</option>
+                                        {for category_options(timer.category)}
+                                    </select>
+                                    <button class="btn btn-secondary" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::AssignTimer(timer.id)})}>{"Set category"}</button>
+                                </div>
+                                <div class="form-group">
+                                    <input type="checkbox" id={format!("billable{}", timer.id)} checked={timer.billable == 1}/>
+                                    <label for={format!("billable{}", timer.id)}>{"Billable"}</label>
+                                    <input type="text" class="form-control" id={format!("client{}", timer.id)} placeholder="Client" value={timer.client.clone().unwrap_or_default()}/>
+                                    <select id={format!("board{}", timer.id)}>
+                                        <option value="None">{"No board"}</option>
+                                        {for board_options(timer.board_type.clone().zip(timer.board_id).map(|(board_type, id)| format!("{}:{}", board_type, id)))}
+                                    </select>
+                                    <button class="btn btn-secondary" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::SaveBilling(timer.id)})}>{"Save billing"}</button>
+                                </div>
+                                <button class="btn btn-info" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Sessions(timer.id)})}>{"Sessions"}</button>
                                 <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Delete(timer.id)})}>{"Delete"}</button>
-                                if timer.status == "stopped" {
-                                    <button class="btn btn-success" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::UpdateTimer(timer.id)})}>{"Start"}</button>
+                                if timer.status == "active" {
+                                    <button class="btn btn-warning" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Pause(timer.id)})}>{"Pause"}</button>
                                 }
                                 else {
-                                    <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::UpdateTimer(timer.id)})}>{"Stop"}</button>
+                                    <button class="btn btn-success" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Start(timer.id)})}>{"Start"}</button>
+                                }
+                                if timer.status != "stopped" {
+                                    <button class="btn btn-danger" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::Stop(timer.id)})}>{"Stop"}</button>
                                 }
                             </div>
                         </div>
                     });
 
+                    let sessions = self.sessions.clone().map(|(id, sessions)| {
+                        let rows = sessions.into_iter().map(|session| html! {
+                            <tr>
+                                <td>{session.start}</td>
+                                <td>{session.duration / 60}{"min"}</td>
+                                <td>
+                                    <select id={format!("session{}", session.id)}>
+                                        <option value="None">{"Uncategorized"}</option>
+                                        {for category_options(session.category)}
+                                    </select>
+                                    <button class="btn btn-secondary" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::AssignSession(session.id)})}>{"Set category"}</button>
+                                </td>
+                                <td>
+                                    <button class="btn btn-link" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::SessionBillable(session.id, session.billable == 0)})}>
+                                        {if session.billable == 1 { "Billable" } else { "Not billable" }}
+                                    </button>
+                                </td>
+                            </tr>
+                        });
+                        html! {
+                            <div>
+                                <h5>{"Sessions of timer "}{id}</h5>
+                                <table class="table">
+                                    <tr><th>{"Start"}</th><th>{"Duration"}</th><th>{"Category"}</th><th>{"Billing"}</th></tr>
+                                    {for rows}
+                                </table>
+                            </div>
+                        }
+                    });
+                    let report = self.report.clone().unwrap().into_iter().map(|row| html! {
+                        <tr>
+                            <td><span style={format!("color: {};", row.color)}>{"\u{25A0} "}</span>{row.name}</td>
+                            <td>{row.time / 3600}{"h "}{row.time % 3600 / 60}{"min"}</td>
+                        </tr>
+                    });
+                    let category_list = categories.iter().map(|category| {
+                        let id = category.id;
+                        html! {
+                            <li style={format!("color: {};", category.color)}>
+                                {category.name.clone()}
+                                if category.team_id.is_some() {
+                                    {" (team)"}
+                                }
+                                <button class="btn btn-link" onclick={ctx.link().callback(move |_: MouseEvent| {Self::Message::DeleteCategory(id)})}>{"Delete"}</button>
+                            </li>
+                        }
+                    });
+                    let team_options = self.teams.iter().map(|team| {
+                        html! {
+                            <option value={team.id.to_string()}>{team.name.clone()}</option>
+                        }
+                    });
                     html! {
                         <div>
                             <Navbar />
+                            <div class="form-group">
+                                <label for="categoryFilter">{"Category:"}</label>
+                                <select id="categoryFilter">
+                                    <option value="None">{"All"}</option>
+                                    {for category_options(self.filter)}
+                                </select>
+                                <button class="btn btn-secondary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::Filter})}>{"Filter"}</button>
+                            </div>
                             <div class="col-xs-6" style="padding-left: 80px;">
                                 {for timers}
                             </div>
+                            {for sessions}
+                            <Goals />
+                            <h5>{"Time by category"}</h5>
+                            <div class="form-group">
+                                <label for="reportDays">{"Last days (empty for all time)"}</label>
+                                <input type="number" class="form-control" id="reportDays" min="1"/>
+                                <button class="btn btn-secondary" onclick={ctx.link().callback(|_: MouseEvent| {Msg::ShowReport})}>{"Show"}</button>
+                            </div>
+                            <table class="table">
+                                {for report}
+                            </table>
+                            <h5>{"Categories"}</h5>
+                            <ul>
+                                {for category_list}
+                            </ul>
+                            <form>
+                                <div class="form-group">
+                                    <label for="categoryName">{"Name"}</label>
+                                    <input type="text" class="form-control" id="categoryName" placeholder="Enter category name"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="categoryColor">{"Color"}</label>
+                                    <input type="color" class="form-control" id="categoryColor" value="#0d6efd"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="categoryTeam">{"Shared with team:"}</label>
+                                    <select id="categoryTeam">
+                                        <option value="None">{"Only me"}</option>
+                                        {for team_options}
+                                    </select>
+                                </div>
+                                <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::CreateCategory})}>{"Add category"}</button>
+                            </form>
                             <form>
                                 <div class="form-group">
                                     <label for="exampleInputEmail1">{"Name"}</label>
                                     <input type="text" class="form-control" id="name" aria-describedby="emailHelp" placeholder="Enter name"/>
                                 </div>
+                                <div class="form-group">
+                                    <input type="checkbox" id="pomodoro" name="Pomodoro" value="yes"/>
+                                    <label for="pomodoro">{"Pomodoro"}</label>
+                                </div>
+                                <div class="form-group">
+                                    <label for="work">{"Work (minutes)"}</label>
+                                    <input type="number" class="form-control" id="work" min="1" max="1440" value="25"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="shortBreak">{"Short break (minutes)"}</label>
+                                    <input type="number" class="form-control" id="shortBreak" min="1" max="1440" value="5"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="longBreak">{"Long break (minutes)"}</label>
+                                    <input type="number" class="form-control" id="longBreak" min="1" max="1440" value="15"/>
+                                </div>
+                                <div class="form-group">
+                                    <label for="cycles">{"Work phases before a long break"}</label>
+                                    <input type="number" class="form-control" id="cycles" min="1" max="12" value="4"/>
+                                </div>
                                 <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::Submit})}>{"Submit"}</button>
                             </form>
+                            <form>
+                                <div class="form-group">
+                                    <label for="idleLimit">{"Stop timers automatically after (hours)"}</label>
+                                    <input type="number" class="form-control" id="idleLimit" min="1" value={idle_limit.to_string()}/>
+                                </div>
+                                <button type="submit" class="btn btn-primary" onclick={ctx.link().callback(|e: MouseEvent| {e.prevent_default(); Msg::SaveSettings})}>{"Save"}</button>
+                            </form>
                         </div>
                     }
                 }
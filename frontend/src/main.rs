@@ -1,8 +1,12 @@
+use components::billing::Billing;
 use components::board::Board;
 use components::login::LoginForm;
 use components::main_page::Main;
+use components::milestone_page::MilestonePage;
 use components::private_board_create::PrivateBoardCreate;
 use components::register::RegisterForm;
+use components::roadmap::Roadmap;
+use components::search::Search;
 use components::team_board_create::TeamBoardCreate;
 use components::team_create::TeamCreate;
 use components::timer::TimerList;
@@ -33,6 +37,14 @@ enum Route {
     Board,
     #[at("timers")]
     TimerList,
+    #[at("billing")]
+    Billing,
+    #[at("milestone")]
+    Milestone,
+    #[at("roadmap")]
+    Roadmap,
+    #[at("search")]
+    Search,
     #[not_found]
     #[at("/")]
     NotFound,
@@ -57,6 +69,10 @@ fn switch(routes: &Route) -> Html {
         Route::TeamBoardCreate => html!(<TeamBoardCreate />),
         Route::Board => html!(<Board />),
         Route::TimerList => html!(<TimerList />),
+        Route::Billing => html!(<Billing />),
+        Route::Milestone => html!(<MilestonePage />),
+        Route::Roadmap => html!(<Roadmap />),
+        Route::Search => html!(<Search />),
         Route::NotFound => html!(<Main />),
     }
 }
@@ -2,7 +2,7 @@ use serde::{Deserialize, Serialize};
 use wasm_timer::{SystemTime, UNIX_EPOCH};
 use yew::Properties;
 
-use crate::utils::set_value;
+use crate::utils::{encode_uri_component, get_parameter, set_checked, set_unchecked, set_value};
 
 #[derive(Clone, PartialEq, Deserialize, Debug)]
 pub struct LoginResponse {
@@ -16,6 +16,9 @@ pub struct MilestoneCreate {
     pub name: String,
     pub board_id: i32,
     pub board_type: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub due_date: Option<String>,
 }
 
 #[derive(Clone, PartialEq, Serialize, Deserialize, Debug)]
@@ -24,8 +27,114 @@ pub struct Milestone {
     pub name: String,
     pub done: i32,
     pub total: i32,
+    pub points_done: i32,
+    pub points_total: i32,
+    pub predecessors: Vec<i32>,
     pub board_id: i32,
     pub board_type: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub due_date: Option<String>,
+    pub status: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ListTasks {
+    pub list: List,
+    pub tasks: Vec<Task>,
+    pub available: Vec<Task>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct MilestoneTasks {
+    pub milestone: Milestone,
+    pub lists: Vec<ListTasks>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BurndownDay {
+    pub date: String,
+    pub total_points: i32,
+    pub done_points: i32,
+    pub remaining_points: i32,
+    pub total_tasks: i32,
+    pub done_tasks: i32,
+    pub remaining_tasks: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct ScopeChange {
+    pub date: String,
+    pub task_id: i32,
+    pub name: String,
+    pub tasks: i32,
+    pub points: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct IdealPoint {
+    pub date: String,
+    pub points: f64,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq, Properties)]
+pub struct Burndown {
+    pub start_date: String,
+    pub due_date: Option<String>,
+    pub days: Vec<BurndownDay>,
+    pub scope_changes: Vec<ScopeChange>,
+    pub ideal: Vec<IdealPoint>,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SearchResult {
+    pub task_id: i32,
+    pub name: String,
+    pub done: i32,
+    pub list_id: i32,
+    pub list_name: String,
+    pub board_id: i32,
+    pub board_type: String,
+    pub board_name: Option<String>,
+    pub snippet: String,
+    pub rank: f64,
+}
+
+#[derive(Serialize)]
+pub struct MilestonePredecessors {
+    pub predecessors: Vec<i32>,
+}
+
+#[derive(Serialize)]
+pub struct TaskBlockers {
+    pub blockers: Vec<i32>,
+}
+
+#[derive(Serialize)]
+pub struct RecurrenceData {
+    pub rule: String,
+    pub list: i32,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct RoadmapItem {
+    pub board_name: String,
+    pub milestone: Milestone,
+}
+
+#[derive(Serialize)]
+pub struct MilestoneTasksUpdate {
+    pub add: Vec<i32>,
+    pub remove: Vec<i32>,
+}
+
+#[derive(Serialize)]
+pub struct MilestoneUpdate {
+    pub name: String,
+    pub description: Option<String>,
+    pub start_date: Option<String>,
+    pub due_date: Option<String>,
+    pub status: String,
 }
 
 #[derive(Deserialize, Serialize)]
@@ -64,6 +173,8 @@ pub struct TeamBoard {
     pub name: String,
     pub owner: i32,
     pub team_name: String,
+    #[serde(default)]
+    pub version: i32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Properties)]
@@ -71,11 +182,35 @@ pub struct PrivateBoard {
     pub id: Option<i32>,
     pub name: String,
     pub owner: i32,
+    #[serde(default)]
+    pub version: i32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Properties)]
 pub struct BoardUpdate {
     pub name: String,
+    pub version: i32, // The version that was edited
+}
+
+/// Board as the backend has it when a rename conflicts
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BoardVersion {
+    pub name: String,
+    pub version: i32,
+}
+
+/// Outcome of an update naming the version it was made to
+/// Invalid field of a request
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UpdateResult<T> {
+    Updated,
+    Conflict(T), // Somebody changed it meanwhile, this is the current version
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Properties)]
@@ -86,7 +221,7 @@ pub struct List {
     pub board_type: String,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
 pub struct Task {
     pub id: Option<i32>,
     pub name: String,
@@ -100,6 +235,183 @@ pub struct Task {
     pub tags: String,
     pub done: i32,
     pub milestone: Option<i32>,
+    #[serde(default)]
+    pub version: i32,
+    #[serde(default)]
+    pub parent: Option<i32>, // Epic the task belongs to
+    #[serde(default, skip_serializing)]
+    pub attachments: Vec<Attachment>, // Sent by the backend, changed by their own endpoints
+    #[serde(default, skip_serializing)]
+    pub blocked_by: Vec<i32>,
+    #[serde(default, skip_serializing)]
+    pub blocks: Vec<i32>,
+    #[serde(default, skip_serializing)]
+    pub blocked: bool, // Some of `blocked_by` aren't done
+    #[serde(default, skip_serializing)]
+    pub children: Rollup,
+    #[serde(default, skip_serializing)]
+    pub recurrence: Option<Recurrence>, // Set on the latest instance of a series
+}
+
+/// Series of a recurring task, the backend creates the next instance once it's done or due
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Recurrence {
+    pub id: Option<i32>,
+    pub task: i32,
+    pub rule: String, // RRULE subset, like FREQ=WEEKLY;BYDAY=MO
+    pub list: i32,
+    pub count: i32,
+}
+
+/// Progress of the task's children
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct Rollup {
+    pub total: i32,
+    pub done: i32,
+    pub points_total: i32,
+    pub points_done: i32,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Attachment {
+    pub id: Option<i32>,
+    pub task_id: i32,
+    pub name: String,
+    pub content_type: String,
+    pub size: i64,
+    pub uploader: i32,
+    pub created: String,
+}
+
+impl Attachment {
+    pub fn is_image(&self) -> bool {
+        self.content_type.starts_with("image/")
+    }
+}
+
+impl Task {
+    /// Fields which differ between the versions, with their values in both
+    pub fn differences(&self, theirs: &Task) -> Vec<(&'static str, String, String)> {
+        let text = |value: &Option<String>| value.clone().unwrap_or_default();
+        let milestone = |value: Option<i32>| value.map(|id| id.to_string()).unwrap_or_default();
+        let done = |value: i32| if value == 1 { "Yes" } else { "No" }.to_string();
+        [
+            ("name", self.name.clone(), theirs.name.clone()),
+            ("list", self.list.to_string(), theirs.list.to_string()),
+            ("note", text(&self.note), text(&theirs.note)),
+            ("place", text(&self.place), text(&theirs.place)),
+            ("members", text(&self.members), text(&theirs.members)),
+            ("deadline", self.deadline.clone(), theirs.deadline.clone()),
+            ("subtasks", self.subtasks.clone(), theirs.subtasks.clone()),
+            ("points", self.points.to_string(), theirs.points.to_string()),
+            ("tags", self.tags.clone(), theirs.tags.clone()),
+            ("done", done(self.done), done(theirs.done)),
+            (
+                "milestone",
+                milestone(self.milestone),
+                milestone(theirs.milestone),
+            ),
+            ("parent", milestone(self.parent), milestone(theirs.parent)),
+        ]
+        .into_iter()
+        .filter(|(_, mine, theirs)| mine != theirs)
+        .collect()
+    }
+
+    /// This task with the chosen fields taken from theirs, on top of their version
+    pub fn merge(&self, theirs: &Task, take_theirs: impl Fn(&str) -> bool) -> Task {
+        macro_rules! pick {
+            ($field:ident) => {
+                if take_theirs(stringify!($field)) {
+                    theirs.$field.clone()
+                } else {
+                    self.$field.clone()
+                }
+            };
+        }
+        Task {
+            id: theirs.id,
+            name: pick!(name),
+            list: pick!(list),
+            note: pick!(note),
+            place: pick!(place),
+            members: pick!(members),
+            deadline: pick!(deadline),
+            subtasks: pick!(subtasks),
+            points: pick!(points),
+            tags: pick!(tags),
+            done: pick!(done),
+            milestone: pick!(milestone),
+            version: theirs.version,
+            attachments: theirs.attachments.clone(),
+            blocked_by: theirs.blocked_by.clone(),
+            blocks: theirs.blocks.clone(),
+            blocked: theirs.blocked,
+            parent: pick!(parent),
+            children: theirs.children.clone(),
+            recurrence: theirs.recurrence.clone(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SnapshotCounts {
+    pub lists: usize,
+    pub tasks: usize,
+    pub done: usize,
+    pub points: i32,
+    pub milestones: usize,
+}
+
+/// Everything needed to show a board
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct Snapshot {
+    pub board_type: String,
+    pub board_id: i32,
+    pub name: String,
+    pub lists: Vec<List>,
+    pub tasks: Vec<Task>,
+    pub milestones: Vec<Milestone>,
+    pub counts: SnapshotCounts,
+}
+
+/// Change of a board's task, list, milestone or comment, or a reset when the board has to be
+/// reloaded
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct BoardEvent {
+    pub id: u64,
+    pub kind: String,
+    pub action: String,
+    pub entity: i32,
+    pub data: Option<serde_json::Value>, // The entity after the change, None when it was deleted
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct Comment {
+    pub id: i32,
+    pub task_id: i32,
+    pub parent: Option<i32>,
+    pub author: i32,
+    pub author_name: String,
+    pub body: String,
+    pub mentions: Vec<String>, // Mentioned users, the other `@names` are plain text
+    pub created: String,
+    pub edited: Option<String>,
+    pub deleted: bool, // Kept without its text for the replies
+    pub replies: Vec<Comment>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CommentData {
+    pub body: String,
+    pub parent: Option<i32>,
+}
+
+/// Comment in the board's activity
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub struct CommentActivity {
+    pub task_name: String,
+    pub comment: Comment,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
@@ -112,6 +424,35 @@ pub struct TaskFilter {
     pub points_min: Option<i32>,
     pub points_max: Option<i32>,
     pub tags: String,
+    #[serde(default)]
+    pub query: String,
+    pub done: Option<bool>,
+    pub milestone: Option<i32>,
+    #[serde(default)]
+    pub overdue: bool,
+    #[serde(default)]
+    pub no_deadline: bool,
+    #[serde(default)]
+    pub unassigned: bool,
+    #[serde(default)]
+    pub open_subtasks: bool,
+    #[serde(default)]
+    pub members_any: bool,
+    #[serde(default)]
+    pub tags_any: bool,
+    #[serde(default)]
+    pub blocked: Option<bool>,
+    #[serde(default)]
+    pub epic: Option<bool>,
+    #[serde(default)]
+    pub parent: Option<i32>,
+}
+
+/// Why the backend rejected the filter query
+#[derive(Deserialize, Debug)]
+pub struct ParseError {
+    pub position: usize,
+    pub message: String,
 }
 
 impl TaskFilter {
@@ -127,14 +468,127 @@ impl TaskFilter {
             set_value("pointsMin", "");
         }
         if self.points_max.is_some() {
-            set_value("pointsMin", self.points_max.unwrap().to_string().as_str());
+            set_value("pointsMax", self.points_max.unwrap().to_string().as_str());
         } else {
-            set_value("pointsMin", "");
+            set_value("pointsMax", "");
         }
         set_value("tagsFilter", self.tags.clone().as_str());
+        set_value("queryFilter", self.query.as_str());
+        let done = self.done.map(|done| done.to_string()).unwrap_or_default();
+        set_value("doneFilter", &done);
+        let milestone = self.milestone.map(|id| id.to_string()).unwrap_or_default();
+        set_value("milestoneFilter", &milestone);
+        let blocked = self
+            .blocked
+            .map(|blocked| blocked.to_string())
+            .unwrap_or_default();
+        set_value("blockedFilter", &blocked);
+        let epic = self.epic.map(|epic| epic.to_string()).unwrap_or_default();
+        set_value("epicFilter", &epic);
+        for (id, checked) in [
+            ("overdueFilter", self.overdue),
+            ("noDeadlineFilter", self.no_deadline),
+            ("unassignedFilter", self.unassigned),
+            ("openSubtasksFilter", self.open_subtasks),
+            ("membersAnyFilter", self.members_any),
+            ("tagsAnyFilter", self.tags_any),
+        ] {
+            if checked {
+                set_checked(id);
+            } else {
+                set_unchecked(id);
+            }
+        }
+    }
+
+    /// Filter stored in the page's query string, None if there is none
+    pub fn from_query() -> Option<TaskFilter> {
+        let filter = TaskFilter {
+            name: get_parameter("name"),
+            place: get_parameter("place"),
+            members: get_parameter("members"),
+            deadline_start: get_parameter("deadline_start"),
+            deadline_end: get_parameter("deadline_end"),
+            points_min: get_parameter("points_min").parse::<i32>().ok(),
+            points_max: get_parameter("points_max").parse::<i32>().ok(),
+            tags: get_parameter("tags"),
+            query: get_parameter("query"),
+            done: get_parameter("done").parse::<bool>().ok(),
+            milestone: get_parameter("milestone").parse::<i32>().ok(),
+            overdue: get_parameter("overdue") == "true",
+            no_deadline: get_parameter("no_deadline") == "true",
+            unassigned: get_parameter("unassigned") == "true",
+            open_subtasks: get_parameter("open_subtasks") == "true",
+            members_any: get_parameter("members_any") == "true",
+            tags_any: get_parameter("tags_any") == "true",
+            blocked: get_parameter("blocked").parse::<bool>().ok(),
+            epic: get_parameter("epic").parse::<bool>().ok(),
+            parent: get_parameter("parent").parse::<i32>().ok(),
+        };
+        if filter.to_query().is_empty() {
+            None
+        } else {
+            Some(filter)
+        }
+    }
+
+    /// Non-empty fields as query string parameters, starting with `&`
+    pub fn to_query(&self) -> String {
+        let points_min = self.points_min.map(|p| p.to_string()).unwrap_or_default();
+        let points_max = self.points_max.map(|p| p.to_string()).unwrap_or_default();
+        let done = self.done.map(|d| d.to_string()).unwrap_or_default();
+        let milestone = self.milestone.map(|m| m.to_string()).unwrap_or_default();
+        let blocked = self.blocked.map(|b| b.to_string()).unwrap_or_default();
+        let epic = self.epic.map(|e| e.to_string()).unwrap_or_default();
+        let parent = self.parent.map(|p| p.to_string()).unwrap_or_default();
+        let flag = |set: bool| if set { "true" } else { "" }.to_owned();
+        [
+            ("name", &self.name),
+            ("place", &self.place),
+            ("members", &self.members),
+            ("deadline_start", &self.deadline_start),
+            ("deadline_end", &self.deadline_end),
+            ("points_min", &points_min),
+            ("points_max", &points_max),
+            ("tags", &self.tags),
+            ("query", &self.query),
+            ("done", &done),
+            ("milestone", &milestone),
+            ("overdue", &flag(self.overdue)),
+            ("no_deadline", &flag(self.no_deadline)),
+            ("unassigned", &flag(self.unassigned)),
+            ("open_subtasks", &flag(self.open_subtasks)),
+            ("members_any", &flag(self.members_any)),
+            ("tags_any", &flag(self.tags_any)),
+            ("blocked", &blocked),
+            ("epic", &epic),
+            ("parent", &parent),
+        ]
+        .into_iter()
+        .filter(|(_, value)| !value.is_empty())
+        .map(|(key, value)| format!("&{}={}", key, encode_uri_component(value)))
+        .collect()
     }
 }
 
+#[derive(Serialize)]
+pub struct SavedFilterData {
+    pub name: String,
+    pub board_type: String,
+    pub board_id: i32,
+    pub shared: bool,
+    pub filter: TaskFilter,
+}
+
+#[derive(Deserialize, Debug, Clone, PartialEq)]
+pub struct SavedFilter {
+    pub id: i32,
+    pub name: String,
+    pub user_id: i32,
+    pub shared: bool,
+    pub filter: TaskFilter,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Timer {
     pub id: i32,
@@ -143,6 +597,18 @@ pub struct Timer {
     pub status: String,
     pub time: u64,
     pub start: Option<u64>,
+    pub review: u64,
+    pub kind: String,
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    pub cycles: u64,
+    pub pomodoros: u64,
+    pub category: Option<i32>,
+    pub billable: i32,
+    pub client: Option<String>,
+    pub board_id: Option<i32>,
+    pub board_type: Option<String>,
 }
 
 impl Timer {
@@ -157,11 +623,185 @@ impl Timer {
             self.time
         }
     }
+
+    /// Phase of a running pomodoro, seconds left in it and work phases finished since the start
+    pub fn pomodoro_phase(&self) -> Option<(String, u64, u64)> {
+        if self.kind != "pomodoro" || self.status != "active" {
+            return None;
+        }
+        let mut phases = Vec::new();
+        for cycle in 1..=self.cycles {
+            phases.push(("work", self.work));
+            if cycle == self.cycles {
+                phases.push(("long break", self.long_break));
+            } else {
+                phases.push(("short break", self.short_break));
+            }
+        }
+        let round: u64 = phases.iter().map(|(_, length)| length).sum();
+        let elapsed = self.get_time() - self.time;
+        let mut completed = elapsed / round * self.cycles;
+        let mut left = elapsed % round;
+        for (phase, length) in phases {
+            if left < length {
+                return Some((phase.to_owned(), length - left, completed));
+            }
+            left -= length;
+            if phase == "work" {
+                completed += 1;
+            }
+        }
+        None
+    }
 }
 
 #[derive(Serialize)]
 pub struct TimerData {
     pub name: String,
+    pub pomodoro: Option<PomodoroConfig>,
+}
+
+#[derive(Serialize)]
+pub struct PomodoroConfig {
+    pub work: u64,
+    pub short_break: u64,
+    pub long_break: u64,
+    pub cycles: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimerSettings {
+    pub idle_limit: u64,
+}
+
+#[derive(Serialize)]
+pub struct TimerReview {
+    pub session: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimerCategory {
+    pub id: i32,
+    pub name: String,
+    pub color: String,
+    pub user_id: Option<i32>,
+    pub team_id: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct CategoryData {
+    pub name: String,
+    pub color: String,
+    pub team: Option<i32>,
+}
+
+#[derive(Serialize)]
+pub struct CategoryAssignment {
+    pub category: Option<i32>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct CategoryReport {
+    pub category: Option<i32>,
+    pub name: String,
+    pub color: String,
+    pub time: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimerGoal {
+    pub id: i32,
+    pub user_id: i32,
+    pub period: String,
+    pub category: Option<i32>,
+    pub target: u64,
+}
+
+#[derive(Serialize)]
+pub struct GoalData {
+    pub period: String,
+    pub category: Option<i32>,
+    pub target: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct GoalProgress {
+    pub goal: TimerGoal,
+    pub tracked: u64,
+    pub streak: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct TimerSession {
+    pub id: i32,
+    pub timer_id: i32,
+    pub user_id: i32,
+    pub category: Option<i32>,
+    pub start: u64,
+    pub duration: u64,
+    pub billable: i32,
+}
+
+#[derive(Serialize)]
+pub struct TimerBilling {
+    pub billable: bool,
+    pub client: Option<String>,
+    pub board_id: Option<i32>,
+    pub board_type: Option<String>,
+}
+
+#[derive(Serialize)]
+pub struct SessionBilling {
+    pub billable: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillingRate {
+    pub id: i32,
+    pub scope: String,
+    pub scope_id: i32,
+    pub rate: u64,
+    pub currency: String,
+    pub rounding: u64,
+    pub rounding_mode: String,
+}
+
+#[derive(Serialize)]
+pub struct RateData {
+    pub scope: String,
+    pub scope_id: i32,
+    pub rate: u64,
+    pub currency: String,
+    pub rounding: u64,
+    pub rounding_mode: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillingLine {
+    pub session: i32,
+    pub start: u64,
+    pub timer: String,
+    pub client: String,
+    pub duration: u64,
+    pub billed: u64,
+    pub rate: u64,
+    pub currency: String,
+    pub amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ClientTotal {
+    pub client: String,
+    pub currency: String,
+    pub duration: u64,
+    pub billed: u64,
+    pub amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct BillingSummary {
+    pub lines: Vec<BillingLine>,
+    pub clients: Vec<ClientTotal>,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Properties)]
@@ -191,4 +831,6 @@ pub struct Log {
     pub subtasks: String,
     pub points: i32,
     pub tags: String,
+    #[serde(default)]
+    pub changes: Option<String>, // JSON of the fields a partial update changed
 }
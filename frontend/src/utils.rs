@@ -3,6 +3,7 @@ use std::num::ParseIntError;
 use gloo_net::Error;
 use gloo_storage::errors::StorageError;
 use wasm_bindgen::prelude::*;
+use wasm_bindgen_futures::js_sys::Promise;
 
 #[wasm_bindgen(
     inline_js = "export function get_value(input) { return document.getElementById(input).value; }"
@@ -34,7 +35,7 @@ pub fn map_result(result: Result<i32, ParseIntError>) -> Option<i32> {
         var url_string = window.location.href;
         var url = new URL(url_string);
         var par = url.searchParams.get(input);
-        return par;
+        return par === null ? "" : par;
      }")]
 
 extern "C" {
@@ -90,6 +91,16 @@ extern "C" {
     pub fn set_checked(input: &str) -> bool;
 }
 
+#[wasm_bindgen(inline_js = "export function set_unchecked(input) { 
+    var el = document.getElementById(input);
+    el.checked = false;
+    return true;
+ }")]
+
+extern "C" {
+    pub fn set_unchecked(input: &str) -> bool;
+}
+
 #[wasm_bindgen(inline_js = "export function is_checked(input) { 
     var el = document.getElementById(input);
     if (el.checked) {
@@ -120,3 +131,168 @@ extern "C" {
 extern "C" {
     pub fn get_backend() -> String;
 }
+
+#[wasm_bindgen(inline_js = "export function notify(input) { 
+    if (!(\"Notification\" in window)) {
+        return false;
+    }
+    if (Notification.permission === \"granted\") {
+        new Notification(input);
+        return true;
+    }
+    if (Notification.permission !== \"denied\") {
+        Notification.requestPermission();
+    }
+    return false;
+ }")]
+
+extern "C" {
+    pub fn notify(input: &str) -> bool;
+}
+
+#[wasm_bindgen(inline_js = "export function download(url, token, filename) { 
+    fetch(url, { headers: { Authorization: token } })
+        .then(response => response.blob())
+        .then(blob => {
+            const link = document.createElement(\"a\");
+            link.href = URL.createObjectURL(blob);
+            link.download = filename;
+            link.click();
+            URL.revokeObjectURL(link.href);
+        });
+    return true;
+ }")]
+
+extern "C" {
+    pub fn download(url: &str, token: &str, filename: &str) -> bool;
+}
+
+#[wasm_bindgen(inline_js = "export function upload_file(url, token, input) { 
+    var files = document.getElementById(input).files;
+    if (files.length === 0) {
+        return Promise.resolve(0);
+    }
+    var form = new FormData();
+    form.append(\"file\", files[0]);
+    return fetch(url, { method: \"POST\", headers: { Authorization: token }, body: form })
+        .then(response => response.status, () => 0);
+ }")]
+
+extern "C" {
+    /// Sends the file chosen in the input as a multipart form, resolves to the status of
+    /// the response or 0 when nothing was sent
+    pub fn upload_file(url: &str, token: &str, input: &str) -> Promise;
+}
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_name = encodeURIComponent)]
+    pub fn encode_uri_component(input: &str) -> String;
+}
+
+#[wasm_bindgen(inline_js = "export function get_selected(input) { 
+    var el = document.getElementById(input);
+    return Array.from(el.selectedOptions).map(function (option) { return option.value; }).join(',');
+ }")]
+
+extern "C" {
+    /// Values of the options selected in a multiple select, separated by commas
+    pub fn get_selected(input: &str) -> String;
+}
+
+#[wasm_bindgen(inline_js = "export function get_timestamp(input) { 
+    const value = Date.parse(document.getElementById(input).value);
+    return isNaN(value) ? -1 : value / 1000;
+ }")]
+
+extern "C" {
+    pub fn get_timestamp(input: &str) -> f64;
+}
+
+#[wasm_bindgen(inline_js = "export function format_date(timestamp) { 
+    return new Date(timestamp * 1000).toISOString().slice(0, 10);
+ }")]
+
+extern "C" {
+    pub fn format_date(timestamp: f64) -> String;
+}
+
+#[wasm_bindgen(inline_js = "export function day_number(date) { 
+    return Math.round(Date.parse(date) / 86400000);
+ }")]
+
+extern "C" {
+    pub fn day_number(date: &str) -> f64;
+}
+
+#[wasm_bindgen(inline_js = "export function replace_url(url) { 
+    window.history.replaceState(null, '', url);
+ }")]
+
+extern "C" {
+    pub fn replace_url(url: &str);
+}
+
+#[wasm_bindgen(inline_js = "export function scrolled_to_bottom(input) { 
+    var el = document.getElementById(input);
+    return el.scrollTop + el.clientHeight >= el.scrollHeight - 50;
+ }")]
+
+extern "C" {
+    pub fn scrolled_to_bottom(input: &str) -> bool;
+}
+
+#[wasm_bindgen(
+    inline_js = "export function subscribe_events(url, on_event, on_closed) { 
+    var source = new EventSource(url);
+    source.onmessage = function (event) { on_event(event.data); };
+    source.onerror = function () {
+        // The browser reconnects by itself unless the server refused the stream
+        if (source.readyState === EventSource.CLOSED) {
+            on_closed();
+        }
+    };
+    return source;
+ }
+ export function close_events(source) { 
+    source.close();
+ }"
+)]
+
+extern "C" {
+    fn subscribe_events(
+        url: &str,
+        on_event: &Closure<dyn Fn(String)>,
+        on_closed: &Closure<dyn Fn()>,
+    ) -> JsValue;
+    fn close_events(source: &JsValue);
+}
+
+/// Server-sent events of the url, the stream is closed when this is dropped
+pub struct EventSubscription {
+    source: JsValue,
+    _on_event: Closure<dyn Fn(String)>,
+    _on_closed: Closure<dyn Fn()>,
+}
+
+impl EventSubscription {
+    pub fn new(
+        url: &str,
+        on_event: impl Fn(String) + 'static,
+        on_closed: impl Fn() + 'static,
+    ) -> Self {
+        let on_event = Closure::wrap(Box::new(on_event) as Box<dyn Fn(String)>);
+        let on_closed = Closure::wrap(Box::new(on_closed) as Box<dyn Fn()>);
+        Self {
+            source: subscribe_events(url, &on_event, &on_closed),
+            _on_event: on_event,
+            _on_closed: on_closed,
+        }
+    }
+}
+
+impl Drop for EventSubscription {
+    fn drop(&mut self) {
+        close_events(&self.source);
+    }
+}